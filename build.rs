@@ -3,10 +3,11 @@ extern crate sqlite;
 
 #[path = "runners/add_runners.rs"]
 mod add_runners;
-use add_runners::{
-    Runners,
-    Shell::{Bash, Nushell, Powershell, Zsh},
-};
+#[path = "runners/shells.rs"]
+mod shells;
+use add_runners::Runners;
+use shells::Shell;
+#[allow(dead_code)]
 #[path = "src/metadata.rs"]
 mod metadata;
 use metadata::Environment;
@@ -21,10 +22,11 @@ fn main() {
 
     println!("Building Bunnyhop version {}", env!("CARGO_PKG_VERSION"));
     for script in [
-        "src/add_runners.rs",
-        "src/runners/runner.ps1",
-        "src/runners/runner.sh",
-        "src/runners/runner.nu",
+        "add_runners.rs",
+        "shells.rs",
+        "scripts/runner.ps1",
+        "scripts/runner.sh",
+        "scripts/runner.nu",
     ]
     .iter()
     {
@@ -33,6 +35,7 @@ fn main() {
     for env_var in [
         "BHOP_ZSH_CONFIG_DIR",
         "BHOP_BASH_CONFIG_DIR",
+        "BHOP_FISH_CONFIG_DIR",
         "BHOP_NUSHELL_CONFIG_DIR",
         "BHOP_POWERSHELL_CONFIG_DIR",
     ]
@@ -41,9 +44,8 @@ fn main() {
         println!("cargo:rerun-if-env-changed={}", env_var);
     }
 
-    // Any new shells added in the future must be added in the following vector to be properly
+    // Any new shells added in the future must be added to `Shell::supported` to be properly
     // configured with their respective runner script when `Bunnyhop` is built.
-    let supported_shells = vec![Zsh, Bash, Nushell, Powershell];
-    let runners = Runners::new(supported_shells, script_dir);
+    let runners = Runners::new(Shell::supported(), script_dir);
     runners.add_runners();
 }
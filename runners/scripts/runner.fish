@@ -1,10 +1,17 @@
-function hp
-    set output (__SHELL_CALLABLE__ -c "__HOPPERCMD__ $argv")
-    if not string match -q "*__CMD_SEPARATOR__*" $output
+function __FUNCTION_ALIAS__
+    # The output is captured, so bhop is told whether it ends up on a terminal.
+    set -l tty (isatty stdout; and echo 1)
+    set output (env BHOP_TERMINAL=$tty __HOPPERCMD__ $argv | string collect)
+    if not string match -q "*__CMD_SEPARATOR__*" -- $output
         echo $output
     else
-        set cmds (string split "__CMD_SEPARATOR__" $output)
+        set cmds (string split -m 1 "__CMD_SEPARATOR__" -- $output)
         cd $cmds[1]
         __SHELL_CALLABLE__ -c "$cmds[2]"
     end
 end
+
+# Record every directory change into the bhop history, not just jumps made with __FUNCTION_ALIAS__.
+function __bhop_record_pwd --on-variable PWD
+    __HOPPERCMD__ record "$PWD" >/dev/null 2>&1
+end
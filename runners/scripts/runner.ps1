@@ -1,5 +1,8 @@
-function __FUNCTION_ALIAS__($cmd, $p1, $p2, $p3, $p4) {
-    $fullCmd = Invoke-Expression "__HOPPERCMD__ $cmd $p1 $p2 $p3 $p4"
+function __FUNCTION_ALIAS__ {
+    # The output is captured, so bhop is told whether it ends up on a terminal.
+    $env:BHOP_TERMINAL = if ([Console]::IsOutputRedirected) { "" } else { "1" }
+    $fullCmd = (& __HOPPERCMD__ @args | Out-String).Trim()
+    Remove-Item Env:BHOP_TERMINAL
     if ($fullCmd.Contains("__CMD_SEPARATOR__")) {
         $to_move, $to_exec = $fullCmd.trim().Split("__CMD_SEPARATOR__", 2)
         cd $to_move
@@ -9,3 +12,16 @@ function __FUNCTION_ALIAS__($cmd, $p1, $p2, $p3, $p4) {
     }
 }
 
+
+# Record every directory change into the bhop history, not just jumps made with __FUNCTION_ALIAS__.
+if (-not $global:__BhopPrompt) {
+    $global:__BhopPrompt = $function:prompt
+    $global:__BhopLastPwd = $PWD.Path
+    function global:prompt {
+        if ($PWD.Path -ne $global:__BhopLastPwd) {
+            $global:__BhopLastPwd = $PWD.Path
+            __HOPPERCMD__ record $PWD.Path *> $null
+        }
+        & $global:__BhopPrompt
+    }
+}
@@ -1,11 +1,27 @@
 __FUNCTION_ALIAS__() {
-    out=$(__SHELL_CALLABLE__ -c "__HOPPERCMD__ ${1} ${2} ${3} ${4}")
+    # The output is captured, so bhop is told whether it ends up on a terminal.
+    local tty=
+    [ -t 1 ] && tty=1
+    out=$(BHOP_TERMINAL=$tty __HOPPERCMD__ "$@")
     if [[ "$out" != *"__CMD_SEPARATOR__"* ]]; then
         echo $out
         return
     fi
-    IFS="__CMD_SEPARATOR__" read -ra arr <<< "$out"
-    cd ${arr[0]}
-    __SHELL_CALLABLE__ -c "${arr[1]}"
+    # Only the first separator splits, the command itself may contain more of them.
+    cd "${out%%"__CMD_SEPARATOR__"*}"
+    __SHELL_CALLABLE__ -c "${out#*"__CMD_SEPARATOR__"}"
 }
 
+
+# Record every directory change into the bhop history, not just jumps made with __FUNCTION_ALIAS__.
+__bhop_record_pwd() {
+    if [ "$PWD" != "$__BHOP_LAST_PWD" ]; then
+        __BHOP_LAST_PWD="$PWD"
+        __HOPPERCMD__ record "$PWD" >/dev/null 2>&1
+    fi
+}
+__BHOP_LAST_PWD="$PWD"
+case ";${PROMPT_COMMAND:-};" in
+    *";__bhop_record_pwd;"*) ;;
+    *) PROMPT_COMMAND="__bhop_record_pwd${PROMPT_COMMAND:+;$PROMPT_COMMAND}" ;;
+esac
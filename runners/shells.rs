@@ -0,0 +1,224 @@
+// The shells bhop installs runners for and where their configuration files are.  This is shared
+// by the build script, which adds the runners to those files, and `hp doctor`, which checks that
+// they're still loaded.
+use dirs::home_dir;
+use std::{env::var, path::PathBuf, process::Command};
+
+pub enum Shell {
+    Zsh,
+    Bash,
+    Fish,
+    Nushell,
+    Powershell,
+}
+
+impl Shell {
+    pub fn supported() -> Vec<Shell> {
+        vec![
+            Shell::Zsh,
+            Shell::Bash,
+            Shell::Fish,
+            Shell::Nushell,
+            Shell::Powershell,
+        ]
+    }
+}
+
+impl Shell {
+    // These are the foundational methods that need to be implemented for each new shell runner
+    // support is being added for.
+    pub fn call_cmd(&self) -> &str {
+        // This method returns the command used to call shell commands in this from any other shell.
+        match self {
+            Shell::Zsh => "zsh",
+            Shell::Bash => "sh",
+            Shell::Fish => "fish",
+            Shell::Nushell => "nu",
+            Shell::Powershell => "pwsh",
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        // This method returns the proper name of the shell.
+        match self {
+            Shell::Zsh => "ZSH",
+            Shell::Bash => "BASH",
+            Shell::Fish => "FISH",
+            Shell::Nushell => "NUSHELL",
+            Shell::Powershell => "POWERSHELL",
+        }
+    }
+
+    fn env_var(&self) -> &str {
+        // This method returns the environment variable that can be set to specify a non-standard
+        // shell configuration file location.
+        match self {
+            Shell::Zsh => "BHOP_ZSH_CONFIG_DIR",
+            Shell::Bash => "BHOP_BASH_CONFIG_DIR",
+            Shell::Fish => "BHOP_FISH_CONFIG_DIR",
+            Shell::Nushell => "BHOP_NUSHELL_CONFIG_DIR",
+            Shell::Powershell => "BHOP_POWERSHELL_CONFIG_DIR",
+        }
+    }
+
+    pub fn ext(&self) -> &str {
+        // This method returns the file format extension of saved shell scripts for each shell.
+        match self {
+            Shell::Nushell => "nu",
+            Shell::Powershell => "ps1",
+            Shell::Zsh => "zsh",
+            Shell::Fish => "fish",
+            _ => "sh",
+        }
+    }
+
+    fn config_dir(&self) -> &str {
+        // This method returns the the configuration file or the directory the configuration file
+        // is in when called as variables within their respective shells.
+        //
+        // For example, in Zsh if
+        // you call `zsh -c "echo $ZDOTDIR"`, it will return the current configuration directory
+        // that .zshrc is in or it will return an empty string.
+        //
+        // Similarly, `nu -c "echo $nu.env-path"` and `powershell -c "echo $profile"` will both
+        // return the path to their direct configuration files that need to be updated, while
+        // `fish -c "echo $__fish_config_dir"` returns the directory config.fish is in.
+        match self {
+            Shell::Zsh => "ZDOTDIR",
+            Shell::Bash => "HOME",
+            Shell::Fish => "__fish_config_dir",
+            Shell::Nushell => "nu.env-path",
+            Shell::Powershell => "profile",
+        }
+    }
+
+    fn config_name(&self) -> &str {
+        // This method returns the default name of the configuration file for each shell that will
+        // need to be updated.  This is used to parse the shell configuration file when the above
+        // used commands fail or return nothing.
+        match self {
+            Shell::Zsh => ".zshrc",
+            Shell::Bash => ".bashrc",
+            Shell::Fish => "config.fish",
+            Shell::Nushell => "env.nu",
+            Shell::Powershell => "Microsoft.Powershell_profile.ps1",
+        }
+    }
+
+    fn find_default(&self) -> Option<PathBuf> {
+        // This method points to the specific implementations for determining the shell
+        // configuration file for each shell.
+        //
+        // Zsh and Bash are relatively simple because they are
+        // in the same location on almost every operating system.
+        //
+        // Nushell's default location varies between operating systems
+        // and Powershell's varies not only between operating
+        // systems but between different configurations of Windows itself.
+        //
+        // If the configuration file for any new shell being added doesn't default to a user's home
+        // directory, a new method will have to be implemented and to derive the default
+        // shell configuration path and it will have to be pointed to in this method.
+        match self {
+            Shell::Fish => self.fish_default(),
+            Shell::Nushell => self.nushell_default(),
+            Shell::Powershell => self.powershell_default(),
+            _ => self.home_default(),
+        }
+    }
+}
+
+impl Shell {
+    // These implementations are collections of methods for determining the default path for each
+    // shell's configuration file.
+    fn home_default(&self) -> Option<PathBuf> {
+        home_dir().map(|home| home.join(self.config_name()))
+    }
+
+    fn fish_default(&self) -> Option<PathBuf> {
+        home_dir().map(|home| home.join(".config").join("fish").join(self.config_name()))
+    }
+
+    fn nushell_default(&self) -> Option<PathBuf> {
+        if cfg!(windows) {
+            home_dir().map(|home| {
+                home.join("AppData")
+                    .join("Roaming")
+                    .join("nushell")
+                    .join("env.nu")
+            })
+        } else {
+            home_dir().map(|home| home.join("config").join("nushell").join("env.nu"))
+        }
+    }
+
+    fn powershell_default(&self) -> Option<PathBuf> {
+        if cfg!(windows) {
+            match home_dir() {
+                Some(home) => {
+                    if home.join("OneDrive").exists() {
+                        Some(
+                            home.join("OneDrive")
+                                .join("Documents")
+                                .join("WindowsPowerShell")
+                                .join(self.config_name()),
+                        )
+                    } else {
+                        Some(
+                            home.join("Documents")
+                                .join("WindowsPowerShell")
+                                .join("Microsoft.PowerShell_profile.ps1"),
+                        )
+                    }
+                }
+                None => None,
+            }
+        } else {
+            home_dir().map(|home| {
+                home.join(".config")
+                    .join("powershell")
+                    .join("Microsoft.Powershell_profile.ps1")
+            })
+        }
+    }
+}
+
+impl Shell {
+    // These methods combine all other methods together to determine (where possible) the best
+    // guess shell configuration path if a specific configuration path isn't specified through
+    // environment variables.
+    pub fn derive_config_path(&self) -> Option<PathBuf> {
+        let from_env = var(self.env_var());
+        match from_env {
+            Ok(p) => Some(PathBuf::from(&p)),
+            Err(_) => match Command::new(self.call_cmd())
+                .arg("-c")
+                .arg(format!("echo ${}", &self.config_dir()))
+                .output()
+            {
+                Ok(out) => match String::from_utf8(out.stdout) {
+                    Ok(p) => {
+                        let derived = p.trim();
+                        if !derived.is_empty() {
+                            // the below is slightly more convoluted that I think it should be, but
+                            // it's this way because calling `bash -c "echo $HOME"` and `sh -c "echo $HOME"`
+                            // on Windows sucks and returns path strings unparsable by PathBuf
+                            // depending on which implementation you're using (the two that I
+                            // primarily use, Ubuntu WSL and Git-Bash both return completely
+                            // different bad paths).
+                            let derived_path = PathBuf::from(&derived);
+                            if derived_path.is_file() {
+                                return Some(derived_path);
+                            } else if derived_path.is_dir() {
+                                return Some(derived_path.as_path().join(self.config_name()));
+                            }
+                        }
+                        self.find_default()
+                    }
+                    Err(_) => self.find_default(),
+                },
+                Err(_) => self.find_default(),
+            },
+        }
+    }
+}
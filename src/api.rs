@@ -0,0 +1,266 @@
+// The library side of bhop.  Everything here hands back plain values describing what was found or
+// what should happen, rather than printing or building the `dir|cmd` strings the shell runners
+// expect, which is left to `args.rs`.  Editor plugins and other tools linking bhop as a crate
+// should stick to these.
+use crate::groups::BhopGroup;
+use crate::logging::trace;
+use crate::search::Matcher;
+use crate::{sanitize, split_position, Hopper, ListOptions};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Dir,
+    File,
+    Missing,
+}
+
+impl Kind {
+    pub fn of<T: AsRef<Path>>(path: T) -> Self {
+        let path = path.as_ref();
+        if path.is_dir() {
+            Kind::Dir
+        } else if path.exists() {
+            Kind::File
+        } else {
+            Kind::Missing
+        }
+    }
+}
+
+// Where a reference was resolved from, in the order they're tried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    Project,
+    Shortcut,
+    Local,
+    History,
+    Repo,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Resolution {
+    pub path: PathBuf,
+    pub kind: Kind,
+    pub source: Source,
+    // The frecency score, for matches from the history.
+    pub score: Option<f64>,
+    // The name of the stored shortcut, for matches from the shortcuts.
+    pub shortcut: Option<String>,
+}
+
+impl Resolution {
+    fn new(path: PathBuf, source: Source, score: Option<f64>) -> Self {
+        Resolution {
+            kind: Kind::of(&path),
+            path,
+            source,
+            score,
+            shortcut: None,
+        }
+    }
+
+    fn shortcut((name, path): (String, PathBuf)) -> Self {
+        Resolution {
+            shortcut: Some(name),
+            ..Resolution::new(path, Source::Shortcut, None)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    Cd(PathBuf),
+    // `cmd` is the editor command with the file filled in, and `dir` is only set when
+    // `always_jump` asks for a move into the file's directory as well.
+    Edit { dir: Option<PathBuf>, cmd: String },
+    Run { dir: PathBuf, cmd: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub name: String,
+    pub location: String,
+    pub source: Source,
+    pub tags: Vec<String>,
+    pub description: Option<String>,
+    // How often and when (as a unix timestamp) the entry was last used.
+    pub usage: i64,
+    pub last_used: Option<i64>,
+    // Positions of the characters in the name matched by a fuzzy search, for highlighting.
+    pub matched: Vec<usize>,
+}
+
+fn traced(name: &str, step: &str, found: Option<Resolution>) -> Option<Resolution> {
+    match &found {
+        Some(found) => trace!(1, "`{}` {} match: {}", name, step, found.path.display()),
+        None => trace!(1, "`{}` has no {} match", name, step),
+    }
+    found
+}
+
+impl Hopper {
+    // Where `name` points, without touching anything.  A shortcut whose target has gone missing
+    // still resolves, with a `Missing` kind, and it's up to the caller to decide what to do with
+    // it (`action` tries to relocate it).  Nothing is recorded as used either.
+    pub fn resolve(&self, name: &str) -> Option<Resolution> {
+        // `@tag/name` only looks through the shortcuts carrying that tag.
+        if let Some((tag, rest)) = name.strip_prefix('@').and_then(|n| n.split_once('/')) {
+            let found = self
+                .find_tagged_shortcut(tag, rest)
+                .map(Resolution::shortcut);
+            return traced(name, "tagged shortcut", found);
+        }
+        if let Some(path) = self.find_in_project(name) {
+            return traced(
+                name,
+                "project",
+                Some(Resolution::new(path, Source::Project, None)),
+            );
+        }
+        let shortcut = self.find_shortcut(name).map(Resolution::shortcut);
+        let shortcut = traced(name, "shortcut", shortcut);
+        let history = self.find_history(name).map(|(location, score)| {
+            Resolution::new(location.into(), Source::History, Some(score))
+        });
+        let history = traced(name, "history", history);
+        let local = self
+            .find_local(name)
+            .map(|path| Resolution::new(path, Source::Local, None));
+        let local = traced(name, "local", local);
+        let repo = self
+            .find_repo(name)
+            .map(|path| Resolution::new(path, Source::Repo, None));
+        let repo = traced(name, "repo", repo);
+
+        let mut order = Vec::new();
+        if self.config.prioritize_shortcuts {
+            order.push(shortcut);
+            order.push(local);
+        } else {
+            order.push(local);
+            order.push(shortcut);
+        }
+        order.push(history);
+        order.push(repo);
+
+        let resolution = order.into_iter().flatten().next()?;
+        trace!(
+            1,
+            "`{}` resolved to {} from {:?} (prioritize_shortcuts = {})",
+            name,
+            resolution.path.display(),
+            resolution.source,
+            self.config.prioritize_shortcuts
+        );
+        Some(resolution)
+    }
+
+    // What hopping to `name` should do.  Directories are changed into unless `edit_dir` is set,
+    // anything else is opened in its editor.  Shortcuts whose target has gone missing get a
+    // chance to be found again, and the jump is counted as a use of the shortcut.  Both opened
+    // files and directories jumped to are recorded in the history, with the runners' hooks
+    // leaving out the directory change that follows.
+    pub fn action(&mut self, name: &str, edit_dir: bool) -> anyhow::Result<Action> {
+        let (resolution, position) = match self.resolve(name) {
+            Some(resolution) => (Some(resolution), None),
+            None => match split_position(name) {
+                Some((base, position)) => {
+                    trace!(1, "retrying `{}` without its line and column", base);
+                    (self.resolve(&base), Some(position))
+                }
+                None => (None, None),
+            },
+        };
+        let resolution = match resolution {
+            Some(resolution) => resolution,
+            None => return Err(anyhow::Error::msg("No matching options found.")),
+        };
+        let mut path = resolution.path;
+        if let Some(shortcut) = &resolution.shortcut {
+            if resolution.kind == Kind::Missing {
+                trace!(1, "`{}` points at missing {}", shortcut, path.display());
+                if let Some(found) = self.relocate(shortcut, &path)? {
+                    path = found;
+                }
+            }
+            self.record_shortcut_use(shortcut);
+        }
+        self.add_history(&path)?;
+        if path.is_dir() && !edit_dir {
+            return Ok(Action::Cd(sanitize(path)?.into()));
+        }
+        let dir = if self.config.always_jump {
+            Some(sanitize(path.parent().unwrap_or(&path))?.into())
+        } else {
+            None
+        };
+        let cmd = self.map_editor(sanitize(&path)?, position.as_ref())?;
+        Ok(Action::Edit { dir, cmd })
+    }
+
+    // Runs `subgroup` (or "default") from the .bhop file of `group`.  Layouts have their
+    // multiplexer session set up before the action to attach to it is returned.
+    pub fn group_action(&mut self, group: &str, subgroup: Option<&str>) -> anyhow::Result<Action> {
+        match self.load_group(group, subgroup)? {
+            Some((path, group_path, options)) => {
+                self.options_action(group, &path, &group_path, &options)
+            }
+            None => Err(anyhow::Error::msg("Unable to find group or subgroup.")),
+        }
+    }
+
+    // Shortcuts and then history entries whose name or location (or description and tags, for
+    // shortcuts) match `filter` the way `options.mode` says.  History is left out when filtering
+    // by tag.
+    pub fn search(
+        &self,
+        filter: Option<&str>,
+        options: &ListOptions,
+    ) -> anyhow::Result<Vec<Entry>> {
+        let matcher = Matcher::new(
+            filter,
+            options.mode,
+            &self.config.search_match_prefix,
+            &self.config.search_match_suffix,
+        )?;
+        let mut entries = self.search_shortcuts(&matcher, options)?;
+        if options.tag.is_none() {
+            entries.extend(self.search_history(&matcher)?);
+        }
+        Ok(entries)
+    }
+
+    pub(crate) fn load_group(
+        &mut self,
+        group: &str,
+        subgroup: Option<&str>,
+    ) -> anyhow::Result<Option<(PathBuf, PathBuf, BhopGroup)>> {
+        let path = self.grab(group.to_string()).unwrap_or(PathBuf::from("."));
+        let group_path = path.join(self.project_configs());
+        let options = BhopGroup::from(subgroup.unwrap_or("default"), &group_path)?;
+        Ok(options.map(|options| (path, group_path, options)))
+    }
+
+    pub(crate) fn options_action(
+        &mut self,
+        group: &str,
+        path: &Path,
+        group_path: &Path,
+        options: &BhopGroup,
+    ) -> anyhow::Result<Action> {
+        if let Some(layout) = &options.layout {
+            return self.layout_action(path, group_path, layout);
+        }
+        match self.group_cmd(options)? {
+            Some(cmd) => Ok(Action::Run {
+                dir: sanitize(path)?.into(),
+                cmd,
+            }),
+            None => match options.files {
+                Some(_) => self.action(group, true),
+                None => Err(anyhow::Error::msg("No matching options found.")),
+            },
+        }
+    }
+}
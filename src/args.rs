@@ -1,7 +1,12 @@
 // Enum used to parse input arguments.  Ended up rolling my own arg parser instead of using an
 // existing crate because I wanted `hp` commands to be more natural language-like and use dynamic
-use crate::Hopper;
-use colored::Colorize;
+use crate::api::Action;
+use crate::layouts::render;
+use crate::logging::{self, trace};
+use crate::search::SearchMode;
+use crate::theme::{ColorMode, Style};
+use crate::views::{ListView, SortBy};
+use crate::{Hopper, ListOptions};
 use std::env;
 
 #[derive(Debug, Eq, PartialEq)]
@@ -12,20 +17,89 @@ pub enum Request {
     Find(String),
     Group(String, Option<String>),
     Passthrough(String),
-    Search(Option<String>),
+    Search(Option<String>, ListOptions),
+    Tag(String, Vec<String>),
+    Untag(String, Vec<String>),
+    Describe(String, String),
+    Rename(String, String),
+    Retarget(String, String),
+    MoveTree(String, String),
+    EditShortcuts,
     Notify(String),
     Help,
+    Version,
     Configure,
+    ConfigGet(String),
+    ConfigSet(String, String),
+    ConfigList(bool),
     Locate,
+    WhichEditor(String),
+    Undo,
+    Doctor(bool),
+    Repos(bool),
+    Record(String),
+    History(Option<String>),
+    Stats(i64),
+    PruneHistory,
+    Completions(String),
+    Complete(Vec<String>),
+    ForgetHistory(String),
     Skip,
+    DryRun(Box<Request>),
+    Verbose(u8, Box<Request>),
+    Color(ColorMode, Box<Request>),
+    Confirmed(Box<Request>),
 }
 
 impl Request {
     pub fn parse() -> Self {
-        let args: Vec<String> = match env::var("BHOP_TEST_ARGS") {
-            Ok(val) => val.split(' ').map(|s| s.to_string()).collect(),
-            Err(_) => env::args().collect(),
-        };
+        match env::var("BHOP_TEST_ARGS") {
+            Ok(val) => Self::parse_from(val.split(' ')),
+            Err(_) => Self::parse_from(env::args()),
+        }
+    }
+
+    // Parses a full command line, starting with the program name the way `env::args` does.
+    pub fn parse_from<I, T>(args: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        let mut args: Vec<String> = args.into_iter().map(|arg| arg.into()).collect();
+        let dry_run = take_switch(&mut args, "--dry-run");
+        let confirmed = take_switch(&mut args, "--yes") | take_switch(&mut args, "-y");
+        let very_verbose = take_switch(&mut args, "-vv");
+        let verbose = take_switch(&mut args, "-v") | take_switch(&mut args, "--verbose");
+        let color = take_flag(&mut args, "--color").map(|mode| mode.parse::<ColorMode>());
+        let mut request = Self::parse_args(args);
+        if confirmed {
+            request = Request::Confirmed(Box::new(request));
+        }
+        if dry_run {
+            request = Request::DryRun(Box::new(request));
+        }
+        match color {
+            Some(Ok(mode)) => request = Request::Color(mode, Box::new(request)),
+            Some(Err(e)) => request = Request::Notify(e.to_string()),
+            None => {}
+        }
+        if very_verbose {
+            request = Request::Verbose(2, Box::new(request));
+        } else if verbose {
+            request = Request::Verbose(1, Box::new(request));
+        }
+        request
+    }
+
+    // How much should be logged while handling the request, from -v or -vv.
+    pub fn verbosity(&self) -> u8 {
+        match self {
+            Request::Verbose(level, _) => *level,
+            _ => 0,
+        }
+    }
+
+    fn parse_args(mut args: Vec<String>) -> Self {
         match args.get(1).map(|s| s.to_string()) {
             Some(cmd) => match cmd.as_str() {
                 "add" | "+" => {
@@ -54,37 +128,235 @@ impl Request {
                 "loc" | "locate" => Request::Locate,
                 "v" | "version" => Request::Passthrough("__bhop_version__".to_string()),
                 "h" | "help" => Request::Passthrough("__bhop_help__".to_string()),
-                "l" | "ls" | "list" | ".." => match args.get(2) {
-                    Some(name) => Request::Passthrough(format!("__bhop_list__ {}", name)),
-                    None => Request::Passthrough("__bhop_list__".to_string()),
+                "l" | "ls" | "list" | ".." => passed("__bhop_list__", &args[2..]),
+                "tag" | "untag" => match (args.get(2), args.len() > 3) {
+                    (Some(name), true) if cmd == "tag" => {
+                        Request::Tag(name.to_string(), args[3..].to_vec())
+                    }
+                    (Some(name), true) => Request::Untag(name.to_string(), args[3..].to_vec()),
+                    _ => Request::Notify("No shortcut and tags provided.".to_string()),
                 },
-                "c" | "cfg" | "configure" => Request::Configure,
-                "__bhop_version__" => {
-                    println!(
-                        "{} 🐇 {}{}",
-                        "Bhop".cyan().bold(),
-                        "v.".bold(),
-                        env!("CARGO_PKG_VERSION")
-                    );
-                    Request::Skip
+                "mv" | "rename" => match (args.get(2), args.get(3)) {
+                    (Some(old), Some(new)) => Request::Rename(old.to_string(), new.to_string()),
+                    _ => Request::Notify("No shortcut and new name provided.".to_string()),
+                },
+                "retarget" => match (args.get(2), args.get(3)) {
+                    (Some(name), Some(path)) => {
+                        Request::Retarget(name.to_string(), path.to_string())
+                    }
+                    _ => Request::Notify("No shortcut and new path provided.".to_string()),
+                },
+                "mv-tree" => match (args.get(2), args.get(3)) {
+                    (Some(old), Some(new)) => Request::MoveTree(old.to_string(), new.to_string()),
+                    _ => Request::Notify("No old and new path prefixes provided.".to_string()),
+                },
+                "undo" => Request::Undo,
+                "doctor" => Request::Doctor(take_switch(&mut args, "--fix")),
+                "repos" => match take_switch(&mut args, "--refresh") {
+                    true => Request::Passthrough("__bhop_repos__ --refresh".to_string()),
+                    false => Request::Passthrough("__bhop_repos__".to_string()),
+                },
+                "history" => match (args.get(2).map(|s| s.as_str()), args.get(3)) {
+                    (Some("prune"), _) => Request::PruneHistory,
+                    (Some("forget"), Some(pattern)) => Request::ForgetHistory(pattern.to_string()),
+                    (Some("forget"), None) => {
+                        Request::Notify("No history pattern to forget provided.".to_string())
+                    }
+                    _ => passed("__bhop_history__", &args[2..]),
+                },
+                "__bhop_history__" => Request::History(args.get(2).map(|s| s.to_string())),
+                "stats" => passed("__bhop_stats__", &args[2..]),
+                "__bhop_stats__" => {
+                    match take_flag(&mut args, "--days").map(|d| d.parse::<i64>()) {
+                        None => Request::Stats(30),
+                        Some(Ok(days)) => Request::Stats(days),
+                        Some(Err(_)) => {
+                            Request::Notify("--days needs a number of days.".to_string())
+                        }
+                    }
+                }
+                "completions" => match args.get(2) {
+                    Some(_) => passed("__bhop_completions__", &args[2..3]),
+                    None => Request::Notify(format!(
+                        "No shell provided, use one of: {}.",
+                        crate::completions::SHELLS.join(", ")
+                    )),
+                },
+                "__bhop_completions__" => {
+                    Request::Completions(args.get(2).cloned().unwrap_or_default())
                 }
+                "__complete" => Request::Complete(args[2..].to_vec()),
+                "record" => match args.get(2) {
+                    Some(path) => Request::Record(path.to_string()),
+                    None => Request::Notify("No path to record provided.".to_string()),
+                },
+                "__bhop_repos__" => Request::Repos(take_switch(&mut args, "--refresh")),
+                "edit-shortcuts" => Request::Passthrough("__bhop_edit_shortcuts__".to_string()),
+                "__bhop_edit_shortcuts__" => Request::EditShortcuts,
+                "desc" | "describe" => match args.get(2) {
+                    Some(name) => Request::Describe(name.to_string(), args[3..].join(" ")),
+                    None => Request::Notify("No shortcut to describe provided.".to_string()),
+                },
+                "c" | "cfg" | "configure" => Request::Configure,
+                "config" => match (args.get(2).map(|s| s.as_str()), args.get(3)) {
+                    (Some("get"), Some(key)) => Request::ConfigGet(key.to_string()),
+                    (Some("set"), Some(key)) if args.len() > 4 => {
+                        Request::ConfigSet(key.to_string(), args[4..].join(" "))
+                    }
+                    (Some("list"), _) => passed("__bhop_config__", &args[2..]),
+                    (Some("get" | "set"), _) => {
+                        Request::Notify("No setting or value provided.".to_string())
+                    }
+                    _ => Request::Notify(
+                        "Use one of: config get, config set, config list.".to_string(),
+                    ),
+                },
+                "__bhop_config__" => Request::ConfigList(take_switch(&mut args, "--effective")),
+                "which-editor" => match args.get(2) {
+                    Some(reference) => Request::WhichEditor(reference.to_string()),
+                    None => Request::Notify("No path to check provided.".to_string()),
+                },
+                "__bhop_version__" => Request::Version,
                 "__bhop_help__" => Request::Help,
-                "__bhop_list__" => Request::Search(args.get(2).map(|s| s.to_string())),
-                _ => Request::Use(cmd, args.get(2).map(|s| s.to_string())),
+                "__bhop_list__" => {
+                    let tag = take_flag(&mut args, "--tag");
+                    let view = match (
+                        take_switch(&mut args, "--tree"),
+                        take_switch(&mut args, "--by-tag"),
+                    ) {
+                        (true, true) => {
+                            return Request::Notify(
+                                "Use either --tree or --by-tag, not both.".to_string(),
+                            )
+                        }
+                        (true, false) => ListView::Tree,
+                        (false, true) => ListView::ByTag,
+                        (false, false) => ListView::Table,
+                    };
+                    let sort = match take_flag(&mut args, "--sort").map(|s| s.parse::<SortBy>()) {
+                        Some(Ok(sort)) => Some(sort),
+                        Some(Err(e)) => return Request::Notify(e.to_string()),
+                        None => None,
+                    };
+                    let mode = match (
+                        take_switch(&mut args, "--regex"),
+                        take_switch(&mut args, "--fuzzy"),
+                    ) {
+                        (true, true) => {
+                            return Request::Notify(
+                                "Use either --regex or --fuzzy, not both.".to_string(),
+                            )
+                        }
+                        (true, false) => SearchMode::Regex,
+                        (false, true) => SearchMode::Fuzzy,
+                        (false, false) => SearchMode::Glob,
+                    };
+                    Request::Search(
+                        args.get(2).map(|s| s.to_string()),
+                        ListOptions {
+                            tag,
+                            mode,
+                            view,
+                            sort,
+                        },
+                    )
+                }
+                _ => match cmd.strip_prefix('@').and_then(|c| c.strip_suffix('/')) {
+                    // `hp @tag/` on its own lists everything with that tag.
+                    Some(tag) if !tag.contains('/') => {
+                        passed("__bhop_list__", &["--tag".to_string(), tag.to_string()])
+                    }
+                    _ => Request::Use(cmd, args.get(2).map(|s| s.to_string())),
+                },
             },
             None => Request::Notify("No command provided.".to_string()),
         }
     }
 }
 
+// `cmd` handed back to the runners with `args`, which are quoted since the runner runs the line
+// through the shell.
+fn passed(cmd: &str, args: &[String]) -> Request {
+    match args.is_empty() {
+        true => Request::Passthrough(cmd.to_string()),
+        false => Request::Passthrough(format!("{} {}", cmd, render(args))),
+    }
+}
+
+// Commands handed back to the runners to run in place of this one (see `passthrough`) are given
+// `flag` as well, so e.g. `hp ls -v` still logs.
+fn pass_flag(request: Request, flag: &str) -> Request {
+    match request {
+        Request::Passthrough(cmd) => Request::Passthrough(format!("{} {}", cmd, flag)),
+        Request::Verbose(level, request) => {
+            Request::Verbose(level, Box::new(pass_flag(*request, flag)))
+        }
+        Request::Color(mode, request) => Request::Color(mode, Box::new(pass_flag(*request, flag))),
+        request => request,
+    }
+}
+
+// Removes a boolean flag from the arguments, returning whether it was present.
+fn take_switch(args: &mut Vec<String>, flag: &str) -> bool {
+    let present = args.iter().any(|a| a == flag);
+    args.retain(|a| a != flag);
+    present
+}
+
+// Removes a flag and its value from the arguments, accepting both `--flag value` and
+// `--flag=value`.
+fn take_flag(args: &mut Vec<String>, flag: &str) -> Option<String> {
+    let prefix = format!("{}=", flag);
+    let i = args
+        .iter()
+        .position(|a| a == flag || a.starts_with(&prefix))?;
+    let arg = args.remove(i);
+    match arg.strip_prefix(&prefix) {
+        Some(value) => Some(value.to_string()),
+        None if i < args.len() => Some(args.remove(i)),
+        None => None,
+    }
+}
+
 impl Hopper {
+    // Turns an action into the `dir|cmd` pair the shell runners act on.
+    pub(crate) fn render(&self, action: Action) -> String {
+        let separator = self.separator();
+        match action {
+            Action::Cd(dir) => format!("{}{}", dir.display(), separator),
+            Action::Edit { dir, cmd } => match dir {
+                Some(dir) => format!("{}{}{}", dir.display(), separator, cmd),
+                None => format!(".{}{}", separator, cmd),
+            },
+            Action::Run { dir, cmd } => format!("{}{}{}", dir.display(), separator, cmd),
+        }
+    }
+
     pub fn execute(&mut self, request: Request) -> anyhow::Result<()> {
-        let output = match request {
+        trace!(1, "handling {:?}", request);
+        match self.respond(request) {
+            Ok(msg) => {
+                if !msg.is_empty() {
+                    print!("{}", msg)
+                }
+            }
+            Err(err) => {
+                trace!(1, "failed: {:?}", err);
+                print!("{}", err)
+            }
+        };
+        trace!(1, "done in {}ms", logging::elapsed());
+        Ok(())
+    }
+
+    // The output of a request, which `execute` prints for the runners to act on.
+    pub fn respond(&mut self, request: Request) -> anyhow::Result<String> {
+        match request {
             Request::Add(reference, name) => {
                 self.add_shortcut(reference, name).map(|_| "".to_string())
             }
-            Request::Remove(reference) => self.remove_shortcut(reference).map(|_| "".to_string()),
-            Request::Group(reference, subgroup) => self.use_group(reference, subgroup),
+            Request::Remove(reference) => self.remove_shortcut(reference, false, false),
+            Request::Group(reference, subgroup) => self.use_group(reference, subgroup, false),
             Request::Find(reference) => {
                 let path = self.grab(reference);
                 match path {
@@ -94,43 +366,116 @@ impl Hopper {
                     None => Err(anyhow::anyhow!("Unable to grab reference.")),
                 }
             }
-            Request::Use(reference, name) => match name {
-                Some(n) => {
+            Request::Use(reference, name) => {
+                if let Some(n) = name {
                     self.add_shortcut(&reference, Some(n))?;
-                    self.bhop_it(reference, false)
                 }
-                None => self.bhop_it(reference, false),
-            },
+                let action = self.action(&reference, false)?;
+                Ok(self.render(action))
+            }
             Request::Passthrough(cmd) => self.passthrough(cmd),
-            Request::Search(pattern) => self.search(pattern).map(|_| "".to_string()),
+            Request::Search(pattern, options) => self.list(pattern, options),
+            Request::Tag(name, tags) => self.tag_shortcut(name, tags),
+            Request::Untag(name, tags) => self.untag_shortcut(name, tags),
+            Request::Describe(name, description) => self.describe_shortcut(name, description),
+            Request::Rename(old, new) => self.rename_shortcut(old, new),
+            Request::Retarget(name, path) => self.retarget_shortcut(name, path),
+            Request::MoveTree(old, new) => self.move_tree(old, new, false),
+            Request::EditShortcuts => self.edit_shortcuts(),
+            Request::Undo => self.undo(),
+            Request::Doctor(fix) => self.doctor(fix, false),
+            Request::Repos(refresh) => self.list_repos(refresh),
+            Request::Record(path) => {
+                let path = self.absolute(path);
+                crate::history::record_change(self.store.as_mut(), path, &self.config.history_rules)
+                    .map(|_| "".to_string())
+            }
+            Request::History(filter) => self.list_history(filter),
+            Request::Stats(days) => self.stats(days),
+            Request::PruneHistory => self.prune_history(false),
+            Request::Completions(shell) => match crate::completions::script(&shell) {
+                Some(script) => Ok(script),
+                None => Ok(format!(
+                    "Unknown shell `{}`, use one of: {}.",
+                    shell,
+                    crate::completions::SHELLS.join(", ")
+                )),
+            },
+            Request::Complete(words) => self.complete(words),
+            Request::ForgetHistory(pattern) => self.forget_history(pattern, false),
             Request::Notify(msg) => Ok(msg),
             Request::Configure => self.configure(),
+            Request::ConfigGet(key) => self.config_get(key),
+            Request::ConfigSet(key, value) => self.config_set(key, value),
+            Request::ConfigList(effective) => self.config_list(effective),
             Request::Locate => self.locate(),
-            Request::Help => Ok(format!(include_str!("defaults/help.txt"),
-                "hp".cyan().bold(),
-                "[COMMAND]".green().bold(),
-                "add, a, +".green().bold(),
-                "list, ls, l, ..".green().bold(),
-                "remove, rm, r, -".green().bold(),
-                "find, f, <-, ?".green().bold(),
-                "group, grp, g, !".green().bold(),
-                "locate, loc".green().bold(),
-                "configure, cfg, c".green().bold(),
-                "version, v".green().bold(),
-                "help, h".green().bold(),
-                "brb".green().bold(),
-                "back".green().bold(),
-                "[COMMAND]".green().bold())),
+            Request::WhichEditor(reference) => self.which_editor(reference),
+            Request::Help => {
+                let depth = self.color_depth();
+                let command = |text: &str| Style::named("green").bold().paint(text, depth);
+                Ok(format!(
+                    include_str!("defaults/help.txt"),
+                    Style::named("cyan").bold().paint("hp", depth),
+                    command("[COMMAND]"),
+                    command("add, a, +"),
+                    command("list, ls, l, .."),
+                    command("remove, rm, r, -"),
+                    command("find, f, <-, ?"),
+                    command("group, grp, g, !"),
+                    command("locate, loc"),
+                    command("tag, untag"),
+                    command("describe, desc"),
+                    command("rename, mv"),
+                    command("retarget"),
+                    command("mv-tree"),
+                    command("edit-shortcuts"),
+                    command("undo"),
+                    command("doctor"),
+                    command("repos"),
+                    command("history"),
+                    command("history prune, history forget"),
+                    command("stats"),
+                    command("completions"),
+                    command("configure, cfg, c"),
+                    command("config get, config set, config list"),
+                    command("which-editor"),
+                    command("version, v"),
+                    command("help, h"),
+                    command("brb"),
+                    command("back"),
+                    command("[COMMAND]")
+                ))
+            }
+            Request::Version => Ok(format!(
+                "{} 🐇 {}{}",
+                self.paint("Bhop", Style::named("cyan").bold()),
+                self.paint("v.", Style::default().bold()),
+                env!("CARGO_PKG_VERSION")
+            )),
             Request::Skip => Ok("".to_string()),
-        };
-        match output {
-            Ok(msg) => {
-                if !msg.is_empty() {
-                    print!("{}", msg)
-                }
+            Request::DryRun(request) => match *request {
+                Request::Group(reference, subgroup) => self.use_group(reference, subgroup, true),
+                Request::Remove(reference) => self.remove_shortcut(reference, true, false),
+                Request::MoveTree(old, new) => self.move_tree(old, new, true),
+                Request::PruneHistory => self.prune_history(true),
+                Request::ForgetHistory(pattern) => self.forget_history(pattern, true),
+                Request::Confirmed(request) => self.respond(Request::DryRun(request)),
+                _ => Ok("Dry run is not supported for this command.".to_string()),
+            },
+            Request::Verbose(level, request) => {
+                logging::set_level(level);
+                let flag = format!("-{}", "v".repeat(level as usize));
+                self.respond(pass_flag(*request, &flag))
             }
-            Err(err) => print!("{}", err),
-        };
-        Ok(())
+            Request::Color(mode, request) => {
+                self.config.color = mode;
+                self.respond(pass_flag(*request, &format!("--color={}", mode)))
+            }
+            Request::Confirmed(request) => match *request {
+                Request::Remove(reference) => self.remove_shortcut(reference, false, true),
+                Request::Doctor(fix) => self.doctor(fix, true),
+                request => self.respond(request),
+            },
+        }
     }
 }
@@ -0,0 +1,115 @@
+// Building a `Hopper` piece by piece.  Anything left out is set up the same way `Hopper::new`
+// does it, so giving a store, configuration, working directory and environment makes for an
+// instance that doesn't read or write any global state.
+use crate::configs::Configs;
+use crate::logging::{self, trace};
+use crate::metadata::Environment;
+use crate::store::{SqliteStore, Store};
+use crate::Hopper;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+#[derive(Default)]
+pub struct HopperBuilder {
+    config: Option<Configs>,
+    store: Option<Box<dyn Store>>,
+    config_dir: Option<PathBuf>,
+    cwd: Option<PathBuf>,
+    vars: Option<HashMap<String, String>>,
+    verbosity: u8,
+}
+
+impl HopperBuilder {
+    pub fn config(mut self, config: Configs) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    pub fn store<S: Store + 'static>(mut self, store: S) -> Self {
+        self.store = Some(Box::new(store));
+        self
+    }
+
+    // Where bhop.toml and the database live, instead of `BHOP_CONFIG_DIRECTORY` or the default
+    // (both taken from `env` when it's given).
+    pub fn config_dir<T: Into<PathBuf>>(mut self, config_dir: T) -> Self {
+        self.config_dir = Some(config_dir.into());
+        self
+    }
+
+    // The directory relative references are resolved from, instead of the process's.
+    pub fn cwd<T: Into<PathBuf>>(mut self, cwd: T) -> Self {
+        self.cwd = Some(cwd.into());
+        self
+    }
+
+    // The only environment variables the instance will see (e.g. `BHOP_CMD_SEPARATOR`, `EDITOR`
+    // or `HOME`), instead of the process's.
+    pub fn env<I, K, V>(mut self, vars: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.vars = Some(
+            vars.into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+        );
+        self
+    }
+
+    // The logging level asked for on the command line, which `verbose = true` can't lower.
+    pub fn verbosity(mut self, level: u8) -> Self {
+        self.verbosity = level;
+        self
+    }
+
+    pub fn build(self) -> anyhow::Result<Hopper> {
+        // An instance given its own environment doesn't look at the process's for where its
+        // configuration lives either.
+        let config_dir = match (self.config_dir, &self.vars) {
+            (Some(config_dir), _) => config_dir,
+            (None, Some(vars)) => Environment::config_dir_in(
+                |key| vars.get(key).cloned(),
+                vars.get("HOME")
+                    .or_else(|| vars.get("USERPROFILE"))
+                    .map(PathBuf::from),
+            ),
+            (None, None) => Environment::config_dir(),
+        };
+        let (env, store) = match self.store {
+            Some(store) => (Environment::at(&config_dir), store),
+            None => {
+                let env = Environment::setup(&config_dir);
+                let store: Box<dyn Store> = Box::new(SqliteStore::open(&env.db_path)?);
+                (env, store)
+            }
+        };
+        let layered = self.config.is_none();
+        let mut hopper = Hopper {
+            config: self.config.unwrap_or_default(),
+            store,
+            env,
+            warnings: Vec::new(),
+            cwd: self.cwd,
+            vars: self.vars,
+        };
+        // The layers depend on the working directory and environment of the instance.
+        if layered {
+            let layers = hopper.config_layers()?;
+            hopper.config = Configs::from_layers(&layers, &hopper.config_env())?;
+            hopper.warnings = layers.into_iter().filter_map(|l| l.warning).collect();
+        }
+        // Logging is set up for the whole process, so the most recently built instance decides
+        // the level.
+        logging::set_level(self.verbosity.max(hopper.config.verbose as u8));
+        if hopper.config.log_file {
+            let log_path = hopper.log_path();
+            if let Err(e) = logging::open_file(&log_path) {
+                trace!(1, "unable to open {}: {}", log_path.display(), e);
+            }
+        }
+        Ok(hopper)
+    }
+}
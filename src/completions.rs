@@ -0,0 +1,154 @@
+// Shell completions.  `hp completions <shell>` prints a completion script for the shell, and the
+// scripts call back into `bhop __complete <words>` with the words typed after `hp` so far (the
+// last one being the word under the cursor) to get the candidates from the database.  That runs
+// on every tab press, so it only ever reads: references are resolved without being relocated or
+// counted as used, and nothing is set up if bhop hasn't been yet.
+use crate::groups::BhopGroup;
+use crate::metadata::Environment;
+use crate::store::{MemoryStore, SqliteStore};
+use crate::Hopper;
+use std::env::var;
+use std::path::{Path, PathBuf};
+
+// Every command and alias understood by `Request::parse`.
+const COMMANDS: &[&str] = &[
+    "add",
+    "+",
+    "remove",
+    "rm",
+    "r",
+    "-",
+    "group",
+    "grp",
+    "g",
+    "->",
+    "!",
+    "find",
+    "f",
+    "<-",
+    "?",
+    "brb",
+    "locate",
+    "loc",
+    "version",
+    "v",
+    "help",
+    "h",
+    "list",
+    "ls",
+    "l",
+    "..",
+    "tag",
+    "untag",
+    "describe",
+    "desc",
+    "rename",
+    "mv",
+    "retarget",
+    "mv-tree",
+    "edit-shortcuts",
+    "undo",
+    "doctor",
+    "repos",
+    "history",
+    "stats",
+    "configure",
+    "config",
+    "cfg",
+    "c",
+    "which-editor",
+    "completions",
+];
+
+pub const SHELLS: &[&str] = &["bash", "zsh", "fish", "nushell", "powershell"];
+
+pub fn script(shell: &str) -> Option<String> {
+    let script = match shell {
+        "bash" => include_str!("defaults/completions/bhop.bash"),
+        "zsh" => include_str!("defaults/completions/bhop.zsh"),
+        "fish" => include_str!("defaults/completions/bhop.fish"),
+        "nu" | "nushell" => include_str!("defaults/completions/bhop.nu"),
+        "pwsh" | "powershell" => include_str!("defaults/completions/bhop.ps1"),
+        _ => return None,
+    };
+    Some(
+        script
+            .replace("__HOPPERCMD__", env!("CARGO_PKG_NAME"))
+            .replace(
+                "__FUNCTION_ALIAS__",
+                &var("BHOP_DEFAULT_ALIAS").unwrap_or("hp".to_string()),
+            ),
+    )
+}
+
+// Entry point for `bhop __complete`.  The database is opened directly rather than going through
+// `Hopper::new`, which would create the configuration directory and database when missing.
+pub fn complete(words: Vec<String>) -> anyhow::Result<String> {
+    complete_in(Environment::config_dir(), words)
+}
+
+// Completes `words` against the configuration and database under `config_dir`.
+pub fn complete_in<T: AsRef<Path>>(config_dir: T, words: Vec<String>) -> anyhow::Result<String> {
+    let config_dir = config_dir.as_ref();
+    let db_path = Environment::at(config_dir).db_path;
+    let builder = Hopper::builder().config_dir(config_dir);
+    let hopper = if db_path.exists() {
+        builder.store(SqliteStore::open(db_path)?).build()?
+    } else {
+        builder.store(MemoryStore::new()).build()?
+    };
+    hopper.complete(words)
+}
+
+impl Hopper {
+    fn shortcut_names(&self) -> anyhow::Result<Vec<String>> {
+        Ok(self.store.shortcuts()?.into_keys().collect())
+    }
+
+    fn reference_names(&self) -> anyhow::Result<Vec<String>> {
+        let mut names = self.shortcut_names()?;
+        let history = self.store.history(chrono::Utc::now().timestamp())?;
+        names.extend(history.into_iter().map(|entry| entry.name));
+        Ok(names)
+    }
+
+    fn group_names(&self, reference: &str) -> Vec<String> {
+        let path = self
+            .grab(reference.to_string())
+            .unwrap_or(PathBuf::from("."));
+        BhopGroup::names(path.join(self.project_configs()))
+    }
+
+    pub fn complete(&self, words: Vec<String>) -> anyhow::Result<String> {
+        let partial = words.last().cloned().unwrap_or_default();
+        let command = words.first().map(|w| w.as_str()).unwrap_or("");
+        let mut candidates = match (words.len(), command) {
+            (0 | 1, _) => {
+                let mut candidates: Vec<String> = COMMANDS.iter().map(|c| c.to_string()).collect();
+                candidates.extend(self.reference_names()?);
+                candidates
+            }
+            (2, "g" | "grp" | "group" | "->" | "!" | "f" | "find" | "<-" | "?") => {
+                self.reference_names()?
+            }
+            (3, "g" | "grp" | "group" | "->" | "!") => self.group_names(&words[1]),
+            (
+                2,
+                "r" | "rm" | "remove" | "-" | "tag" | "untag" | "desc" | "describe" | "mv"
+                | "rename" | "retarget",
+            ) => self.shortcut_names()?,
+            (2, "history") => vec!["prune".to_string(), "forget".to_string()],
+            (2, "config") => ["get", "set", "list"].map(|s| s.to_string()).to_vec(),
+            (3, "config") if words[1] != "list" => crate::settings::SETTINGS
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            (2, "completions") => SHELLS.iter().map(|s| s.to_string()).collect(),
+            _ => Vec::new(),
+        };
+        candidates.retain(|c| c.starts_with(&partial));
+        candidates.sort();
+        candidates.dedup();
+        Ok(candidates.join("\n"))
+    }
+}
@@ -1,6 +1,13 @@
+use crate::history::HistoryRules;
+use crate::layers::ConfigLayer;
+use crate::theme::{ColorMode, ReadTheme, Theme};
+use dirs::home_dir;
+use glob::Pattern;
 use serde_derive::Deserialize;
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::fmt;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
 use std::{env::var, fs};
 use toml::from_str;
 
@@ -8,6 +15,8 @@ use toml::from_str;
 pub struct ReadConfig {
     pub settings: Option<ReadSettings>,
     pub editors: Option<HashMap<String, String>>,
+    pub editor_rules: Option<Vec<EditorRule>>,
+    pub theme: Option<ReadTheme>,
 }
 
 #[derive(Deserialize, PartialEq, Debug, Default)]
@@ -21,6 +30,15 @@ pub struct ReadSettings {
     pub always_jump: Option<bool>,
     pub search_match_prefix: Option<String>,
     pub search_match_suffix: Option<String>,
+    pub relocate_roots: Option<Vec<String>>,
+    pub relocate_max_depth: Option<usize>,
+    pub repo_roots: Option<Vec<String>>,
+    pub repo_max_depth: Option<usize>,
+    pub project_markers: Option<Vec<String>>,
+    pub history_exclude: Option<Vec<String>>,
+    pub history_min_depth: Option<usize>,
+    pub log_file: Option<bool>,
+    pub color: Option<ColorMode>,
 }
 
 impl ReadConfig {
@@ -29,22 +47,139 @@ impl ReadConfig {
             fs::read_to_string(config_path).expect("[error] Unable to read config file location.");
         let read_config: ReadConfig =
             from_str(&toml_str).expect("[error] Unable to parse configuration TOML.");
-        let read_settings = match read_config.settings {
-            Some(settings) => settings,
-            None => ReadSettings::default(),
-        };
-        let read_editors = match read_config.editors {
-            Some(editors) => editors,
-            None => HashMap::new(),
-        };
+        let read_settings = read_config.settings.unwrap_or_default();
+        let read_editors = read_config.editors.unwrap_or_default();
+        let read_editor_rules = read_config.editor_rules.unwrap_or_default();
+        let read_theme = read_config.theme.unwrap_or_default();
         ReadConfig {
             settings: Some(read_settings),
             editors: Some(read_editors),
+            editor_rules: Some(read_editor_rules),
+            theme: Some(read_theme),
+        }
+    }
+}
+
+// A single entry of the ordered `[[editor_rules]]` table.  Every criteria that is set must match
+// for the rule to apply, and the first matching rule wins over the extension based `[editors]`.
+#[derive(Deserialize, PartialEq, Eq, Debug, Default, Clone)]
+pub struct EditorRule {
+    pub editor: String,
+    pub glob: Option<String>,
+    pub filename: Option<String>,
+    pub kind: Option<RuleKind>,
+    pub shebang: Option<String>,
+}
+
+// What `kind = "..."` in an editor rule accepts.
+#[derive(Deserialize, PartialEq, Eq, Debug, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleKind {
+    File,
+    #[serde(alias = "directory")]
+    Dir,
+}
+
+impl fmt::Display for RuleKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RuleKind::File => write!(f, "file"),
+            RuleKind::Dir => write!(f, "dir"),
+        }
+    }
+}
+
+impl EditorRule {
+    pub fn matches<T: AsRef<Path>>(&self, path: T) -> bool {
+        let path = path.as_ref();
+        let file_name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        if let Some(glob) = &self.glob {
+            // Patterns without a separator only need to match the file name, the same way
+            // `.gitignore` patterns work.
+            let target = if glob.contains('/') {
+                path.display().to_string().replace('\\', "/")
+            } else {
+                file_name.clone()
+            };
+            match Pattern::new(glob) {
+                Ok(pattern) if pattern.matches(&target) => {}
+                _ => return false,
+            }
+        }
+        if let Some(filename) = &self.filename {
+            if filename != &file_name {
+                return false;
+            }
+        }
+        if let Some(kind) = self.kind {
+            if (kind == RuleKind::Dir) != path.is_dir() {
+                return false;
+            }
+        }
+        if let Some(shebang) = &self.shebang {
+            match read_shebang(path) {
+                Some(line) if line.contains(shebang.as_str()) => {}
+                _ => return false,
+            }
+        }
+        true
+    }
+
+    fn describe(&self) -> String {
+        let mut criteria = Vec::new();
+        if let Some(glob) = &self.glob {
+            criteria.push(format!("glob = \"{}\"", glob));
+        }
+        if let Some(filename) = &self.filename {
+            criteria.push(format!("filename = \"{}\"", filename));
+        }
+        if let Some(kind) = &self.kind {
+            criteria.push(format!("kind = \"{}\"", kind));
+        }
+        if let Some(shebang) = &self.shebang {
+            criteria.push(format!("shebang = \"{}\"", shebang));
+        }
+        criteria.join(", ")
+    }
+}
+
+fn read_shebang(path: &Path) -> Option<String> {
+    if !path.is_file() {
+        return None;
+    }
+    let mut line = String::new();
+    BufReader::new(fs::File::open(path).ok()?)
+        .read_line(&mut line)
+        .ok()?;
+    if line.starts_with("#!") {
+        Some(line.trim().to_string())
+    } else {
+        None
+    }
+}
+
+// Where the editor chosen for a path came from, used to explain the decision in `which-editor`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EditorSource {
+    Rule(usize, String),
+    Extension(String),
+    Default,
+}
+
+impl fmt::Display for EditorSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EditorSource::Rule(i, criteria) => write!(f, "editor_rules[{}] ({})", i, criteria),
+            EditorSource::Extension(ext) => write!(f, "editors.{}", ext),
+            EditorSource::Default => write!(f, "settings.default_editor"),
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Configs {
     pub default_editor: String,
     pub ls_display_block: usize,
@@ -56,17 +191,59 @@ pub struct Configs {
     pub search_match_prefix: String,
     pub search_match_suffix: String,
     pub editors: HashMap<String, String>,
+    pub editor_rules: Vec<EditorRule>,
+    pub relocate_roots: Vec<PathBuf>,
+    pub relocate_max_depth: usize,
+    pub repo_roots: Vec<PathBuf>,
+    pub repo_max_depth: usize,
+    pub project_markers: Vec<String>,
+    pub history_rules: HistoryRules,
+    pub log_file: bool,
+    pub color: ColorMode,
+    pub theme: Theme,
+}
+
+// The defaults that don't come from bhop.toml: the `EDITOR` variable, and the home directory
+// that `~` expands to and `relocate_roots` falls back on.  `Hopper` takes them from its own
+// environment, see `HopperBuilder::env`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ConfigEnv {
+    pub editor: Option<String>,
+    pub home: Option<PathBuf>,
+}
+
+impl ConfigEnv {
+    pub fn process() -> Self {
+        ConfigEnv {
+            editor: var("EDITOR").ok(),
+            home: home_dir(),
+        }
+    }
 }
 
 impl Configs {
     pub fn new(config_path: &PathBuf) -> Self {
-        let read_config = ReadConfig::new(config_path);
-        let settings = read_config.settings.unwrap();
+        Self::from_read(ReadConfig::new(config_path))
+    }
+
+    // Merges every layer of configuration, see `layers.rs` for their precedence.
+    pub fn from_layers(layers: &[ConfigLayer], env: &ConfigEnv) -> anyhow::Result<Self> {
+        Ok(Self::from_read_in(crate::layers::merge(layers)?, env))
+    }
+
+    pub fn from_read(read_config: ReadConfig) -> Self {
+        Self::from_read_in(read_config, &ConfigEnv::process())
+    }
+
+    pub fn from_read_in(read_config: ReadConfig, env: &ConfigEnv) -> Self {
+        let home = env.home.as_deref();
+        let settings = read_config.settings.unwrap_or_default();
+        let history_rules = HistoryRules::from_settings(&settings, home);
         let default_editor = match settings.default_editor {
             Some(editor) => editor,
-            None => match var("EDITOR") {
-                Ok(sys_editor) => sys_editor,
-                Err(_) => {
+            None => match &env.editor {
+                Some(sys_editor) => sys_editor.to_string(),
+                None => {
                     if cfg!(target_os = "windows") {
                         String::from("notepad")
                     } else {
@@ -79,11 +256,36 @@ impl Configs {
         let print_color_primary = settings.print_color_primary.unwrap_or([51, 255, 255]);
         let print_color_secondary = settings.print_color_secondary.unwrap_or([51, 255, 153]);
         let verbose = settings.verbose.unwrap_or(false);
+        let log_file = settings.log_file.unwrap_or(false);
+        let color = settings.color.unwrap_or_default();
         let prioritize_shortcuts = settings.prioritize_shortcuts.unwrap_or(false);
         let always_jump = settings.always_jump.unwrap_or(false);
         let search_match_prefix = settings.search_match_prefix.unwrap_or("*".to_string());
         let search_match_suffix = settings.search_match_suffix.unwrap_or("*".to_string());
-        let editors = read_config.editors.unwrap_or(HashMap::new());
+        let editors = read_config.editors.unwrap_or_default();
+        let editor_rules = read_config.editor_rules.unwrap_or_default();
+        let theme = Theme::from_read(
+            read_config.theme.unwrap_or_default(),
+            print_color_primary,
+            print_color_secondary,
+        );
+        let relocate_roots = match settings.relocate_roots {
+            Some(roots) => roots.iter().map(|r| expand_home_in(r, home)).collect(),
+            None => home.map(Path::to_path_buf).into_iter().collect(),
+        };
+        let relocate_max_depth = settings.relocate_max_depth.unwrap_or(4);
+        let repo_roots = settings
+            .repo_roots
+            .unwrap_or_default()
+            .iter()
+            .map(|r| expand_home_in(r, home))
+            .collect();
+        let repo_max_depth = settings.repo_max_depth.unwrap_or(3);
+        let project_markers = settings.project_markers.unwrap_or(vec![
+            ".git".to_string(),
+            "Cargo.toml".to_string(),
+            ".bhop".to_string(),
+        ]);
         Configs {
             default_editor,
             ls_display_block,
@@ -95,6 +297,54 @@ impl Configs {
             search_match_prefix,
             search_match_suffix,
             editors,
+            editor_rules,
+            relocate_roots,
+            relocate_max_depth,
+            repo_roots,
+            repo_max_depth,
+            project_markers,
+            history_rules,
+            log_file,
+            color,
+            theme,
+        }
+    }
+
+    pub fn editor_for<T: AsRef<Path>>(&self, path: T) -> (String, EditorSource) {
+        let path = path.as_ref();
+        for (i, rule) in self.editor_rules.iter().enumerate() {
+            if rule.matches(path) {
+                return (
+                    rule.editor.to_string(),
+                    EditorSource::Rule(i, rule.describe()),
+                );
+            }
         }
+        if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+            if let Some(editor) = self.editors.get(ext) {
+                return (editor.to_string(), EditorSource::Extension(ext.to_string()));
+            }
+        }
+        (self.default_editor.to_string(), EditorSource::Default)
+    }
+}
+
+// The settings used when there's no bhop.toml at all.
+impl Default for Configs {
+    fn default() -> Self {
+        Self::from_read(ReadConfig::default())
+    }
+}
+
+// Expands a leading `~` to the user's home directory.
+pub fn expand_home(path: &str) -> PathBuf {
+    expand_home_in(path, home_dir().as_deref())
+}
+
+// Expands a leading `~` to `home`.
+pub fn expand_home_in(path: &str, home: Option<&Path>) -> PathBuf {
+    match (path.strip_prefix('~'), home) {
+        (Some(rest), Some(home)) => home.join(rest.trim_start_matches(['/', '\\'])),
+        _ => PathBuf::from(path),
     }
 }
@@ -0,0 +1,6 @@
+# bash completions for __FUNCTION_ALIAS__, generated with `__FUNCTION_ALIAS__ completions bash`.
+_bhop_complete() {
+    local IFS=$'\n'
+    COMPREPLY=($(__HOPPERCMD__ __complete "${COMP_WORDS[@]:1:COMP_CWORD}" 2>/dev/null))
+}
+complete -o default -F _bhop_complete __FUNCTION_ALIAS__
@@ -0,0 +1,2 @@
+# fish completions for __FUNCTION_ALIAS__, generated with `__FUNCTION_ALIAS__ completions fish`.
+complete -c __FUNCTION_ALIAS__ -a '(__HOPPERCMD__ __complete (commandline -opc)[2..-1] (commandline -ct) 2>/dev/null)'
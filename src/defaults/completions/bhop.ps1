@@ -0,0 +1,9 @@
+# PowerShell completions for __FUNCTION_ALIAS__, generated with `__FUNCTION_ALIAS__ completions powershell`.
+Register-ArgumentCompleter -CommandName __FUNCTION_ALIAS__ -ScriptBlock {
+    param($commandName, $parameterName, $wordToComplete, $commandAst, $fakeBoundParameters)
+    $words = @($commandAst.CommandElements | Select-Object -Skip 1 | ForEach-Object { $_.ToString() })
+    if ($wordToComplete -eq "") { $words += "" }
+    & __HOPPERCMD__ __complete @words | ForEach-Object {
+        [System.Management.Automation.CompletionResult]::new($_, $_, "ParameterValue", $_)
+    }
+}
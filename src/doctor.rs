@@ -0,0 +1,343 @@
+// `hp doctor` checks stored shortcuts and history for locations that no longer work, broken
+// `.bhop` files and shells that are missing their runner.  Missing shortcuts are listed with the
+// directories of the same name in the history as candidates for where they went.  With `--fix`,
+// each one is retargeted or removed after asking, and so is pruning the missing history entries.
+// Adding `--yes` skips the questions, retargeting the shortcuts with a single candidate, removing
+// those without any and leaving the ones with several for `hp retarget`.
+use crate::groups::BhopGroup;
+use crate::shells::Shell;
+use crate::theme::{ColorDepth, Style};
+use crate::Hopper;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{ErrorKind, IsTerminal};
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathProblem {
+    Missing,
+    Unreadable(String),
+}
+
+pub fn check_path<T: AsRef<Path>>(path: T) -> Option<PathProblem> {
+    let path = path.as_ref();
+    let result = match fs::metadata(path) {
+        Ok(metadata) if metadata.is_dir() => fs::read_dir(path).map(|_| ()),
+        Ok(_) => fs::File::open(path).map(|_| ()),
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(_) => None,
+        Err(e) if e.kind() == ErrorKind::NotFound => Some(PathProblem::Missing),
+        Err(e) => Some(PathProblem::Unreadable(e.to_string())),
+    }
+}
+
+fn finding(label: &str, text: String, depth: Option<ColorDepth>) -> String {
+    let label = Style::named("yellow")
+        .bold()
+        .paint(&format!("[{}]", label), depth);
+    format!("{} {}", label, text)
+}
+
+// A shortcut whose target is missing, with the existing directories of the same name in the
+// history, most used first.
+struct MissingShortcut {
+    name: String,
+    location: String,
+    candidates: Vec<String>,
+}
+
+enum Fix {
+    Retarget(String),
+    Remove,
+    Skip(&'static str),
+}
+
+impl MissingShortcut {
+    // The questions go to stderr since stdout is captured by the runner.  When there's nobody
+    // around to answer them, nothing is changed without `--yes`.
+    fn choose_fix(&self, confirmed: bool) -> Fix {
+        if confirmed {
+            return match self.candidates.as_slice() {
+                [] => Fix::Remove,
+                [only] => Fix::Retarget(only.to_string()),
+                _ => Fix::Skip("has several candidates, use `hp retarget` to pick one"),
+            };
+        }
+        if !std::io::stdin().is_terminal() {
+            return Fix::Skip("left as is, rerun with `--yes` to fix without asking");
+        }
+        if self.candidates.is_empty() {
+            eprint!(
+                "Shortcut `{}` points at missing {}, remove it? [y/N] ",
+                self.name, self.location
+            );
+            return match proceed::proceed() {
+                true => Fix::Remove,
+                false => Fix::Skip("left as is"),
+            };
+        }
+        eprintln!(
+            "Shortcut `{}` points at missing {}, it may have moved to:",
+            self.name, self.location
+        );
+        for (i, candidate) in self.candidates.iter().enumerate() {
+            eprintln!("  {}) {}", i + 1, candidate);
+        }
+        eprint!(
+            "Retarget it [1-{}], remove it [r] or leave it [N]? ",
+            self.candidates.len()
+        );
+        let mut answer = String::new();
+        if std::io::stdin().read_line(&mut answer).is_err() {
+            return Fix::Skip("left as is");
+        }
+        match answer.trim() {
+            "r" | "R" => Fix::Remove,
+            answer => answer
+                .parse::<usize>()
+                .ok()
+                .and_then(|i| self.candidates.get(i.checked_sub(1)?))
+                .map(|candidate| Fix::Retarget(candidate.to_string()))
+                .unwrap_or(Fix::Skip("left as is")),
+        }
+    }
+}
+
+fn confirm_prune(count: usize, confirmed: bool) -> bool {
+    if confirmed {
+        return true;
+    }
+    if !std::io::stdin().is_terminal() {
+        return false;
+    }
+    eprint!("Prune {} missing history entries? [y/N] ", count);
+    proceed::proceed()
+}
+
+impl Hopper {
+    pub(crate) fn doctor(&mut self, fix: bool, confirmed: bool) -> anyhow::Result<String> {
+        let depth = self.color_depth();
+        let mut report = Vec::new();
+        let mut missing_shortcuts = Vec::new();
+        let mut targets: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for (name, entry) in self.load_shortcuts()? {
+            match check_path(&entry.location) {
+                Some(PathProblem::Missing) => {
+                    report.push(finding(
+                        "missing",
+                        format!("shortcut {} -> {}", name, entry.location),
+                        depth,
+                    ));
+                    missing_shortcuts.push(MissingShortcut {
+                        name: name.clone(),
+                        location: entry.location.clone(),
+                        candidates: Vec::new(),
+                    });
+                }
+                Some(PathProblem::Unreadable(e)) => report.push(finding(
+                    "unreadable",
+                    format!("shortcut {} -> {} ({})", name, entry.location, e),
+                    depth,
+                )),
+                // Shortcuts sharing a target are reported as duplicates below, so their `.bhop`
+                // file only needs checking once.
+                None if !targets.contains_key(&entry.location) => {
+                    let group_path = Path::new(&entry.location).join(self.project_configs());
+                    if group_path.is_file() {
+                        if let Err(e) = BhopGroup::validate(&group_path) {
+                            report.push(finding(
+                                "broken",
+                                format!("{} ({})", group_path.display(), e),
+                                depth,
+                            ));
+                        }
+                    }
+                }
+                None => {}
+            }
+            targets.entry(entry.location).or_default().push(name);
+        }
+        for (location, names) in targets.iter() {
+            if names.len() > 1 {
+                report.push(finding(
+                    "duplicate",
+                    format!("{} all point to {}", names.join(", "), location),
+                    depth,
+                ));
+            }
+        }
+
+        let mut missing_history = Vec::new();
+        let mut existing_history = Vec::new();
+        for [name, location] in self.load_history()? {
+            match check_path(&location) {
+                Some(PathProblem::Missing) => {
+                    report.push(finding(
+                        "missing",
+                        format!("history {} -> {}", name, location),
+                        depth,
+                    ));
+                    missing_history.push(location);
+                }
+                Some(PathProblem::Unreadable(e)) => report.push(finding(
+                    "unreadable",
+                    format!("history {} -> {} ({})", name, location, e),
+                    depth,
+                )),
+                None => existing_history.push(location),
+            }
+        }
+        // The history is sorted by usage, so the most used directories come first.
+        for missing in missing_shortcuts.iter_mut() {
+            let base = Path::new(&missing.location).file_name();
+            missing.candidates = existing_history
+                .iter()
+                .filter(|location| Path::new(location).file_name() == base)
+                .cloned()
+                .collect();
+            if !missing.candidates.is_empty() {
+                report.push(finding(
+                    "candidates",
+                    format!(
+                        "shortcut {} may have moved to {}",
+                        missing.name,
+                        missing.candidates.join(", ")
+                    ),
+                    depth,
+                ));
+            }
+        }
+
+        for shell in Shell::supported() {
+            if let Some(problem) = self.check_runner(&shell) {
+                report.push(finding("runner", problem, depth));
+            }
+        }
+
+        if report.is_empty() {
+            return Ok("No problems found.".to_string());
+        }
+        let fixable = !missing_shortcuts.is_empty() || !missing_history.is_empty();
+        if fix && fixable {
+            report.extend(self.fix_missing(missing_shortcuts, missing_history, confirmed)?);
+        } else if fixable {
+            report.push(
+                "Run `hp doctor --fix` to retarget, remove or prune missing entries.".to_string(),
+            );
+        }
+        Ok(report.join("\n"))
+    }
+
+    pub(crate) fn load_history(&self) -> anyhow::Result<Vec<[String; 2]>> {
+        let mut history = self.store.history(chrono::Utc::now().timestamp())?;
+        history.sort_by_key(|entry| std::cmp::Reverse(entry.usage));
+        Ok(history
+            .into_iter()
+            .map(|entry| [entry.name, entry.location])
+            .collect())
+    }
+
+    fn check_runner(&self, shell: &Shell) -> Option<String> {
+        // Shells without a configuration file on this system aren't in use, so there's nothing
+        // to check for them.
+        let config_path = shell.derive_config_path()?;
+        let contents = fs::read_to_string(&config_path).ok()?;
+        let script_path = self
+            .env
+            .config_path
+            .parent()?
+            .join("scripts")
+            .join(format!("runner.{}", shell.ext()));
+        let script = script_path.display().to_string().replace('\\', "/");
+        if !contents.contains(&script) {
+            Some(format!(
+                "{} config {} doesn't load the runner, reinstall bhop to add it",
+                shell.name(),
+                config_path.display()
+            ))
+        } else if !script_path.is_file() {
+            Some(format!(
+                "{} runner {} is missing, reinstall bhop to add it",
+                shell.name(),
+                script
+            ))
+        } else {
+            None
+        }
+    }
+
+    fn fix_missing(
+        &mut self,
+        missing_shortcuts: Vec<MissingShortcut>,
+        missing_history: Vec<String>,
+        confirmed: bool,
+    ) -> anyhow::Result<Vec<String>> {
+        // Everything is asked before the changes are made, so the database isn't held while
+        // waiting on an answer.
+        let fixes: Vec<(MissingShortcut, Fix)> = missing_shortcuts
+            .into_iter()
+            .map(|missing| {
+                let fix = missing.choose_fix(confirmed);
+                (missing, fix)
+            })
+            .collect();
+        let prune = !missing_history.is_empty() && confirm_prune(missing_history.len(), confirmed);
+        let depth = self.color_depth();
+        let mut report = Vec::new();
+        let names = fixes
+            .iter()
+            .filter(|(_, fix)| !matches!(fix, Fix::Skip(_)))
+            .map(|(missing, _)| missing.name.to_string())
+            .collect();
+        self.journaled("doctor", names, |hopper| {
+            for (missing, fix) in fixes.iter() {
+                let name = &missing.name;
+                match fix {
+                    Fix::Retarget(location) => {
+                        hopper
+                            .update_shortcut(name, |entry| entry.location = location.to_string())?;
+                        hopper.record_identity(name)?;
+                        report.push(finding(
+                            "fixed",
+                            format!("retargeted shortcut {} -> {}", name, location),
+                            depth,
+                        ));
+                    }
+                    Fix::Remove => {
+                        hopper.delete_shortcut(name)?;
+                        report.push(finding(
+                            "fixed",
+                            format!("removed shortcut {}", name),
+                            depth,
+                        ));
+                    }
+                    Fix::Skip(reason) => report.push(finding(
+                        "skipped",
+                        format!("shortcut {} {}", name, reason),
+                        depth,
+                    )),
+                }
+            }
+            for location in missing_history.iter() {
+                if prune {
+                    hopper.store.delete_history(location)?;
+                    report.push(finding(
+                        "fixed",
+                        format!("pruned history {}", location),
+                        depth,
+                    ));
+                } else {
+                    report.push(finding(
+                        "skipped",
+                        format!("history {} left as is", location),
+                        depth,
+                    ));
+                }
+            }
+            Ok(())
+        })?;
+        Ok(report)
+    }
+}
@@ -1,17 +1,30 @@
-use std::{fs, path::Path};
+use crate::layers::{ConfigLayer, Origin};
+use crate::layouts::Layout;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
 use toml::{from_str, value::Table, Value};
 
+// Tables of a `.bhop` file that override the configuration instead of being groups.
+const CONFIG_TABLES: &[&str] = &["settings", "editors", "editor_rules", "theme"];
+
 #[derive(Debug, Clone, PartialEq, Default)]
 pub struct BhopGroup {
     pub cmd: Option<String>,
     pub editor: Option<String>,
     pub files: Option<Vec<String>>,
+    pub layout: Option<Layout>,
 }
 
 impl BhopGroup {
-    pub fn from_str(group: &str, toml: &str) -> Option<Self> {
+    // An unknown layout is an error rather than a group that does nothing.
+    pub fn from_str(group: &str, toml: &str) -> anyhow::Result<Option<Self>> {
+        if CONFIG_TABLES.contains(&group) {
+            return Ok(None);
+        }
         let table: Table = from_str(toml).unwrap_or(Table::new());
-        match table.get(group) {
+        Ok(match table.get(group) {
             Some(t) => match t {
                 Value::String(cmd) => Some(Self {
                     cmd: Some(cmd.to_string()),
@@ -20,28 +33,60 @@ impl BhopGroup {
                 Value::Table(t) => {
                     let editor = t.get("editor").map(|v| v.as_str().unwrap().to_string());
                     let files = match t.get("files") {
-                        Some(f) => Some(
-                            f.as_array()?
-                                .iter()
-                                .map(|v| v.as_str().unwrap().to_string())
-                                .collect(),
-                        ),
+                        Some(Value::Array(f)) => {
+                            Some(f.iter().map(|v| v.as_str().unwrap().to_string()).collect())
+                        }
+                        Some(_) => return Ok(None),
                         None => None,
                     };
+                    let layout = Layout::from_table(t)
+                        .map_err(|e| anyhow::anyhow!("Group `{}`: {}", group, e))?;
                     Some(Self {
                         cmd: None,
                         editor,
                         files,
+                        layout,
                     })
                 }
                 _ => None,
             },
             None => None,
+        })
+    }
+
+    pub fn from<T: AsRef<Path>>(group: &str, toml_path: T) -> anyhow::Result<Option<Self>> {
+        match fs::read_to_string(toml_path) {
+            Ok(toml) => Self::from_str(group, &toml),
+            Err(_) => Ok(None),
         }
     }
 
-    pub fn from<T: AsRef<Path>>(group: &str, toml_path: T) -> Option<Self> {
-        let toml = fs::read_to_string(toml_path).ok()?;
-        Self::from_str(group, &toml)
+    // Names of every group defined in a `.bhop` file.
+    pub fn names<T: AsRef<Path>>(toml_path: T) -> Vec<String> {
+        let toml = fs::read_to_string(toml_path).unwrap_or_default();
+        let table: Table = from_str(&toml).unwrap_or(Table::new());
+        table
+            .iter()
+            .filter(|(name, _)| !CONFIG_TABLES.contains(&name.as_str()))
+            .filter(|(_, value)| matches!(value, Value::String(_) | Value::Table(_)))
+            .map(|(name, _)| name.to_string())
+            .collect()
+    }
+
+    // Checks that a `.bhop` file parses and that its layout groups are usable, returning a
+    // description of the first problem found.
+    pub fn validate<T: AsRef<Path>>(toml_path: T) -> Result<(), String> {
+        let toml = fs::read_to_string(toml_path).map_err(|e| e.to_string())?;
+        let table: Table = from_str(&toml).map_err(|e| e.to_string())?;
+        ConfigLayer::parse(Origin::Project(PathBuf::new()), &toml)
+            .map_err(|e| format!("invalid configuration overrides: {}", e))?;
+        for (name, value) in table.iter() {
+            if let Value::Table(t) = value {
+                if let Err(e) = Layout::from_table(t) {
+                    return Err(format!("group `{}`: {}", name, e));
+                }
+            }
+        }
+        Ok(())
     }
 }
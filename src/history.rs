@@ -0,0 +1,320 @@
+// Recording visited directories into the history table.  Besides jumps made with `hp`, the shell
+// hooks installed with the runners call `bhop record <path>` on every directory change, so that
+// path skips setting up a full hop instance and only reads the history settings it needs.
+use crate::configs::{expand_home_in, ReadSettings};
+use crate::layers;
+use crate::metadata::Environment;
+use crate::store::{SqliteStore, Store};
+use crate::{glob_matches, sanitize, Hopper};
+use glob::Pattern;
+use std::env::var;
+use std::fs;
+use std::path::{Component, Path};
+
+const DAY: i64 = 24 * 60 * 60;
+
+// A directory change the hooks report this soon after the last visit, when that was to the same
+// directory, is the jump that caused it, which was recorded already.  Hooks only report changes,
+// so otherwise the same directory doesn't come up twice in a row.
+const JUMP_WINDOW: i64 = 5;
+
+pub fn ago(timestamp: i64, now: i64) -> String {
+    let elapsed = (now - timestamp).max(0);
+    match elapsed {
+        e if e < 60 => "just now".to_string(),
+        e if e < 60 * 60 => format!("{} min ago", e / 60),
+        e if e < DAY => format!("{} h ago", e / (60 * 60)),
+        e => format!("{} days ago", e / DAY),
+    }
+}
+
+// Which visited directories make it into the history, from `history_exclude` and
+// `history_min_depth`.  Without them every directory is recorded, the usual exclusions only come
+// with the default bhop.toml.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct HistoryRules {
+    pub exclude: Vec<String>,
+    pub min_depth: usize,
+}
+
+impl HistoryRules {
+    // A leading `~` in the patterns expands to `home`.
+    pub fn from_settings(settings: &ReadSettings, home: Option<&Path>) -> Self {
+        HistoryRules {
+            exclude: settings
+                .history_exclude
+                .as_ref()
+                .map(|patterns| {
+                    patterns
+                        .iter()
+                        .map(|p| {
+                            expand_home_in(p, home)
+                                .display()
+                                .to_string()
+                                .replace('\\', "/")
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+            min_depth: settings.history_min_depth.unwrap_or_default(),
+        }
+    }
+
+    // The rules from every configuration layer but the project's, which would mean looking for
+    // a `.bhop` file on every directory change.  Broken configuration just falls back to the
+    // defaults.
+    pub fn load<T: AsRef<Path>>(config_dir: T) -> Self {
+        Self::load_with(config_dir, |key| var(key).ok())
+    }
+
+    // Same as `load`, with `var` looking up the environment variables.
+    pub fn load_with<T: AsRef<Path>, F: Fn(&str) -> Option<String>>(config_dir: T, var: F) -> Self {
+        let user_path = config_dir.as_ref().join("bhop.toml");
+        let settings = layers::load_global(&user_path, var)
+            .and_then(|layers| layers::merge(&layers))
+            .ok()
+            .and_then(|config| config.settings)
+            .unwrap_or_default();
+        Self::from_settings(&settings, dirs::home_dir().as_deref())
+    }
+
+    pub fn allows(&self, location: &str) -> bool {
+        let depth = Path::new(location)
+            .components()
+            .filter(|c| matches!(c, Component::Normal(_)))
+            .count();
+        depth >= self.min_depth
+            && !self.exclude.iter().any(|pattern| {
+                Pattern::new(pattern)
+                    .map(|p| p.matches(location))
+                    .unwrap_or(false)
+            })
+    }
+}
+
+// The name and location `path` is recorded under, if the rules allow it.
+fn history_entry(path: &Path, rules: &HistoryRules) -> anyhow::Result<Option<(String, String)>> {
+    // The filesystem root has no name to look it up by, so it's left out of the history.
+    let name = match fs::canonicalize(path)?.file_name() {
+        Some(name) => name.to_string_lossy().to_string(),
+        None => return Ok(None),
+    };
+    let location = sanitize(path)?;
+    Ok(rules.allows(&location).then_some((name, location)))
+}
+
+pub fn record_visit<T: AsRef<Path>>(
+    store: &mut dyn Store,
+    path: T,
+    rules: &HistoryRules,
+) -> anyhow::Result<()> {
+    match history_entry(path.as_ref(), rules)? {
+        Some((name, location)) => {
+            store.record_visit(&name, &location, chrono::Utc::now().timestamp())
+        }
+        None => Ok(()),
+    }
+}
+
+// A directory change reported by the hooks, which is skipped when it follows a jump made with
+// `hp` to the same directory.  Shells without a hook still have their jumps recorded that way.
+pub fn record_change<T: AsRef<Path>>(
+    store: &mut dyn Store,
+    path: T,
+    rules: &HistoryRules,
+) -> anyhow::Result<()> {
+    let (name, location) = match history_entry(path.as_ref(), rules)? {
+        Some(entry) => entry,
+        None => return Ok(()),
+    };
+    let now = chrono::Utc::now().timestamp();
+    if store
+        .last_visit()?
+        .is_some_and(|(last, visited_at)| last == location && now - visited_at < JUMP_WINDOW)
+    {
+        return Ok(());
+    }
+    store.record_visit(&name, &location, now)
+}
+
+// Number of leading path components `a` and `b` have in common.
+pub fn shared_depth<T: AsRef<Path>, U: AsRef<Path>>(a: T, b: U) -> usize {
+    a.as_ref()
+        .components()
+        .zip(b.as_ref().components())
+        .take_while(|(x, y)| x == y)
+        .count()
+}
+
+// Picks between history entries that match the same name: the one sharing the longest path
+// prefix with `cwd` wins, with ties going to the highest frecency score.
+pub fn closest<T: AsRef<Path>>(cwd: T, candidates: &[(String, f64)]) -> Option<String> {
+    candidates
+        .iter()
+        .max_by(|(a, a_score), (b, b_score)| {
+            shared_depth(&cwd, a)
+                .cmp(&shared_depth(&cwd, b))
+                .then(a_score.total_cmp(b_score))
+                // Reversed so that on a complete tie the first candidate wins.
+                .then(b.cmp(a))
+        })
+        .map(|(location, _)| location.to_string())
+}
+
+// Entry point for `bhop record`.
+pub fn record<T: AsRef<Path>>(path: T) -> anyhow::Result<()> {
+    record_in(Environment::config_dir(), path)
+}
+
+// Records `path` into the database under `config_dir`.  Nothing is recorded until bhop has set
+// up its database there.
+pub fn record_in<T: AsRef<Path>, U: AsRef<Path>>(config_dir: T, path: U) -> anyhow::Result<()> {
+    let config_dir = config_dir.as_ref();
+    let db_path = Environment::at(config_dir).db_path;
+    if !db_path.exists() {
+        return Ok(());
+    }
+    // The hooks may run before any other command after an upgrade, so the schema is brought up
+    // to date here as well when it's behind.
+    let conn = Environment::connect(&db_path)?;
+    if !Environment::is_current(&conn)? {
+        Environment::migrate_database(&db_path)?;
+    }
+    let mut store = SqliteStore::new(conn);
+    record_change(&mut store, path, &HistoryRules::load(config_dir))
+}
+
+impl Hopper {
+    // `name` is matched against directory names, unless it contains a `/`, in which case it's a
+    // parent hint (e.g. `hop/src`) matched against the trailing segments of the location.
+    pub(crate) fn find_history(&self, name: &str) -> Option<(String, f64)> {
+        let hint = name.trim_end_matches('/');
+        let parent_hint = format!("*/{}", hint);
+        let candidates: Vec<(String, f64)> = self
+            .store
+            .history(chrono::Utc::now().timestamp())
+            .ok()?
+            .into_iter()
+            .filter(|entry| {
+                if name.contains('/') {
+                    glob_matches(&parent_hint, &entry.location)
+                        || glob_matches(hint, &entry.location)
+                } else {
+                    glob_matches(hint, &entry.name)
+                }
+            })
+            .map(|entry| (entry.location, entry.score))
+            .collect();
+        let location = closest(self.cwd(), &candidates)?;
+        candidates.into_iter().find(|(found, _)| *found == location)
+    }
+
+    // The history entries whose name or location match `filter`, with how often and how long
+    // before `now` they were last visited.
+    pub fn history_rows(&self, filter: Option<&str>, now: i64) -> anyhow::Result<Vec<[String; 3]>> {
+        let filter = self.search_filter(filter);
+        let mut entries = Vec::new();
+        for entry in self.store.history(now)? {
+            if !glob_matches(&filter, &entry.name) && !glob_matches(&filter, &entry.location) {
+                continue;
+            }
+            let last_visit = match entry.last_visit {
+                Some(last_visit) => format!(", last {}", ago(last_visit, now)),
+                None => "".to_string(),
+            };
+            entries.push([
+                entry.name,
+                entry.location,
+                format!(
+                    "{} visit{}{}",
+                    entry.usage,
+                    if entry.usage == 1 { "" } else { "s" },
+                    last_visit
+                ),
+            ]);
+        }
+        Ok(entries)
+    }
+
+    pub(crate) fn list_history(&self, filter: Option<String>) -> anyhow::Result<String> {
+        let entries = self.history_rows(filter.as_deref(), chrono::Utc::now().timestamp())?;
+        if entries.is_empty() {
+            return Ok("No history found.".to_string());
+        }
+        self.format_map(entries);
+        Ok("".to_string())
+    }
+
+    fn delete_history(&mut self, locations: &[String]) -> anyhow::Result<()> {
+        for location in locations {
+            self.store.delete_history(location)?;
+        }
+        Ok(())
+    }
+
+    fn report_deleted(locations: &[String], dry_run: bool) -> String {
+        if dry_run {
+            let mut listing = vec!["Would remove from history:".to_string()];
+            listing.extend(locations.iter().map(|l| format!("  {}", l)));
+            listing.join("\n")
+        } else {
+            format!("Removed {} history entries.", locations.len())
+        }
+    }
+
+    // Applies the current exclusion rules to everything recorded before they were set.
+    pub(crate) fn prune_history(&mut self, dry_run: bool) -> anyhow::Result<String> {
+        let rules = self.config.history_rules.clone();
+        let pruned: Vec<String> = self
+            .load_history()?
+            .into_iter()
+            .map(|[_, location]| location)
+            .filter(|location| !rules.allows(location))
+            .collect();
+        if pruned.is_empty() {
+            return Ok("Nothing in the history is excluded.".to_string());
+        }
+        if !dry_run {
+            self.transaction(|hopper| hopper.delete_history(&pruned))?;
+        }
+        Ok(Self::report_deleted(&pruned, dry_run))
+    }
+
+    // Patterns with a path separator are matched against the full location, anything else
+    // against the directory name.
+    pub(crate) fn forget_history(
+        &mut self,
+        pattern: String,
+        dry_run: bool,
+    ) -> anyhow::Result<String> {
+        let by_location = pattern.contains('/') || pattern.contains('\\');
+        let pattern = if by_location {
+            expand_home_in(&pattern, self.home_dir().as_deref())
+                .display()
+                .to_string()
+                .replace('\\', "/")
+        } else {
+            pattern
+        };
+        let matcher = Pattern::new(&pattern)?;
+        let forgotten: Vec<String> = self
+            .load_history()?
+            .into_iter()
+            .filter(|[name, location]| {
+                if by_location {
+                    matcher.matches(location)
+                } else {
+                    matcher.matches(name)
+                }
+            })
+            .map(|[_, location]| location)
+            .collect();
+        if forgotten.is_empty() {
+            return Ok(format!("Nothing in the history matches `{}`.", pattern));
+        }
+        if !dry_run {
+            self.transaction(|hopper| hopper.delete_history(&forgotten))?;
+        }
+        Ok(Self::report_deleted(&forgotten, dry_run))
+    }
+}
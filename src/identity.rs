@@ -0,0 +1,128 @@
+// Filesystem identity of a shortcut's target, used to find it again after it has been moved.  The
+// device and inode survive a `mv` on the same filesystem, and the name or the remote URL of a git
+// repository confirm that whatever now has them is really the same target.
+use serde_derive::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Directories that are never worth descending into when looking for a moved shortcut.
+pub(crate) const SKIPPED_DIRS: &[&str] = &["node_modules", "target", "__pycache__"];
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct Identity {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inode: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote: Option<String>,
+}
+
+impl Identity {
+    pub fn of<T: AsRef<Path>>(path: T) -> Self {
+        let path = path.as_ref();
+        let (device, inode) = match fs::metadata(path) {
+            Ok(metadata) => file_id(&metadata),
+            Err(_) => (None, None),
+        };
+        Identity {
+            device,
+            inode,
+            remote: git_remote(path),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inode.is_none() && self.remote.is_none()
+    }
+
+    pub fn same_file(&self, other: &Identity) -> bool {
+        self.inode.is_some() && self.device == other.device && self.inode == other.inode
+    }
+
+    // Breadth first search of `roots` for the target of `missing`, going at most `max_depth`
+    // directories deep.  Inodes get reused once a file is deleted, so the device and inode only
+    // count as a match along with a second sign that it's the same target: the same name or,
+    // for git repositories, the same remote.
+    pub fn find_in<T: AsRef<Path>>(
+        &self,
+        missing: &Path,
+        roots: &[T],
+        max_depth: usize,
+    ) -> Option<PathBuf> {
+        self.inode?;
+        let mut queue: VecDeque<(PathBuf, usize)> = roots
+            .iter()
+            .map(|root| (root.as_ref().to_path_buf(), 0))
+            .collect();
+        while let Some((dir, depth)) = queue.pop_front() {
+            let entries = match fs::read_dir(&dir) {
+                Ok(entries) => entries,
+                Err(_) => continue,
+            };
+            for entry in entries.flatten() {
+                let metadata = match fs::symlink_metadata(entry.path()) {
+                    Ok(metadata) => metadata,
+                    Err(_) => continue,
+                };
+                let (device, inode) = file_id(&metadata);
+                let found = Identity {
+                    device,
+                    inode,
+                    remote: None,
+                };
+                if self.same_file(&found)
+                    && (missing.file_name() == Some(entry.file_name().as_os_str())
+                        || (self.remote.is_some() && git_remote(entry.path()) == self.remote))
+                {
+                    return Some(entry.path());
+                }
+                let name = entry.file_name().to_string_lossy().to_string();
+                if metadata.is_dir()
+                    && depth + 1 < max_depth
+                    && !name.starts_with('.')
+                    && !SKIPPED_DIRS.contains(&name.as_str())
+                {
+                    queue.push_back((entry.path(), depth + 1));
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(unix)]
+fn file_id(metadata: &fs::Metadata) -> (Option<i64>, Option<i64>) {
+    use std::os::unix::fs::MetadataExt;
+    (Some(metadata.dev() as i64), Some(metadata.ino() as i64))
+}
+
+#[cfg(not(unix))]
+fn file_id(_metadata: &fs::Metadata) -> (Option<i64>, Option<i64>) {
+    (None, None)
+}
+
+// Reads the URL of the `origin` remote (or the first remote if there's no `origin`) straight out
+// of `.git/config` so there's no need to shell out to git.
+pub fn git_remote<T: AsRef<Path>>(path: T) -> Option<String> {
+    let config = fs::read_to_string(path.as_ref().join(".git").join("config")).ok()?;
+    let mut remotes = Vec::new();
+    let mut section = String::new();
+    for line in config.lines().map(|l| l.trim()) {
+        if line.starts_with('[') {
+            section = line.to_string();
+        } else if section.starts_with("[remote ") {
+            if let Some((key, value)) = line.split_once('=') {
+                if key.trim() == "url" {
+                    remotes.push((section.clone(), value.trim().to_string()));
+                }
+            }
+        }
+    }
+    remotes
+        .iter()
+        .find(|(section, _)| section == "[remote \"origin\"]")
+        .or(remotes.first())
+        .map(|(_, url)| url.to_string())
+}
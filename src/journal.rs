@@ -0,0 +1,73 @@
+// Journal of changes made to shortcuts so `hp undo` can put things back the way they were.  Every
+// mutation is recorded as a batch holding each touched shortcut as it was before and after the
+// change, identity and creation time included, with a missing entry meaning the shortcut didn't
+// exist.
+use crate::shortcuts::ShortcutEntry;
+use crate::store::JournalChange;
+use crate::Hopper;
+
+// Number of operations kept around for undoing.
+const JOURNAL_LENGTH: i64 = 50;
+
+impl Hopper {
+    pub(crate) fn load_shortcut(&self, name: &str) -> anyhow::Result<Option<ShortcutEntry>> {
+        self.store.shortcut(name)
+    }
+
+    // Runs `f` in a transaction and records the state of the shortcuts in `names` before and
+    // after it in the journal.
+    pub(crate) fn journaled<F>(
+        &mut self,
+        operation: &str,
+        names: Vec<String>,
+        f: F,
+    ) -> anyhow::Result<()>
+    where
+        F: FnOnce(&mut Self) -> anyhow::Result<()>,
+    {
+        self.transaction(|hopper| {
+            let mut before = Vec::new();
+            for name in names.iter() {
+                before.push(hopper.store.shortcut_record(name)?);
+            }
+            f(hopper)?;
+            let mut changes = Vec::new();
+            for (name, old_entry) in names.iter().zip(before) {
+                let new_entry = hopper.store.shortcut_record(name)?;
+                if old_entry != new_entry {
+                    changes.push(JournalChange {
+                        name: name.to_string(),
+                        old_entry,
+                        new_entry,
+                    });
+                }
+            }
+            hopper
+                .store
+                .push_journal(operation, &changes, JOURNAL_LENGTH)
+        })
+    }
+
+    pub(crate) fn undo(&mut self) -> anyhow::Result<String> {
+        let batch = match self.store.last_journal()? {
+            Some(batch) => batch,
+            None => return Ok("Nothing to undo.".to_string()),
+        };
+        self.transaction(|hopper| {
+            for change in batch.changes.iter() {
+                match &change.old_entry {
+                    Some(record) => hopper.store.restore_shortcut(&change.name, record)?,
+                    None => hopper.delete_shortcut(&change.name)?,
+                }
+            }
+            hopper.store.drop_journal(batch.batch)
+        })?;
+        let mut names: Vec<String> = batch.changes.into_iter().map(|c| c.name).collect();
+        names.sort();
+        Ok(format!(
+            "Undid {} of {}.",
+            batch.operation,
+            names.join(", ")
+        ))
+    }
+}
@@ -0,0 +1,261 @@
+// Configuration is read from several layers, each overriding the ones before it key by key:
+//
+//   1) the system wide file, `/etc/bhop/bhop.toml` (or `BHOP_SYSTEM_CONFIG`), as a shared baseline
+//   2) the user's own bhop.toml
+//   3) the `[settings]` and `[theme]` of the nearest project `.bhop` file, leaving out
+//      `default_editor` so a checked out project can't choose what gets run
+//   4) `BHOP_SETTING_<NAME>` environment variables, e.g. `BHOP_SETTING_ALWAYS_JUMP=true`
+//
+// Rules from `[[editor_rules]]` are the exception, with those of higher layers tried first rather
+// than replacing the lower ones.  A project file that can't be read is skipped with a warning
+// instead of stopping bhop from starting, since it may just hold groups of an older version.
+use crate::configs::ReadConfig;
+use crate::logging::trace;
+use crate::settings::{parse_value, SETTINGS};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml::value::Table;
+use toml::Value;
+
+const TABLES: &[&str] = &["settings", "editors", "editor_rules", "theme"];
+
+// What project files can't set, as they'd pick the commands bhop runs.
+const EXECUTABLE: &[(&str, Option<&str>)] = &[
+    ("settings", Some("default_editor")),
+    ("editors", None),
+    ("editor_rules", None),
+];
+
+pub fn system_config_path() -> PathBuf {
+    if cfg!(target_os = "windows") {
+        PathBuf::from(std::env::var("PROGRAMDATA").unwrap_or("C:\\ProgramData".to_string()))
+            .join("bhop")
+            .join("bhop.toml")
+    } else {
+        PathBuf::from("/etc/bhop/bhop.toml")
+    }
+}
+
+pub fn setting_var(key: &str) -> String {
+    format!("BHOP_SETTING_{}", key.to_uppercase())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Origin {
+    System(PathBuf),
+    User(PathBuf),
+    Project(PathBuf),
+    Env(String),
+}
+
+impl Origin {
+    pub fn kind(&self) -> &str {
+        match self {
+            Origin::System(_) => "system",
+            Origin::User(_) => "user",
+            Origin::Project(_) => "project",
+            Origin::Env(_) => "env",
+        }
+    }
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Origin::System(_) => write!(f, "system"),
+            Origin::User(_) => write!(f, "user"),
+            Origin::Project(path) => write!(f, "project {}", path.display()),
+            Origin::Env(name) => write!(f, "env {}", name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigLayer {
+    pub origin: Origin,
+    // Only the configuration tables of the layer, so project files can hold groups as well.
+    pub document: Table,
+    // What was left out of the layer and why, for the user to fix.
+    pub warning: Option<String>,
+}
+
+impl ConfigLayer {
+    pub fn parse(origin: Origin, toml: &str) -> anyhow::Result<Self> {
+        let mut document: Table = toml::from_str::<Table>(toml)?
+            .into_iter()
+            .filter(|(key, _)| TABLES.contains(&key.as_str()))
+            .collect();
+        Value::Table(document.clone()).try_into::<ReadConfig>()?;
+        let mut warning = None;
+        if let Origin::Project(path) = &origin {
+            let ignored: Vec<String> = EXECUTABLE
+                .iter()
+                .filter_map(|(table, key)| match key {
+                    Some(key) => document
+                        .get_mut(*table)
+                        .and_then(|t| t.as_table_mut())
+                        .and_then(|t| t.remove(*key))
+                        .map(|_| format!("{}.{}", table, key)),
+                    None => document.remove(*table).map(|_| format!("[{}]", table)),
+                })
+                .collect();
+            if !ignored.is_empty() {
+                warning = Some(format!(
+                    "Ignoring {} in {}, only bhop.toml can choose the editors that are run.",
+                    ignored.join(", "),
+                    path.display()
+                ));
+            }
+        }
+        Ok(ConfigLayer {
+            origin,
+            document,
+            warning,
+        })
+    }
+
+    // Missing files are skipped, while broken ones are an error naming the file.
+    fn read(origin: Origin, path: &Path) -> anyhow::Result<Option<Self>> {
+        match fs::read_to_string(path) {
+            Ok(toml) => {
+                trace!(
+                    1,
+                    "reading {} configuration from {}",
+                    origin.kind(),
+                    path.display()
+                );
+                Self::parse(origin, &toml)
+                    .map(Some)
+                    .map_err(|e| anyhow::anyhow!("Unable to read {}: {}", path.display(), e))
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                trace!(
+                    1,
+                    "no {} configuration at {}",
+                    origin.kind(),
+                    path.display()
+                );
+                Ok(None)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn from_env<F: Fn(&str) -> Option<String>>(var: F) -> anyhow::Result<Vec<Self>> {
+        let mut layers = Vec::new();
+        for key in SETTINGS {
+            let name = setting_var(key);
+            if let Some(value) = var(&name) {
+                trace!(1, "{} overrides settings.{}", name, key);
+                let value = parse_value("settings", key, &value)
+                    .map_err(|e| anyhow::anyhow!("Unable to read {}: {}", name, e))?;
+                let mut settings = Table::new();
+                settings.insert(key.to_string(), value);
+                let mut document = Table::new();
+                document.insert("settings".to_string(), Value::Table(settings));
+                layers.push(ConfigLayer {
+                    origin: Origin::Env(name),
+                    document,
+                    warning: None,
+                });
+            }
+        }
+        Ok(layers)
+    }
+
+    pub fn contains(&self, table: &str, key: &str) -> bool {
+        self.document
+            .get(table)
+            .and_then(|t| t.as_table())
+            .is_some_and(|t| t.contains_key(key))
+    }
+}
+
+// Every layer that's present, from lowest to highest precedence.  `var` looks up environment
+// variables, which also allow `BHOP_SYSTEM_CONFIG` to move the system wide file.
+pub fn load<F: Fn(&str) -> Option<String>>(
+    user_path: &Path,
+    cwd: &Path,
+    project_configs: &str,
+    var: F,
+) -> anyhow::Result<Vec<ConfigLayer>> {
+    let mut layers = file_layers(user_path, &var)?;
+    if let Some(project) = cwd
+        .ancestors()
+        .map(|dir| dir.join(project_configs))
+        .find(|path| path.is_file())
+    {
+        let origin = Origin::Project(project.clone());
+        match ConfigLayer::read(origin.clone(), &project) {
+            Ok(layer) => layers.extend(layer),
+            Err(e) => layers.push(ConfigLayer {
+                origin,
+                document: Table::new(),
+                warning: Some(format!("{}, skipping it.", e)),
+            }),
+        }
+    }
+    layers.extend(ConfigLayer::from_env(var)?);
+    Ok(layers)
+}
+
+// The layers that don't depend on the working directory, for when looking for a project file
+// isn't worth it.
+pub fn load_global<F: Fn(&str) -> Option<String>>(
+    user_path: &Path,
+    var: F,
+) -> anyhow::Result<Vec<ConfigLayer>> {
+    let mut layers = file_layers(user_path, &var)?;
+    layers.extend(ConfigLayer::from_env(var)?);
+    Ok(layers)
+}
+
+fn file_layers<F: Fn(&str) -> Option<String>>(
+    user_path: &Path,
+    var: &F,
+) -> anyhow::Result<Vec<ConfigLayer>> {
+    let mut layers = Vec::new();
+    let system_path = var("BHOP_SYSTEM_CONFIG")
+        .map(PathBuf::from)
+        .unwrap_or_else(system_config_path);
+    layers.extend(ConfigLayer::read(
+        Origin::System(system_path.clone()),
+        &system_path,
+    )?);
+    layers.extend(ConfigLayer::read(
+        Origin::User(user_path.to_path_buf()),
+        user_path,
+    )?);
+    Ok(layers)
+}
+
+pub fn merge(layers: &[ConfigLayer]) -> anyhow::Result<ReadConfig> {
+    let mut merged = Table::new();
+    for layer in layers {
+        for (name, value) in &layer.document {
+            match (merged.get_mut(name), value) {
+                (Some(Value::Table(table)), Value::Table(overrides)) => {
+                    table.extend(overrides.clone());
+                }
+                (Some(Value::Array(rules)), Value::Array(first)) => {
+                    let lower = std::mem::replace(rules, first.clone());
+                    rules.extend(lower);
+                }
+                _ => {
+                    merged.insert(name.to_string(), value.clone());
+                }
+            }
+        }
+    }
+    Ok(Value::Table(merged).try_into()?)
+}
+
+// The layer `key` of `table` was last set in, if any.
+pub fn origin<'a>(layers: &'a [ConfigLayer], table: &str, key: &str) -> Option<&'a Origin> {
+    layers
+        .iter()
+        .rev()
+        .find(|layer| layer.contains(table, key))
+        .map(|layer| &layer.origin)
+}
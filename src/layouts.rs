@@ -0,0 +1,302 @@
+// Terminal multiplexer layouts built from `.bhop` groups.  A layout group doesn't run a command
+// itself, it references other groups in the same `.bhop` file and opens each of them in its own
+// named window (or pane) of a tmux session or zellij layout rooted at the group's directory.
+use std::path::Path;
+use std::process::{Command, Stdio};
+use toml::{value::Table, Value};
+
+// What `layout = "..."` accepts.
+const MULTIPLEXERS: &[&str] = &["tmux", "zellij"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Multiplexer {
+    Tmux,
+    Zellij,
+}
+
+impl Multiplexer {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "tmux" => Some(Multiplexer::Tmux),
+            "zellij" => Some(Multiplexer::Zellij),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layout {
+    pub multiplexer: Multiplexer,
+    pub session: Option<String>,
+    pub windows: Vec<String>,
+    pub panes: bool,
+}
+
+impl Layout {
+    // Nothing when the group isn't a layout, and an error when it names a multiplexer bhop
+    // doesn't know.
+    pub fn from_table(table: &Table) -> anyhow::Result<Option<Self>> {
+        let name = match table.get("layout") {
+            Some(name) => name,
+            None => return Ok(None),
+        };
+        let multiplexer = match name.as_str().and_then(Multiplexer::from_name) {
+            Some(multiplexer) => multiplexer,
+            None => anyhow::bail!(
+                "Unknown layout {}, expected one of: {}.",
+                name,
+                MULTIPLEXERS.join(", ")
+            ),
+        };
+        let session = table
+            .get("session")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string());
+        let windows = match table.get("windows") {
+            Some(Value::Array(windows)) => windows
+                .iter()
+                .filter_map(|v| v.as_str())
+                .map(|s| s.to_string())
+                .collect(),
+            _ => Vec::new(),
+        };
+        let panes = table
+            .get("panes")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        Ok(Some(Layout {
+            multiplexer,
+            session,
+            windows,
+            panes,
+        }))
+    }
+}
+
+// tmux refuses session names containing periods or colons, and in window names they'd break the
+// `session:window` targets.
+fn target_name(name: &str) -> String {
+    name.replace(['.', ':'], "_")
+}
+
+// A window (or pane) of a layout with the command of the group it references already resolved.
+// Windows that don't reference a group with a command just open a shell.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Window {
+    pub name: String,
+    pub cmd: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Session {
+    pub name: String,
+    pub dir: String,
+    pub windows: Vec<Window>,
+    pub panes: bool,
+}
+
+impl Session {
+    pub fn new(layout: &Layout, dir: &str, windows: Vec<Window>) -> Self {
+        let name = match &layout.session {
+            Some(name) => name.to_string(),
+            None => Path::new(dir)
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or("bhop")
+                .to_string(),
+        };
+        let windows = if windows.is_empty() {
+            vec![Window {
+                name: "shell".to_string(),
+                cmd: None,
+            }]
+        } else {
+            windows
+                .into_iter()
+                .map(|window| Window {
+                    name: target_name(&window.name),
+                    ..window
+                })
+                .collect()
+        };
+        Session {
+            name: target_name(&name),
+            dir: dir.to_string(),
+            windows,
+            panes: layout.panes,
+        }
+    }
+
+    pub fn tmux_setup(&self) -> Vec<Vec<String>> {
+        let mut cmds = Vec::new();
+        for (i, window) in self.windows.iter().enumerate() {
+            let target = if self.panes {
+                self.name.clone()
+            } else {
+                format!("{}:{}", self.name, window.name)
+            };
+            let create = if i == 0 {
+                vec![
+                    "new-session",
+                    "-d",
+                    "-s",
+                    &self.name,
+                    "-n",
+                    &window.name,
+                    "-c",
+                    &self.dir,
+                ]
+            } else if self.panes {
+                vec!["split-window", "-t", &self.name, "-c", &self.dir]
+            } else {
+                vec![
+                    "new-window",
+                    "-t",
+                    &self.name,
+                    "-n",
+                    &window.name,
+                    "-c",
+                    &self.dir,
+                ]
+            };
+            cmds.push(tmux(&create));
+            if let Some(cmd) = &window.cmd {
+                cmds.push(tmux(&["send-keys", "-t", &target, cmd, "Enter"]));
+            }
+        }
+        if self.panes {
+            cmds.push(tmux(&["select-layout", "-t", &self.name, "tiled"]));
+        } else {
+            let first = format!("{}:{}", self.name, self.windows[0].name);
+            cmds.push(tmux(&["select-window", "-t", &first]));
+        }
+        cmds
+    }
+
+    pub fn tmux_attach(&self, nested: bool) -> Vec<String> {
+        // Attaching from inside of tmux would nest sessions, so switch the client instead.
+        let cmd = if nested {
+            "switch-client"
+        } else {
+            "attach-session"
+        };
+        tmux(&[cmd, "-t", &self.name])
+    }
+
+    pub fn zellij_layout(&self) -> String {
+        let panes: Vec<String> = self
+            .windows
+            .iter()
+            .map(|window| match &window.cmd {
+                Some(cmd) => format!(
+                    "pane name={} command=\"sh\" {{\n            args \"-c\" {}\n        }}",
+                    kdl_str(&window.name),
+                    kdl_str(cmd)
+                ),
+                None => format!("pane name={}", kdl_str(&window.name)),
+            })
+            .collect();
+        let tabs = if self.panes {
+            vec![format!(
+                "    tab name={} cwd={} {{\n        {}\n    }}",
+                kdl_str(&self.name),
+                kdl_str(&self.dir),
+                panes.join("\n        ")
+            )]
+        } else {
+            self.windows
+                .iter()
+                .zip(panes)
+                .map(|(window, pane)| {
+                    format!(
+                        "    tab name={} cwd={} {{\n        {}\n    }}",
+                        kdl_str(&window.name),
+                        kdl_str(&self.dir),
+                        pane
+                    )
+                })
+                .collect()
+        };
+        format!("layout {{\n{}\n}}\n", tabs.join("\n"))
+    }
+
+    pub fn zellij_launch(&self, layout_file: &str, exists: bool) -> Vec<String> {
+        if exists {
+            vec![
+                "zellij".to_string(),
+                "attach".to_string(),
+                self.name.clone(),
+            ]
+        } else {
+            vec![
+                "zellij".to_string(),
+                "--session".to_string(),
+                self.name.clone(),
+                "--layout".to_string(),
+                layout_file.to_string(),
+            ]
+        }
+    }
+}
+
+fn tmux(args: &[&str]) -> Vec<String> {
+    let mut cmd = vec!["tmux".to_string()];
+    cmd.extend(args.iter().map(|s| s.to_string()));
+    cmd
+}
+
+fn kdl_str(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+// Renders an argument vector as a single POSIX shell command line.
+pub fn render(argv: &[String]) -> String {
+    argv.iter()
+        .map(|arg| {
+            if !arg.is_empty()
+                && arg
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || "-_./=:@+,".contains(c))
+            {
+                arg.to_string()
+            } else {
+                format!("'{}'", arg.replace('\'', "'\\''"))
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+pub fn run(argv: &[String]) -> anyhow::Result<()> {
+    let status = Command::new(&argv[0])
+        .args(&argv[1..])
+        .stdout(Stdio::null())
+        .status()?;
+    if !status.success() {
+        anyhow::bail!("`{}` exited with {}", render(argv), status);
+    }
+    Ok(())
+}
+
+pub fn tmux_session_exists(name: &str) -> bool {
+    Command::new("tmux")
+        .args(["has-session", "-t", &format!("={}", name)])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map(|s| s.success())
+        .unwrap_or(false)
+}
+
+pub fn zellij_session_exists(name: &str) -> bool {
+    match Command::new("zellij")
+        .args(["list-sessions", "--short", "--no-formatting"])
+        .stderr(Stdio::null())
+        .output()
+    {
+        Ok(out) => String::from_utf8_lossy(&out.stdout)
+            .lines()
+            .any(|line| line.trim() == name),
+        Err(_) => false,
+    }
+}
@@ -1,12 +1,38 @@
+pub mod api;
 pub mod args;
+pub mod builder;
+pub mod completions;
 pub mod configs;
+pub mod doctor;
 pub mod groups;
+pub mod history;
+pub mod identity;
+mod journal;
+pub mod layers;
+pub mod layouts;
+pub mod logging;
 pub mod metadata;
-use colored::Colorize;
+pub mod project;
+mod relocate;
+mod repos;
+pub mod search;
+pub mod settings;
+#[path = "../runners/shells.rs"]
+mod shells;
+pub mod shortcuts;
+mod stats;
+pub mod store;
+pub mod theme;
+pub mod views;
 use glob::glob;
+use logging::trace;
+use std::collections::{BTreeMap, HashMap};
 use std::env::var;
 use std::fs;
+use std::io::IsTerminal;
 use std::path::{Path, PathBuf};
+use std::process::Command;
+use theme::{ColorDepth, ColorMode, Style};
 
 pub fn sanitize<T: AsRef<Path>>(p: T) -> anyhow::Result<String> {
     // Back slashes in Windows paths create so many headaches.  Since Windows accepts forward
@@ -20,22 +46,232 @@ pub fn sanitize<T: AsRef<Path>>(p: T) -> anyhow::Result<String> {
     Ok(location.replace('\\', "/").replace("//?/", ""))
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Position {
+    pub line: usize,
+    pub col: Option<usize>,
+}
+
+// Splits a trailing `:line` or `:line:col` off of a reference, as used by compiler and grep
+// output (e.g. `src/lib.rs:120:4`).  Only numeric suffixes are stripped so Windows drive letters
+// are left alone.
+pub fn split_position(reference: &str) -> Option<(String, Position)> {
+    let mut parts = reference.rsplitn(3, ':');
+    let last = parts.next()?.parse::<usize>().ok()?;
+    let rest = parts.next()?;
+    match (rest.parse::<usize>(), parts.next()) {
+        (Ok(line), Some(base)) if !base.is_empty() => Some((
+            base.to_string(),
+            Position {
+                line,
+                col: Some(last),
+            },
+        )),
+        _ => {
+            let base = reference.rsplit_once(':')?.0;
+            if base.is_empty() {
+                return None;
+            }
+            Some((
+                base.to_string(),
+                Position {
+                    line: last,
+                    col: None,
+                },
+            ))
+        }
+    }
+}
+
+// Shortcuts and history are looked up by glob, with an invalid pattern matching nothing.
+pub fn glob_matches(pattern: &str, text: &str) -> bool {
+    glob::Pattern::new(pattern)
+        .map(|p| p.matches(text))
+        .unwrap_or(false)
+}
+
+// Entries in name order, or best match first for fuzzy searches.
+fn ranked(matcher: &search::Matcher, mut found: Vec<(i64, api::Entry)>) -> Vec<api::Entry> {
+    if matcher.ranked() {
+        found.sort_by(|(a, _), (b, _)| b.cmp(a));
+    }
+    found.into_iter().map(|(_, entry)| entry).collect()
+}
+
+// Fills an editor command template with the file and, if present, its line and column.  Editors
+// without a `{}` placeholder have the file appended to the end of the command.
+pub fn fill_editor(editor: &str, f: &str, position: Option<&Position>) -> String {
+    let line = position.map(|p| p.line).unwrap_or(1);
+    let col = position.and_then(|p| p.col).unwrap_or(1);
+    let editor = editor
+        .replace("{line}", &line.to_string())
+        .replace("{col}", &col.to_string());
+    if editor.contains("{}") {
+        editor.replace("{}", f)
+    } else {
+        format!("{} {}", editor, f)
+    }
+}
+
+// Options for narrowing down and displaying `hp list` results.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ListOptions {
+    pub tag: Option<String>,
+    pub mode: search::SearchMode,
+    pub view: views::ListView,
+    // Left as `None` to keep the order entries were found in, name order or the best matches
+    // first for fuzzy searches.
+    pub sort: Option<views::SortBy>,
+}
+
 pub struct Hopper {
     pub config: configs::Configs,
-    pub db: sqlite::Connection,
+    pub store: Box<dyn store::Store>,
     pub env: metadata::Environment,
+    // Problems with the configuration layers that were worked around, like a broken `.bhop`.
+    pub warnings: Vec<String>,
+    // Stand-ins for the process's working directory and environment, see `HopperBuilder`.
+    cwd: Option<PathBuf>,
+    vars: Option<HashMap<String, String>>,
 }
 
 impl Hopper {
     pub fn new() -> anyhow::Result<Self> {
-        let env = metadata::Environment::new();
-        let config = configs::Configs::new(&env.config_path);
-        let conn = sqlite::open(&env.db_path)?;
-        Ok(Hopper {
-            config,
-            db: conn,
-            env,
-        })
+        Self::builder().build()
+    }
+
+    pub fn builder() -> builder::HopperBuilder {
+        builder::HopperBuilder::default()
+    }
+
+    fn var(&self, key: &str) -> Option<String> {
+        match &self.vars {
+            Some(vars) => vars.get(key).cloned(),
+            None => var(key).ok(),
+        }
+    }
+
+    // The home directory from the instance's environment, or the user's one when it has none of
+    // its own.
+    pub(crate) fn home_dir(&self) -> Option<PathBuf> {
+        match &self.vars {
+            Some(_) => self
+                .var("HOME")
+                .or_else(|| self.var("USERPROFILE"))
+                .map(PathBuf::from),
+            None => dirs::home_dir(),
+        }
+    }
+
+    // `EDITOR` and the home directory for the configuration defaults, from the instance's
+    // environment.
+    pub(crate) fn config_env(&self) -> configs::ConfigEnv {
+        configs::ConfigEnv {
+            editor: self.var("EDITOR"),
+            home: self.home_dir(),
+        }
+    }
+
+    fn separator(&self) -> String {
+        self.var("BHOP_CMD_SEPARATOR").unwrap_or("|".to_string())
+    }
+
+    fn project_configs(&self) -> String {
+        self.var("BHOP_PROJECT_CONFIGS")
+            .unwrap_or(".bhop".to_string())
+    }
+
+    pub(crate) fn config_layers(&self) -> anyhow::Result<Vec<layers::ConfigLayer>> {
+        layers::load(
+            &self.env.config_path,
+            &self.cwd(),
+            &self.project_configs(),
+            |key| self.var(key),
+        )
+    }
+
+    // bhop.log goes in the state directory, or the configuration folder on systems without one.
+    pub(crate) fn log_path(&self) -> PathBuf {
+        let state_dir = match self.var("BHOP_STATE_DIRECTORY") {
+            Some(dir) => PathBuf::from(dir),
+            None => match dirs::state_dir() {
+                Some(dir) => dir.join("bhop"),
+                None => self
+                    .env
+                    .config_path
+                    .parent()
+                    .map(|dir| dir.to_path_buf())
+                    .unwrap_or_default(),
+            },
+        };
+        state_dir.join("bhop.log")
+    }
+
+    // Whether stdout is a terminal.  The runners capture the output of the first call to act on
+    // it, so they set `BHOP_TERMINAL` when what they print goes to one, while commands they hand
+    // back (like listings) write to the terminal or pipe directly.
+    fn on_terminal(&self) -> bool {
+        std::io::stdout().is_terminal() || self.var("BHOP_TERMINAL").is_some_and(|v| v == "1")
+    }
+
+    // Colors are only used when printing to a terminal, unless `--color` or the `color` setting
+    // say otherwise, and `NO_COLOR` turns them off while left on auto.
+    pub(crate) fn color_depth(&self) -> Option<ColorDepth> {
+        let term = self.var("TERM").unwrap_or_default();
+        let enabled = match self.config.color {
+            ColorMode::Always => true,
+            ColorMode::Never => false,
+            ColorMode::Auto => {
+                self.var("NO_COLOR").is_none_or(|v| v.is_empty())
+                    && term != "dumb"
+                    && self.on_terminal()
+            }
+        };
+        if !enabled {
+            return None;
+        }
+        let colorterm = self.var("COLORTERM").unwrap_or_default();
+        if colorterm == "truecolor" || colorterm == "24bit" || self.var("WT_SESSION").is_some() {
+            Some(ColorDepth::TrueColor)
+        } else if term.contains("256") {
+            Some(ColorDepth::Ansi256)
+        } else {
+            Some(ColorDepth::Ansi16)
+        }
+    }
+
+    // `text` in `style`, if colors are used.
+    pub(crate) fn paint(&self, text: &str, style: Style) -> String {
+        style.paint(text, self.color_depth())
+    }
+
+    // Listings are fitted to `COLUMNS`, or else the width of the terminal they're printed to.
+    fn terminal_width(&self) -> Option<usize> {
+        if let Some(columns) = self.var("COLUMNS").and_then(|c| c.parse().ok()) {
+            return Some(columns);
+        }
+        if cfg!(windows) || !self.on_terminal() {
+            return None;
+        }
+        let tty = fs::File::open("/dev/tty").ok()?;
+        let output = Command::new("stty").arg("size").stdin(tty).output().ok()?;
+        String::from_utf8_lossy(&output.stdout)
+            .split_whitespace()
+            .nth(1)?
+            .parse()
+            .ok()
+    }
+
+    fn cwd(&self) -> PathBuf {
+        match &self.cwd {
+            Some(cwd) => cwd.clone(),
+            None => std::env::current_dir().unwrap_or_default(),
+        }
+    }
+
+    // Relative paths are taken from the working directory of the instance.
+    fn absolute<T: AsRef<Path>>(&self, path: T) -> PathBuf {
+        self.cwd().join(path)
     }
 
     fn locate(&self) -> anyhow::Result<String> {
@@ -58,7 +294,7 @@ impl Hopper {
         let cmd = format!(
             "{}{}{}",
             move_dir,
-            var("BHOP_CMD_SEPARATOR").unwrap_or("|".to_string()),
+            self.separator(),
             self.map_editor(config_path, None)?
         );
         Ok(cmd)
@@ -66,27 +302,27 @@ impl Hopper {
 
     fn passthrough(&self, cmd: String) -> anyhow::Result<String> {
         let bhop_exe = sanitize(std::env::current_exe()?)?;
-        Ok(format!(
-            ".{}{} {}",
-            var("BHOP_CMD_SEPARATOR").unwrap_or("|".to_string()),
-            bhop_exe,
-            cmd
-        ))
+        Ok(format!(".{}{} {}", self.separator(), bhop_exe, cmd))
     }
 
-    fn map_editor(&self, f: String, ext: Option<String>) -> anyhow::Result<String> {
-        let editor = match ext {
-            None => self.config.default_editor.to_string(),
-            Some(ext) => match &self.config.editors.get(&ext) {
-                Some(special_editor) => special_editor.to_string(),
-                None => self.config.default_editor.to_string(),
-            },
-        };
-        if editor.contains("{}") {
-            Ok(editor.replace("{}", &f))
-        } else {
-            Ok(format!("{} {}", editor, f))
-        }
+    fn map_editor(&self, f: String, position: Option<&Position>) -> anyhow::Result<String> {
+        let (editor, source) = self.config.editor_for(&f);
+        trace!(1, "opening {} with `{}` from {}", f, editor, source);
+        Ok(fill_editor(&editor, &f, position))
+    }
+
+    fn which_editor(&self, reference: String) -> anyhow::Result<String> {
+        let path = self
+            .grab(reference.clone())
+            .unwrap_or(PathBuf::from(&reference));
+        let (editor, source) = self.config.editor_for(&path);
+        Ok(format!(
+            "{} {}\n{} {}",
+            self.paint("editor:", Style::named("bright_white").bold()),
+            editor,
+            self.paint("source:", Style::named("bright_white").bold()),
+            source
+        ))
     }
 
     fn add_shortcut<T: AsRef<Path>>(
@@ -104,288 +340,598 @@ impl Hopper {
                 .ok_or(anyhow::anyhow!("Unable to extract file name for shortcut"))?
                 .to_string(),
         };
-        let path_as_string = sanitize(path)?;
-        let query = format!(
-            "INSERT OR REPLACE INTO shortcuts (name, location) VALUES (\"{}\", \"{}\")",
-            name, &path_as_string
-        );
-        self.db.execute(query)?;
-        Ok(())
+        let location = sanitize(self.absolute(path))?;
+        // The description and tags of an existing shortcut survive it being pointed somewhere
+        // else.
+        self.journaled("add", vec![name.clone()], |hopper| {
+            let mut entry = hopper.store.shortcut(&name)?.unwrap_or_default();
+            entry.location = location;
+            hopper.store.write_shortcut(&name, &entry)?;
+            hopper.record_identity(&name)
+        })
+    }
+
+    fn matching_shortcuts(&self, pattern: &str) -> anyhow::Result<Vec<[String; 2]>> {
+        Ok(self
+            .store
+            .shortcuts()?
+            .into_iter()
+            .filter(|(name, _)| glob_matches(pattern, name))
+            .map(|(name, entry)| [name, entry.location])
+            .collect())
+    }
+
+    fn remove_shortcut(
+        &mut self,
+        pattern: String,
+        dry_run: bool,
+        confirmed: bool,
+    ) -> anyhow::Result<String> {
+        let matches = self.matching_shortcuts(&pattern)?;
+        if matches.is_empty() {
+            return Ok(format!("No shortcuts match `{}`.", pattern));
+        }
+        let listing = matches
+            .iter()
+            .map(|[name, location]| format!("  {} -> {}", name, location))
+            .collect::<Vec<String>>()
+            .join("\n");
+        if dry_run {
+            return Ok(format!("Would remove:\n{}", listing));
+        }
+        // Globs that hit more than one shortcut need a confirmation, which is asked on stderr
+        // since stdout is captured by the runner.
+        if matches.len() > 1 && !confirmed {
+            eprint!(
+                "About to remove {} shortcuts:\n{}\nProceed? [y/N] ",
+                matches.len(),
+                listing
+            );
+            if !proceed::proceed() {
+                return Ok("Nothing removed.".to_string());
+            }
+        }
+        let names: Vec<String> = matches.into_iter().map(|[name, _]| name).collect();
+        self.journaled("remove", names.clone(), |hopper| {
+            for name in names.iter() {
+                hopper.delete_shortcut(name)?;
+            }
+            Ok(())
+        })?;
+        Ok("".to_string())
+    }
+
+    fn shortcut_exists(&self, name: &str) -> anyhow::Result<bool> {
+        Ok(self.store.shortcut(name)?.is_some())
     }
 
-    fn remove_shortcut(&mut self, name: String) -> anyhow::Result<()> {
-        let query = format!("DELETE FROM shortcuts WHERE name GLOB '{}'", name);
-        self.db.execute(query)?;
+    // Applies `f` to the shortcut with `name`, if there is one.
+    fn update_shortcut<F>(&mut self, name: &str, f: F) -> anyhow::Result<()>
+    where
+        F: FnOnce(&mut shortcuts::ShortcutEntry),
+    {
+        if let Some(mut entry) = self.store.shortcut(name)? {
+            f(&mut entry);
+            self.store.write_shortcut(name, &entry)?;
+        }
         Ok(())
     }
 
-    fn find_shortcut(&mut self, name: &str) -> Option<PathBuf> {
-        let query = format!(
-            "SELECT location FROM shortcuts WHERE name GLOB '{}' ORDER BY name",
-            name
-        );
-        match self.db.prepare(query.as_str()) {
-            Ok(mut statement) => {
-                if let Ok(sqlite::State::Row) = statement.next() {
-                    let location = statement.read::<String, _>("location");
-                    match location {
-                        Ok(location) => Some(PathBuf::from(location)),
-                        Err(_) => None,
-                    }
-                } else {
-                    None
-                }
+    fn tag_shortcut(&mut self, name: String, tags: Vec<String>) -> anyhow::Result<String> {
+        if !self.shortcut_exists(&name)? {
+            anyhow::bail!("No shortcut named `{}` to tag.", name);
+        }
+        self.journaled("tag", vec![name.clone()], |hopper| {
+            hopper.update_shortcut(&name, |entry| {
+                entry.tags.extend(tags);
+                entry.tags.sort();
+                entry.tags.dedup();
+            })
+        })?;
+        Ok("".to_string())
+    }
+
+    fn untag_shortcut(&mut self, name: String, tags: Vec<String>) -> anyhow::Result<String> {
+        self.journaled("untag", vec![name.clone()], |hopper| {
+            hopper.update_shortcut(&name, |entry| entry.tags.retain(|tag| !tags.contains(tag)))
+        })?;
+        Ok("".to_string())
+    }
+
+    fn describe_shortcut(&mut self, name: String, description: String) -> anyhow::Result<String> {
+        if !self.shortcut_exists(&name)? {
+            anyhow::bail!("No shortcut named `{}` to describe.", name);
+        }
+        self.journaled("describe", vec![name.clone()], |hopper| {
+            hopper.update_shortcut(&name, |entry| {
+                entry.description = (!description.is_empty()).then_some(description)
+            })
+        })?;
+        Ok("".to_string())
+    }
+
+    fn rename_shortcut(&mut self, old: String, new: String) -> anyhow::Result<String> {
+        if !self.shortcut_exists(&old)? {
+            anyhow::bail!("No shortcut named `{}` to rename.", old);
+        }
+        if self.shortcut_exists(&new)? {
+            anyhow::bail!("A shortcut named `{}` already exists.", new);
+        }
+        self.journaled("rename", vec![old.clone(), new.clone()], |hopper| {
+            hopper.store.rename_shortcut(&old, &new)
+        })?;
+        Ok("".to_string())
+    }
+
+    fn retarget_shortcut(&mut self, name: String, path: String) -> anyhow::Result<String> {
+        if !self.shortcut_exists(&name)? {
+            anyhow::bail!("No shortcut named `{}` to retarget.", name);
+        }
+        let location = sanitize(self.absolute(&path))?;
+        self.journaled("retarget", vec![name.clone()], |hopper| {
+            hopper.update_shortcut(&name, |entry| entry.location = location)?;
+            hopper.record_identity(&name)
+        })?;
+        Ok("".to_string())
+    }
+
+    // Runs `f` inside of a single transaction, rolling everything back if it fails.
+    fn transaction<F>(&mut self, f: F) -> anyhow::Result<()>
+    where
+        F: FnOnce(&mut Self) -> anyhow::Result<()>,
+    {
+        self.store.begin()?;
+        match f(self) {
+            Ok(_) => self.store.commit(),
+            Err(e) => {
+                self.store.rollback()?;
+                Err(e)
             }
-            Err(_) => None,
         }
     }
 
-    fn find_history(&mut self, name: &str) -> Option<(String, i64)> {
-        let query = format!(
-            "SELECT location, usage FROM history WHERE name GLOB '{}' ORDER BY usage DESC",
-            name
-        );
-        match self.db.prepare(query.as_str()) {
-            Ok(mut statement) => {
-                if let Ok(sqlite::State::Row) = statement.next() {
-                    let location = statement.read::<String, _>("location");
-                    let usage = statement.read::<i64, _>("usage").unwrap_or(0);
-                    match location {
-                        Ok(location) => Some((location, usage)),
-                        Err(_) => None,
+    fn load_shortcuts(&self) -> anyhow::Result<BTreeMap<String, shortcuts::ShortcutEntry>> {
+        self.store.shortcuts()
+    }
+
+    fn write_shortcut(
+        &mut self,
+        name: &str,
+        entry: &shortcuts::ShortcutEntry,
+    ) -> anyhow::Result<()> {
+        self.store.write_shortcut(name, entry)?;
+        self.record_identity(name)
+    }
+
+    fn delete_shortcut(&mut self, name: &str) -> anyhow::Result<()> {
+        self.store.delete_shortcut(name)
+    }
+
+    // Writes every shortcut to a temporary TOML file, waits for the user to edit it and then
+    // applies the differences back to the database.  This runs as a passthrough so the editor
+    // gets the terminal to itself.
+    fn edit_shortcuts(&mut self) -> anyhow::Result<String> {
+        let original = self.load_shortcuts()?;
+        let file = tempfile::Builder::new()
+            .prefix("bhop-shortcuts-")
+            .suffix(".toml")
+            .tempfile()?;
+        let contents = shortcuts::ShortcutFile {
+            shortcuts: original.clone(),
+        }
+        .to_toml()?;
+        fs::write(file.path(), &contents)?;
+        let editor = fill_editor(&self.config.default_editor, &sanitize(file.path())?, None);
+        let status = if cfg!(windows) {
+            Command::new("cmd").arg("/C").arg(&editor).status()?
+        } else {
+            Command::new("sh").arg("-c").arg(&editor).status()?
+        };
+        if !status.success() {
+            anyhow::bail!("Editor exited with {}, no changes applied.", status);
+        }
+        let edited = match shortcuts::ShortcutFile::from_toml(&fs::read_to_string(file.path())?) {
+            Ok(edited) => edited.shortcuts,
+            Err(e) => {
+                // Hang on to the file so the edits aren't lost.
+                let (_, kept) = file.keep()?;
+                anyhow::bail!(
+                    "Unable to parse edited shortcuts ({}), no changes applied.  Edits saved to {}",
+                    e,
+                    kept.display()
+                );
+            }
+        };
+        let changes = shortcuts::diff(&original, &edited);
+        let mut conflicts = Vec::new();
+        let names = changes.iter().map(|c| c.name().to_string()).collect();
+        self.journaled("edit-shortcuts", names, |hopper| {
+            // Anything another shell changed while the file was open is left alone.
+            let current = hopper.load_shortcuts()?;
+            for change in changes.iter() {
+                if current.get(change.name()) != original.get(change.name()) {
+                    conflicts.push(change.name().to_string());
+                    continue;
+                }
+                match change {
+                    shortcuts::Change::Add(name, entry)
+                    | shortcuts::Change::Update(name, entry) => {
+                        hopper.write_shortcut(name, entry)?
                     }
-                } else {
-                    None
+                    shortcuts::Change::Remove(name) => hopper.delete_shortcut(name)?,
                 }
             }
-            Err(_) => None,
+            Ok(())
+        })?;
+        let applied = changes.len() - conflicts.len();
+        let mut report = format!("Applied {} shortcut change(s).", applied);
+        if !conflicts.is_empty() {
+            report.push_str(&format!(
+                "\nSkipped {} shortcut(s) changed elsewhere while editing: {}",
+                conflicts.len(),
+                conflicts.join(", ")
+            ));
         }
+        Ok(report)
+    }
+
+    fn find_tagged_shortcut(&self, tag: &str, name: &str) -> Option<(String, PathBuf)> {
+        let name = if name.is_empty() { "*" } else { name };
+        self.store
+            .shortcuts()
+            .ok()?
+            .into_iter()
+            .find(|(found, entry)| glob_matches(name, found) && entry.tags.iter().any(|t| t == tag))
+            .map(|(found, entry)| (found, PathBuf::from(entry.location)))
+    }
+
+    fn find_shortcut(&self, name: &str) -> Option<(String, PathBuf)> {
+        self.store
+            .shortcuts()
+            .ok()?
+            .into_iter()
+            .find(|(found, _)| glob_matches(name, found))
+            .map(|(found, entry)| (found, PathBuf::from(entry.location)))
     }
 
     fn add_history<T: AsRef<Path>>(&mut self, path: T) -> anyhow::Result<()> {
-        let path = path.as_ref();
-        let file_name = fs::canonicalize(path)?
-            .file_name()
-            .expect("Unable to extract file name for shortcut")
-            .to_str()
-            .expect("Unable to extract file name for shortcut")
-            .to_string();
-        let history = self.find_history(&file_name);
-        let usage = match history {
-            Some((_, usage)) => usage + 1,
-            None => 1,
-        };
-        let path_as_string = sanitize(path)?;
-        let query = format!(
-            "INSERT OR REPLACE INTO history (name, location, usage) VALUES (\"{}\", \"{}\", {})",
-            file_name, &path_as_string, usage
-        );
-        self.db.execute(query)?;
-        Ok(())
+        history::record_visit(self.store.as_mut(), path, &self.config.history_rules)
     }
 
     fn find_local(&self, name: &str) -> Option<PathBuf> {
-        match glob(name).expect("Failed to parse glob.").next() {
+        let pattern = if Path::new(name).is_absolute() {
+            name.to_string()
+        } else {
+            format!(
+                "{}/{}",
+                glob::Pattern::escape(&self.cwd().display().to_string()),
+                name
+            )
+        };
+        match glob(&pattern).expect("Failed to parse glob.").next() {
             Some(path) => path.ok(),
             None => None,
         }
     }
 
-    fn grab(&mut self, name: String) -> Option<PathBuf> {
-        let shortcut = self.find_shortcut(&name);
-        let history = self
-            .find_history(&name)
-            .map(|(path, _)| PathBuf::from(path));
-        let local = self.find_local(&name);
-
-        let mut order = Vec::new();
-        if self.config.prioritize_shortcuts {
-            order.push(shortcut);
-            order.push(local);
-        } else {
-            order.push(local);
-            order.push(shortcut);
-        }
-        order.push(history);
-
-        match order.into_iter().find(|x| x.is_some()) {
-            Some(Some(path)) => Some(path),
-            _ => None,
-        }
+    fn grab(&self, name: String) -> Option<PathBuf> {
+        self.resolve(&name).map(|resolution| resolution.path)
     }
 
-    fn bhop_it(&mut self, name: String, edit_dir: bool) -> anyhow::Result<String> {
-        let path_opt = self.grab(name);
-        match path_opt {
-            Some(path) => {
-                self.add_history(&path)?;
-                if path.is_dir() && !edit_dir {
-                    Ok(format!(
-                        "{}{}",
-                        sanitize(path)?,
-                        var("BHOP_CMD_SEPARATOR").unwrap_or("|".to_string())
-                    ))
-                } else {
-                    let sanitized = sanitize(&path)?;
-                    let ext = path.extension().map(|s| s.to_str().unwrap().to_string());
-                    let move_dir = if self.config.always_jump {
-                        sanitize(path.parent().unwrap_or(&path))?
-                    } else {
-                        ".".to_string()
-                    };
-                    Ok(format!(
-                        "{}{}{}",
-                        move_dir,
-                        var("BHOP_CMD_SEPARATOR").unwrap_or("|".to_string()),
-                        self.map_editor(sanitized, ext)?
-                    ))
-                }
+    fn search_shortcuts(
+        &self,
+        matcher: &search::Matcher,
+        options: &ListOptions,
+    ) -> anyhow::Result<Vec<api::Entry>> {
+        let usage = self.store.shortcut_usage()?;
+        let mut found = Vec::new();
+        for (name, entry) in self.store.shortcuts()? {
+            if options
+                .tag
+                .as_ref()
+                .is_some_and(|tag| !entry.tags.contains(tag))
+            {
+                continue;
             }
-            None => Err(anyhow::Error::msg("No matching options found.")),
+            let by_name = matcher.matches(&name);
+            let score = [Some(&entry.location), entry.description.as_ref()]
+                .into_iter()
+                .flatten()
+                .chain(entry.tags.iter())
+                .filter_map(|text| matcher.matches(text))
+                .map(|(score, _)| score)
+                .chain(by_name.iter().map(|(score, _)| *score))
+                .max();
+            let score = match score {
+                Some(score) => score,
+                None => continue,
+            };
+            let (uses, last_used) = match usage.get(&name) {
+                Some((uses, last_used)) => (*uses, Some(*last_used)),
+                None => (0, None),
+            };
+            found.push((
+                score,
+                api::Entry {
+                    name,
+                    location: entry.location,
+                    source: api::Source::Shortcut,
+                    tags: entry.tags,
+                    description: entry.description,
+                    usage: uses,
+                    last_used,
+                    matched: by_name.map(|(_, matched)| matched).unwrap_or_default(),
+                },
+            ));
         }
+        Ok(ranked(matcher, found))
     }
 
-    fn pull_maps(&self, query: &str) -> anyhow::Result<()> {
-        let mut statement = self.db.prepare(query)?;
-        let mut results = Vec::new();
-        while let Ok(sqlite::State::Row) = statement.next() {
-            let name = statement.read::<String, _>("name");
-            let location = statement.read::<String, _>("location");
-            if let Ok(n) = name {
-                if let Ok(l) = location {
-                    results.push([n, l])
+    // The least used location for every matching name.
+    fn search_history(&self, matcher: &search::Matcher) -> anyhow::Result<Vec<api::Entry>> {
+        let mut by_name: BTreeMap<String, (i64, Vec<usize>, store::HistoryEntry)> = BTreeMap::new();
+        for entry in self.store.history(chrono::Utc::now().timestamp())? {
+            let by_location = matcher.matches(&entry.location).map(|(score, _)| score);
+            let (score, matched) = match (matcher.matches(&entry.name), by_location) {
+                (Some((score, matched)), by_location) => {
+                    (score.max(by_location.unwrap_or(score)), matched)
+                }
+                (None, Some(score)) => (score, Vec::new()),
+                (None, None) => continue,
+            };
+            match by_name.get(&entry.name) {
+                Some((_, _, kept))
+                    if (kept.usage, &kept.location) <= (entry.usage, &entry.location) => {}
+                _ => {
+                    by_name.insert(entry.name.clone(), (score, matched, entry));
                 }
             }
         }
-        self.format_map(results);
-        Ok(())
+        let found = by_name
+            .into_values()
+            .map(|(score, matched, entry)| {
+                (
+                    score,
+                    api::Entry {
+                        name: entry.name,
+                        location: entry.location,
+                        source: api::Source::History,
+                        tags: Vec::new(),
+                        description: None,
+                        usage: entry.usage,
+                        last_used: entry.last_visit,
+                        matched,
+                    },
+                )
+            })
+            .collect();
+        Ok(ranked(matcher, found))
     }
 
-    fn search_shortcuts(&self, filter: &Option<String>) -> anyhow::Result<()> {
-        let filter = match filter {
+    fn search_filter(&self, filter: Option<&str>) -> String {
+        match filter {
             Some(f) => format!(
                 "{}{}{}",
                 self.config.search_match_prefix, f, self.config.search_match_suffix
             ),
             None => "*".to_string(),
-        };
-        let query = format!(
-            "SELECT name, location FROM shortcuts WHERE name GLOB '{}' OR location GLOB '{}' ORDER BY name",
-            &filter,
-            &filter,
-        );
-        self.pull_maps(&query)?;
-        Ok(())
+        }
     }
 
-    fn search_history(&self, filter: &Option<String>) -> anyhow::Result<()> {
-        let filter = match filter {
-            Some(f) => format!(
-                "{}{}{}",
-                self.config.search_match_prefix, f, self.config.search_match_suffix
-            ),
-            None => "*".to_string(),
-        };
-        let query = format!(
-            r#"SELECT h1.name, h1.location FROM history AS h1 
-                INNER JOIN 
-                    (SELECT name, MIN(usage) AS min_usage, MIN(location) AS min_loc FROM history 
-                     WHERE name GLOB '{}' OR location GLOB '{}' GROUP BY name) AS h2 
-                ON (h1.name = h2.name AND h1.usage = h2.min_usage AND h1.location = h2.min_loc) 
-                GROUP BY h1.name, h1.location 
-                ORDER BY h1.name"#,
-            &filter, &filter
-        );
-        self.pull_maps(&query)?;
-        Ok(())
+    fn list(&self, filter: Option<String>, options: ListOptions) -> anyhow::Result<String> {
+        let (mut shortcuts, mut history): (Vec<api::Entry>, Vec<api::Entry>) = self
+            .search(filter.as_deref(), &options)?
+            .into_iter()
+            .partition(|entry| entry.source == api::Source::Shortcut);
+        if let Some(sort) = options.sort {
+            views::sort_entries(&mut shortcuts, sort);
+            views::sort_entries(&mut history, sort);
+        }
+        let depth = self.color_depth();
+        match options.view {
+            views::ListView::Table => {
+                println!("{}", self.config.theme.source.paint("Shortcut:", depth));
+                self.format_matches(views::rows(shortcuts));
+            }
+            views::ListView::Tree => self.print_tree("Shortcut:", &shortcuts),
+            // There are no tags to group history entries under.
+            views::ListView::ByTag => {
+                self.print_by_tag(shortcuts);
+                return Ok("".to_string());
+            }
+        }
+        // History entries can't be tagged, so they're left out of tag filtered listings.
+        if options.tag.is_none() {
+            match options.view {
+                views::ListView::Tree => self.print_tree("History:", &history),
+                _ => {
+                    println!("{}", self.config.theme.source.paint("History:", depth));
+                    self.format_matches(views::rows(history));
+                }
+            }
+        }
+        Ok("".to_string())
     }
 
-    fn search(&self, filter: Option<String>) -> anyhow::Result<()> {
-        println!("{}", "Shortcut:".bright_white().bold());
-        self.search_shortcuts(&filter)?;
-        println!("{}", "History:".bright_white().bold());
-        self.search_history(&filter)?;
-        Ok(())
+    fn format_map(&self, hops: Vec<[String; 3]>) {
+        self.format_matches(hops.into_iter().map(|hop| (hop, Vec::new())).collect());
     }
 
-    fn format_map(&self, hops: Vec<[String; 2]>) {
-        let max_name_size = hops.iter().map(|[name, _]| name.len()).max().unwrap_or(0);
-        let first_col = self.config.print_color_primary;
-        let sec_col = self.config.print_color_secondary;
+    // `format_map`, with the characters of each name at the positions given highlighted.
+    fn format_matches(&self, hops: Vec<([String; 3], Vec<usize>)>) {
+        let max_name_size = hops
+            .iter()
+            .map(|([name, _, _], _)| name.chars().count())
+            .max()
+            .unwrap_or(0);
+        let theme = &self.config.theme;
+        let depth = self.color_depth();
+        let width = self.terminal_width();
+        let home = self.home_dir().map(|home| home.display().to_string());
         let formatted_hops: Vec<String> = hops
             .into_iter()
-            .map(|[name, location]| {
-                (
-                    String::from_utf8(vec![b' '; max_name_size - name.len() + 1])
-                        .unwrap_or(" ".to_string()),
-                    name,
-                    location,
-                )
-            })
-            .map(|(ws, name, location)| {
+            .map(|([name, location, notes], matched)| {
+                let (location, notes) = match width {
+                    // Room is left for the name column and the arrow after it.
+                    Some(width) => theme::fit_row(
+                        &location,
+                        &notes,
+                        width.saturating_sub(max_name_size + 4),
+                        home.as_deref(),
+                    ),
+                    None => (location, notes),
+                };
+                let notes = if notes.is_empty() {
+                    "".to_string()
+                } else {
+                    format!(" {}", theme.notes.paint(&notes, depth))
+                };
                 format!(
-                    "{}{}{} {}",
-                    name.truecolor(first_col[0], first_col[1], first_col[2])
-                        .bold(),
-                    ws,
-                    "->".bright_white().bold(),
-                    &location
-                        .truecolor(sec_col[0], sec_col[1], sec_col[2])
-                        .bold(),
+                    "{}{}{} {}{}",
+                    theme::paint_matched(&name, &matched, theme.name, theme.matched, depth),
+                    " ".repeat(max_name_size - name.chars().count() + 1),
+                    theme.arrow.paint("->", depth),
+                    theme.location.paint(&location, depth),
+                    notes,
                 )
             })
             .collect();
         println!("{}", formatted_hops.join("\n"));
     }
 
-    fn use_group(&mut self, group: String, subgroup: Option<String>) -> anyhow::Result<String> {
-        let subgroup = subgroup.unwrap_or("default".to_string());
-        let path = self.grab(group.clone()).unwrap_or(PathBuf::from("."));
-        let group_path = path.join(var("BHOP_PROJECT_CONFIGS").unwrap_or(".bhop".to_string()));
-        match groups::BhopGroup::from(&subgroup, group_path) {
-            Some(options) => match options.cmd {
-                Some(cmd) => Ok(format!(
-                    "{}{}{}",
-                    sanitize(&path)?,
-                    var("BHOP_CMD_SEPARATOR").unwrap_or("|".to_string()),
-                    cmd
-                )),
-                None => match options.files {
-                    Some(files) => {
-                        let mut files = files.into_iter();
-                        match files.next() {
-                            Some(first) => {
-                                // the method to find the extension below is a bit hacky, but
-                                // works.  Originally I was going to bring in the external glob
-                                // crate, expand the glob, and then find the extension of the
-                                // first file, but didn't want to add another dependency just for
-                                // this.
-                                let ext = PathBuf::from(first.replace('*', "x"))
-                                    .extension()
-                                    .map(|s| s.to_str().unwrap().to_string());
-                                let editor_cmd =
-                                    options.editor.unwrap_or(self.map_editor(first, ext)?);
-                                let rest = files.collect::<Vec<String>>().join(" ");
-                                let rest = if rest.is_empty() {
-                                    "".to_string()
-                                } else {
-                                    format!(" {}", rest)
-                                };
-                                Ok(format!(
-                                    "{}{}{}{}",
-                                    sanitize(&path)?,
-                                    var("BHOP_CMD_SEPARATOR").unwrap_or("|".to_string()),
-                                    editor_cmd,
-                                    rest
-                                ))
-                            }
-                            None => self.bhop_it(group, true),
-                        }
-                    }
-                    None => Err(anyhow::Error::msg("No matching options found.")),
-                },
+    fn use_group(
+        &mut self,
+        group: String,
+        subgroup: Option<String>,
+        dry_run: bool,
+    ) -> anyhow::Result<String> {
+        let (path, group_path, options) = match self.load_group(&group, subgroup.as_deref())? {
+            Some(loaded) => loaded,
+            None => return Ok("Unable to find group or subgroup.".to_string()),
+        };
+        if dry_run {
+            if let Some(layout) = &options.layout {
+                return self.preview_layout(&path, &group_path, layout);
+            }
+        }
+        match self.options_action(&group, &path, &group_path, &options)? {
+            api::Action::Run { dir, cmd } if dry_run => {
+                Ok(format!("cd {}\n{}", dir.display(), cmd))
+            }
+            action => Ok(self.render(action)),
+        }
+    }
+
+    fn group_cmd(&self, options: &groups::BhopGroup) -> anyhow::Result<Option<String>> {
+        if let Some(cmd) = &options.cmd {
+            return Ok(Some(cmd.to_string()));
+        }
+        match &options.files {
+            Some(files) => match files.split_first() {
+                Some((first, rest)) => {
+                    let editor_cmd = match &options.editor {
+                        Some(editor) => editor.to_string(),
+                        None => self.map_editor(first.to_string(), None)?,
+                    };
+                    let rest = if rest.is_empty() {
+                        "".to_string()
+                    } else {
+                        format!(" {}", rest.join(" "))
+                    };
+                    Ok(Some(format!("{}{}", editor_cmd, rest)))
+                }
+                None => Ok(None),
             },
-            None => Ok("Unable to find group or subgroup.".to_string()),
+            None => Ok(None),
+        }
+    }
+
+    fn layout_session(
+        &self,
+        path: &Path,
+        group_path: &Path,
+        layout: &layouts::Layout,
+    ) -> anyhow::Result<layouts::Session> {
+        let dir = sanitize(path)?;
+        let mut windows = Vec::new();
+        for name in layout.windows.iter() {
+            // Windows can only run plain group commands, so anything else (including other
+            // layouts) just opens a shell in the group's directory.
+            let cmd = match groups::BhopGroup::from(name, group_path)? {
+                Some(options) if options.layout.is_none() => self.group_cmd(&options)?,
+                _ => None,
+            };
+            windows.push(layouts::Window {
+                name: name.to_string(),
+                cmd,
+            });
+        }
+        Ok(layouts::Session::new(layout, &dir, windows))
+    }
+
+    fn layout_file(&self, session: &layouts::Session) -> anyhow::Result<(PathBuf, String)> {
+        let layout_dir = PathBuf::from(self.locate()?).join("layouts");
+        let layout_file = layout_dir.join(format!("{}.kdl", session.name));
+        let layout_file = layout_file.display().to_string().replace('\\', "/");
+        Ok((layout_dir, layout_file))
+    }
+
+    // Sets up the multiplexer session for `layout` if it isn't running yet.
+    fn layout_action(
+        &mut self,
+        path: &Path,
+        group_path: &Path,
+        layout: &layouts::Layout,
+    ) -> anyhow::Result<api::Action> {
+        let session = self.layout_session(path, group_path, layout)?;
+        let cmd = match layout.multiplexer {
+            layouts::Multiplexer::Tmux => {
+                if !layouts::tmux_session_exists(&session.name) {
+                    for cmd in session.tmux_setup() {
+                        layouts::run(&cmd)?;
+                    }
+                }
+                layouts::render(&session.tmux_attach(self.var("TMUX").is_some()))
+            }
+            layouts::Multiplexer::Zellij => {
+                let (layout_dir, layout_file) = self.layout_file(&session)?;
+                let exists = layouts::zellij_session_exists(&session.name);
+                if !exists {
+                    fs::create_dir_all(&layout_dir)?;
+                    fs::write(&layout_file, session.zellij_layout())?;
+                }
+                layouts::render(&session.zellij_launch(&layout_file, exists))
+            }
+        };
+        Ok(api::Action::Run {
+            dir: session.dir.clone().into(),
+            cmd,
+        })
+    }
+
+    fn preview_layout(
+        &self,
+        path: &Path,
+        group_path: &Path,
+        layout: &layouts::Layout,
+    ) -> anyhow::Result<String> {
+        let session = self.layout_session(path, group_path, layout)?;
+        match layout.multiplexer {
+            layouts::Multiplexer::Tmux => {
+                let mut cmds = session.tmux_setup();
+                cmds.push(session.tmux_attach(self.var("TMUX").is_some()));
+                let cmds: Vec<String> = cmds.iter().map(|c| layouts::render(c)).collect();
+                Ok(cmds.join("\n"))
+            }
+            layouts::Multiplexer::Zellij => {
+                let (_, layout_file) = self.layout_file(&session)?;
+                let launch = session.zellij_launch(&layout_file, false);
+                Ok(format!(
+                    "{}{}",
+                    session.zellij_layout(),
+                    layouts::render(&launch)
+                ))
+            }
         }
     }
 }
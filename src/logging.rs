@@ -0,0 +1,97 @@
+// Tracing of what bhop is doing, for `-v`/`-vv` and the `verbose` setting.  Messages go to stderr
+// so they never end up in the `dir|cmd` output the runners act on, and with `log_file` set they're
+// also appended to a log file, which is the only way to see what happened inside a runner.
+//
+// Level 1 covers resolution steps, configuration files, editor choices and timing, and level 2
+// adds every SQL statement run.
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+// Log files are started over once they grow past this, instead of being rotated.
+const MAX_LOG_SIZE: u64 = 1024 * 1024;
+
+static LEVEL: AtomicU8 = AtomicU8::new(0);
+static LOG_FILE: Mutex<Option<File>> = Mutex::new(None);
+static START: OnceLock<Instant> = OnceLock::new();
+
+// The level is shared by the whole process, so every instance built after this logs at it as well
+// until it's set again.
+pub fn set_level(level: u8) {
+    START.get_or_init(Instant::now);
+    LEVEL.store(level, Ordering::Relaxed);
+}
+
+pub fn level() -> u8 {
+    LEVEL.load(Ordering::Relaxed)
+}
+
+fn file_level() -> u8 {
+    match LOG_FILE.lock() {
+        Ok(file) if file.is_some() => level().max(1),
+        _ => 0,
+    }
+}
+
+pub fn enabled(level: u8) -> bool {
+    level <= self::level() || level <= file_level()
+}
+
+pub fn open_file<T: AsRef<Path>>(path: T) -> anyhow::Result<()> {
+    let path = path.as_ref();
+    START.get_or_init(Instant::now);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let oversized = fs::metadata(path).is_ok_and(|m| m.len() > MAX_LOG_SIZE);
+    let file = OpenOptions::new()
+        .create(true)
+        .append(!oversized)
+        .write(true)
+        .truncate(oversized)
+        .open(path)?;
+    if let Ok(mut log_file) = LOG_FILE.lock() {
+        *log_file = Some(file);
+    }
+    Ok(())
+}
+
+// Milliseconds since logging was set up, which is close enough to the start of the process.
+pub fn elapsed() -> u128 {
+    START.get_or_init(Instant::now).elapsed().as_millis()
+}
+
+pub fn log(level: u8, message: &str) {
+    let line = format!("[bhop +{}ms] {}", elapsed(), message);
+    if level <= self::level() {
+        eprintln!("{}", line);
+    }
+    if level <= file_level() {
+        if let Ok(mut file) = LOG_FILE.lock() {
+            if let Some(file) = file.as_mut() {
+                // Several runners may be writing at once, so each line says which one it's from.
+                let _ = writeln!(
+                    file,
+                    "{} [{}] {}",
+                    chrono::Local::now().format("%Y-%m-%dT%H:%M:%S"),
+                    std::process::id(),
+                    line
+                );
+            }
+        }
+    }
+}
+
+// `trace!(level, "format", args...)`, which only formats the message when it'll be logged.
+macro_rules! trace {
+    ($level:expr, $($arg:tt)*) => {
+        if $crate::logging::enabled($level) {
+            $crate::logging::log($level, &format!($($arg)*));
+        }
+    };
+}
+
+pub(crate) use trace;
@@ -1,11 +1,36 @@
 fn main() {
     let command = bhop::args::Request::parse();
-    let hopper = bhop::Hopper::new();
+    bhop::logging::set_level(command.verbosity());
+    // Directory changes from the shell hooks happen on every `cd`, so they're recorded without
+    // setting up a full hop instance.
+    if let bhop::args::Request::Record(path) = &command {
+        if let Err(e) = bhop::history::record(path) {
+            println!("[error] Unable to record directory: {}", e);
+        }
+        return;
+    }
+    // Completions run on every tab press and anything printed becomes a candidate, so errors
+    // are left out.
+    if let bhop::args::Request::Complete(words) = command {
+        if let Ok(candidates) = bhop::completions::complete(words) {
+            print!("{}", candidates);
+        }
+        return;
+    }
+    let hopper = bhop::Hopper::builder()
+        .verbosity(command.verbosity())
+        .build();
     match hopper {
-        Ok(mut hopper) => match hopper.execute(command) {
-            Ok(_) => {}
-            Err(e) => println!("[error] Unable to execute hop command: {}", e),
-        },
+        Ok(mut hopper) => {
+            // Warnings go to stderr so they don't end up in what the runners act on.
+            for warning in hopper.warnings.iter() {
+                eprintln!("[warning] {}", warning);
+            }
+            match hopper.execute(command) {
+                Ok(_) => {}
+                Err(e) => println!("[error] Unable to execute hop command: {}", e),
+            }
+        }
         Err(e) => println!("[error] Unable to create hop instance: {}", e),
     };
 }
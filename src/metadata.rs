@@ -2,6 +2,59 @@ use dirs::home_dir;
 use std::path::{Path, PathBuf};
 use std::{env::var, fs};
 
+// Schema changes applied on top of the tables made in `create_database`, in order.  The index of
+// the last applied migration is tracked with SQLite's `user_version` pragma so existing databases
+// are upgraded in place.  Only ever append to this list.
+const MIGRATIONS: &[&str] = &[
+    // 1: descriptions and tags for shortcuts
+    "ALTER TABLE shortcuts ADD COLUMN description TEXT;
+    CREATE TABLE IF NOT EXISTS tags (
+        name TEXT NOT NULL,
+        tag TEXT NOT NULL,
+        PRIMARY KEY (name, tag)
+    );",
+    // 2: journal of shortcut changes for `hp undo`
+    "CREATE TABLE IF NOT EXISTS journal (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        batch INTEGER NOT NULL,
+        operation TEXT NOT NULL,
+        name TEXT NOT NULL,
+        old_entry TEXT,
+        new_entry TEXT,
+        created_at INTEGER NOT NULL
+    );",
+    // 3: filesystem identity of shortcuts for finding them again after they move
+    "ALTER TABLE shortcuts ADD COLUMN device INTEGER;
+    ALTER TABLE shortcuts ADD COLUMN inode INTEGER;
+    ALTER TABLE shortcuts ADD COLUMN remote TEXT;",
+    // 4: index of directories under `repo_roots` and the git repositories found in them
+    "CREATE TABLE IF NOT EXISTS repo_index (
+        location TEXT PRIMARY KEY,
+        parent TEXT NOT NULL,
+        name TEXT NOT NULL,
+        mtime INTEGER,
+        repo INTEGER NOT NULL,
+        remote TEXT
+    );",
+    // 5: timestamped directory visits and shortcut uses for `hp history` and `hp stats`
+    "CREATE TABLE IF NOT EXISTS visits (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        location TEXT NOT NULL,
+        visited_at INTEGER NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS visits_location ON visits (location);
+    CREATE INDEX IF NOT EXISTS visits_visited_at ON visits (visited_at);
+    CREATE TABLE IF NOT EXISTS shortcut_uses (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        name TEXT NOT NULL,
+        used_at INTEGER NOT NULL
+    );
+    ALTER TABLE shortcuts ADD COLUMN created_at INTEGER;",
+];
+
+// How long to wait on another process holding the database lock, in milliseconds.
+const BUSY_TIMEOUT: usize = 5000;
+
 #[derive(Debug, Clone)]
 pub struct Environment {
     pub config_path: PathBuf,
@@ -9,22 +62,52 @@ pub struct Environment {
 }
 
 impl Environment {
-    pub fn new() -> Self {
-        let config_dir = match var("BHOP_CONFIG_DIRECTORY") {
-            Ok(loc) => PathBuf::from(&loc),
-            Err(_) => {
-                let mut config_dir_temp = home_dir().unwrap_or(PathBuf::from("~/"));
+    pub fn config_dir() -> PathBuf {
+        Self::config_dir_in(|key| var(key).ok(), home_dir())
+    }
+
+    // Same as `config_dir`, with `var` looking up the environment variables and `home` the home
+    // directory.
+    pub fn config_dir_in<F: Fn(&str) -> Option<String>>(var: F, home: Option<PathBuf>) -> PathBuf {
+        match var("BHOP_CONFIG_DIRECTORY") {
+            Some(loc) => PathBuf::from(&loc),
+            None => {
+                let mut config_dir_temp = home.unwrap_or(PathBuf::from("~/"));
                 config_dir_temp.push(".config");
                 config_dir_temp.push("bhop");
                 config_dir_temp
             }
-        };
-        let config_path = config_dir.join("bhop.toml");
+        }
+    }
+
+    pub fn db_path() -> PathBuf {
+        Self::config_dir().join("db").join("bhop.db")
+    }
+
+    pub fn new() -> Self {
+        Self::setup(Self::config_dir())
+    }
+
+    // The paths used for `config_dir`, without creating anything.
+    pub fn at(config_dir: impl AsRef<Path>) -> Self {
+        Environment {
+            config_path: config_dir.as_ref().join("bhop.toml"),
+            db_path: config_dir.as_ref().join("db").join("bhop.db"),
+        }
+    }
+
+    // Creates the configuration directory, default bhop.toml and database under `config_dir`
+    // where they're missing.
+    pub fn setup(config_dir: impl AsRef<Path>) -> Self {
+        let config_dir = config_dir.as_ref();
+        let Environment {
+            config_path,
+            db_path,
+        } = Self::at(config_dir);
         let script_dir = config_dir.join("scripts");
-        let db_path = config_dir.join("db").join("bhop.db");
-        if !config_dir.exists() {
-            fs::create_dir(config_dir).expect("Failed to create config directory.");
-        };
+        // Several shells may be setting things up at once, so everything here has to cope with
+        // another process having gotten there first.
+        fs::create_dir_all(config_dir).expect("Failed to create config directory.");
         if !config_path.exists() {
             // Add default hop.toml to config directory
             let default_toml = if cfg!(windows) {
@@ -32,41 +115,106 @@ impl Environment {
             } else {
                 "src/defaults/unix_defaults.toml"
             };
-            fs::copy(default_toml, &config_path)
+            // Copied under a name of its own first so that nobody reads a half written file.
+            let staged = config_dir.join(format!(
+                "bhop.toml.{}.{:?}",
+                std::process::id(),
+                std::thread::current().id()
+            ));
+            fs::copy(default_toml, &staged).expect("Failed to copy bhop.toml to config directory");
+            fs::rename(&staged, &config_path)
                 .expect("Failed to copy bhop.toml to config directory");
         };
-        if !db_path.exists() {
-            fs::create_dir(db_path.parent().unwrap())
-                .expect("Failed to create database directory.");
+        fs::create_dir_all(db_path.parent().unwrap())
+            .expect("Failed to create database directory.");
+        // Only the schema version is read when the database is up to date already, which is the
+        // case on nearly every run.  Otherwise the file may exist but still be empty while
+        // another process is in the middle of creating it, which creating it again copes with.
+        let current = db_path.exists()
+            && Self::connect(&db_path)
+                .and_then(|conn| Self::is_current(&conn))
+                .unwrap_or(false);
+        if !current {
             Self::create_database(&db_path).expect("Failed to create database");
-        };
-        if !script_dir.exists() {
-            fs::create_dir(&script_dir).expect("Failed to create script directory.");
-        };
+            Self::migrate_database(&db_path).expect("Failed to migrate database");
+        }
+        fs::create_dir_all(&script_dir).expect("Failed to create script directory.");
         Environment {
             config_path,
             db_path,
         }
     }
 
+    // Opens the database in WAL mode so readers never block the writer, and waits on a locked
+    // database instead of failing straight away when another shell is writing to it.
+    pub fn connect(db_path: impl AsRef<Path>) -> anyhow::Result<sqlite::Connection> {
+        let mut conn = sqlite::open(db_path.as_ref())?;
+        conn.set_busy_timeout(BUSY_TIMEOUT)?;
+        // WAL mode sticks to the database file, so it only has to be switched on once.
+        let mut statement = conn.prepare("PRAGMA journal_mode")?;
+        let wal = match statement.next()? {
+            sqlite::State::Row => statement.read::<String, _>(0)?.eq_ignore_ascii_case("wal"),
+            sqlite::State::Done => false,
+        };
+        drop(statement);
+        if !wal {
+            conn.execute("PRAGMA journal_mode = WAL")?;
+        }
+        Ok(conn)
+    }
+
     fn create_database(db_path: impl AsRef<Path>) -> anyhow::Result<()> {
-        let conn = sqlite::open(db_path.as_ref())?;
+        let conn = Self::connect(db_path)?;
         conn.execute(
-            "CREATE TABLE IF NOT EXISTS shortcuts (
+            "BEGIN IMMEDIATE;
+            CREATE TABLE IF NOT EXISTS shortcuts (
             name TEXT PRIMARY KEY,
             location TEXT NOT NULL
-            )",
-        )?;
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS history (
+            );
+            CREATE TABLE IF NOT EXISTS history (
             name TEXT NOT NULL,
             location TEXT NOT NULL,
             usage INTEGER NOT NULL,
             PRIMARY KEY (name, location)
-            )",
+            );
+            COMMIT;",
         )?;
         Ok(())
     }
+
+    pub fn migrate_database(db_path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let conn = Self::connect(db_path)?;
+        let version = Self::user_version(&conn)?;
+        for (i, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+            // The version is checked again once the write lock is held, in case another process
+            // applied this migration in the meantime.
+            conn.execute("BEGIN IMMEDIATE")?;
+            if Self::user_version(&conn)? > i {
+                conn.execute("COMMIT")?;
+                continue;
+            }
+            if let Err(e) = conn.execute(format!("{} PRAGMA user_version = {};", migration, i + 1))
+            {
+                conn.execute("ROLLBACK")?;
+                return Err(e.into());
+            }
+            conn.execute("COMMIT")?;
+        }
+        Ok(())
+    }
+
+    // Whether every migration has been applied, which only reads the schema version.
+    pub fn is_current(conn: &sqlite::Connection) -> anyhow::Result<bool> {
+        Ok(Self::user_version(conn)? >= MIGRATIONS.len())
+    }
+
+    fn user_version(conn: &sqlite::Connection) -> anyhow::Result<usize> {
+        let mut statement = conn.prepare("PRAGMA user_version")?;
+        match statement.next()? {
+            sqlite::State::Row => Ok(statement.read::<i64, _>(0)? as usize),
+            sqlite::State::Done => Ok(0),
+        }
+    }
 }
 
 impl Default for Environment {
@@ -0,0 +1,46 @@
+// Jumps relative to the root of the project containing the current directory.  `hp /` goes to the
+// root itself and `hp //tests` to `<root>/tests`, where the root is the nearest directory above
+// the current one holding any of the configured `project_markers`.
+use crate::Hopper;
+use glob::glob;
+use std::path::{Path, PathBuf};
+
+// Splits a project relative reference into the part below the project root, so `/` and `//` give
+// `""` and `//src/bin` gives `src/bin`.
+pub fn project_reference(name: &str) -> Option<&str> {
+    if name == "/" {
+        Some("")
+    } else {
+        name.strip_prefix("//")
+    }
+}
+
+pub fn project_root<T: AsRef<Path>, U: AsRef<str>>(start: T, markers: &[U]) -> Option<PathBuf> {
+    start
+        .as_ref()
+        .ancestors()
+        .find(|dir| {
+            markers
+                .iter()
+                .any(|marker| dir.join(marker.as_ref()).exists())
+        })
+        .map(|dir| dir.to_path_buf())
+}
+
+impl Hopper {
+    // Outside of a project this gives nothing, so `hp /` still falls back to the filesystem root.
+    pub(crate) fn find_in_project(&self, name: &str) -> Option<PathBuf> {
+        let sub = project_reference(name)?;
+        let root = project_root(self.cwd(), &self.config.project_markers)?;
+        if sub.is_empty() {
+            return Some(root);
+        }
+        // Only the part below the root is a pattern, the root may have glob characters of its own.
+        let pattern = format!(
+            "{}/{}",
+            glob::Pattern::escape(&root.display().to_string()),
+            sub
+        );
+        glob(&pattern).ok()?.flatten().next()
+    }
+}
@@ -0,0 +1,154 @@
+// Keeping shortcuts working after the directories they point to move.  Every shortcut records the
+// filesystem identity of its target when it's written, so a shortcut whose location has gone
+// missing can be found again under the configured `relocate_roots`.  Whole trees can also be
+// moved in one go with `hp mv-tree`.
+use crate::identity::Identity;
+use crate::{sanitize, Hopper};
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+
+// Like `sanitize`, but for paths that may no longer (or not yet) exist.  `path` has to be
+// absolute already.
+fn prefix_path(path: PathBuf) -> anyhow::Result<String> {
+    let prefix = sanitize(path)?;
+    Ok(match prefix.trim_end_matches('/') {
+        "" => "/".to_string(),
+        trimmed => trimmed.to_string(),
+    })
+}
+
+// Returns `location` with `old` swapped out for `new` if it's inside of the `old` tree.
+fn rewrite(location: &str, old: &str, new: &str) -> Option<String> {
+    if location == old {
+        Some(new.to_string())
+    } else {
+        let rest = location.strip_prefix(old)?;
+        if rest.starts_with('/') || old.ends_with('/') {
+            Some(format!(
+                "{}/{}",
+                new.trim_end_matches('/'),
+                rest.trim_start_matches('/')
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+impl Hopper {
+    pub(crate) fn record_identity(&mut self, name: &str) -> anyhow::Result<()> {
+        let location = match self.load_shortcut(name)? {
+            Some(entry) => entry.location,
+            None => return Ok(()),
+        };
+        self.store.set_identity(name, &Identity::of(&location))
+    }
+
+    // Looks for the moved target of shortcut `name`, offering to update the shortcut when it's
+    // found.  Nothing found leaves the shortcut pointing at `missing`, but a match that isn't
+    // taken (or can't be asked about) fails the lookup rather than guessing.
+    pub(crate) fn relocate(
+        &mut self,
+        name: &str,
+        missing: &Path,
+    ) -> anyhow::Result<Option<PathBuf>> {
+        let identity = self.store.identity(name)?;
+        let found = match identity.find_in(
+            missing,
+            &self.config.relocate_roots,
+            self.config.relocate_max_depth,
+        ) {
+            Some(found) => found,
+            None => return Ok(None),
+        };
+        let location = sanitize(&found)?;
+        // The prompt goes to stderr since stdout is captured by the runner.  When there's nobody
+        // around to answer it the move is only reported.
+        if !std::io::stdin().is_terminal() {
+            anyhow::bail!(
+                "Shortcut `{}` points at missing {}, it looks like it moved to {}.",
+                name,
+                missing.display(),
+                location
+            );
+        }
+        eprint!(
+            "Shortcut `{}` moved from {} to {}, update it? [y/N] ",
+            name,
+            missing.display(),
+            location
+        );
+        if !proceed::proceed() {
+            anyhow::bail!(
+                "Shortcut `{}` points at missing {}.",
+                name,
+                missing.display()
+            );
+        }
+        self.journaled("relocate", vec![name.to_string()], |hopper| {
+            hopper.update_shortcut(name, |entry| entry.location = location)?;
+            hopper.record_identity(name)
+        })?;
+        Ok(Some(found))
+    }
+
+    pub(crate) fn move_tree(
+        &mut self,
+        old_prefix: String,
+        new_prefix: String,
+        dry_run: bool,
+    ) -> anyhow::Result<String> {
+        let old = prefix_path(self.absolute(&old_prefix))?;
+        let new = prefix_path(self.absolute(&new_prefix))?;
+        let shortcuts: Vec<(String, String, String)> = self
+            .load_shortcuts()?
+            .into_iter()
+            .filter_map(|(name, entry)| {
+                rewrite(&entry.location, &old, &new).map(|moved| (name, entry.location, moved))
+            })
+            .collect();
+        let history: Vec<(String, String)> = self
+            .load_history()?
+            .into_iter()
+            .filter_map(|[_, location]| {
+                rewrite(&location, &old, &new).map(|moved| (location, moved))
+            })
+            .collect();
+        if shortcuts.is_empty() && history.is_empty() {
+            return Ok(format!("Nothing is stored under {}.", old));
+        }
+        if dry_run {
+            let mut listing = vec!["Would move:".to_string()];
+            for (name, location, moved) in shortcuts.iter() {
+                listing.push(format!("  {}: {} -> {}", name, location, moved));
+            }
+            for (location, moved) in history.iter() {
+                listing.push(format!("  history: {} -> {}", location, moved));
+            }
+            return Ok(listing.join("\n"));
+        }
+        let names = shortcuts
+            .iter()
+            .map(|(name, _, _)| name.to_string())
+            .collect();
+        self.journaled("mv-tree", names, |hopper| {
+            for (name, _, moved) in shortcuts.iter() {
+                hopper.update_shortcut(name, |entry| entry.location = moved.to_string())?;
+                hopper.record_identity(name)?;
+            }
+            for (location, moved) in history.iter() {
+                let moved_name = Path::new(moved)
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or(moved.to_string());
+                hopper.store.move_history(location, &moved_name, moved)?;
+            }
+            Ok(())
+        })?;
+        Ok(format!(
+            "Moved {} shortcut(s) and {} history entries.",
+            shortcuts.len(),
+            history.len()
+        ))
+    }
+}
@@ -0,0 +1,153 @@
+// Git repositories found under the configured `repo_roots`, which can be jumped to by name without
+// ever being visited or added first.  Every directory walked is cached in the store's repository
+// index along with its modification time, so a refresh only has to re-read directories whose entries
+// have changed since the last scan.
+use crate::identity::{git_remote, SKIPPED_DIRS};
+use crate::store::IndexedDir;
+use crate::{glob_matches, sanitize, Hopper};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+fn modified(path: &Path) -> Option<i64> {
+    // Nanoseconds, so that changes made within a second of the last scan aren't missed.
+    let nanos = fs::metadata(path)
+        .ok()?
+        .modified()
+        .ok()?
+        .duration_since(UNIX_EPOCH)
+        .ok()?
+        .as_nanos();
+    i64::try_from(nanos).ok()
+}
+
+fn subdirectories(path: &Path) -> Vec<PathBuf> {
+    let entries = match fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+    entries
+        .flatten()
+        .filter(|entry| entry.file_type().map(|t| t.is_dir()).unwrap_or(false))
+        .filter(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            !name.starts_with('.') && !SKIPPED_DIRS.contains(&name.as_str())
+        })
+        .map(|entry| entry.path())
+        .collect()
+}
+
+impl Hopper {
+    // Walks every repo root, reusing the cached list of subdirectories for directories that
+    // haven't changed, and drops anything from the index that wasn't seen again.
+    pub(crate) fn refresh_repos(&mut self) -> anyhow::Result<()> {
+        let roots = self.config.repo_roots.clone();
+        let max_depth = self.config.repo_max_depth;
+        let mut seen = HashSet::new();
+        self.transaction(|hopper| {
+            for root in roots.iter().filter(|root| root.is_dir()) {
+                hopper.index_dir(root, "", 0, max_depth, &mut seen)?;
+            }
+            for location in hopper.store.indexed_locations()? {
+                if !seen.contains(&location) {
+                    hopper.store.delete_indexed_dir(&location)?;
+                }
+            }
+            Ok(())
+        })
+    }
+
+    fn index_dir(
+        &mut self,
+        path: &Path,
+        parent: &str,
+        depth: usize,
+        max_depth: usize,
+        seen: &mut HashSet<String>,
+    ) -> anyhow::Result<()> {
+        let location = sanitize(path)?;
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or(location.clone());
+        seen.insert(location.clone());
+        let mut dir = IndexedDir {
+            location,
+            parent: parent.to_string(),
+            name,
+            mtime: None,
+            repo: false,
+            remote: None,
+        };
+        // Repositories nested inside of other repositories (submodules, vendored code) aren't
+        // worth indexing, so the walk stops at the first `.git`.
+        if path.join(".git").exists() {
+            dir.repo = true;
+            dir.remote = git_remote(path);
+            return self.store.write_indexed_dir(&dir);
+        }
+        if depth >= max_depth {
+            return self.store.write_indexed_dir(&dir);
+        }
+        dir.mtime = modified(path);
+        let cached = self
+            .store
+            .indexed_dir(&dir.location)?
+            .and_then(|cached| cached.mtime);
+        let children = if dir.mtime.is_some() && cached == dir.mtime {
+            self.store
+                .indexed_children(&dir.location)?
+                .into_iter()
+                .map(PathBuf::from)
+                .collect()
+        } else {
+            subdirectories(path)
+        };
+        self.store.write_indexed_dir(&dir)?;
+        for child in children.iter().filter(|child| child.is_dir()) {
+            self.index_dir(child, &dir.location, depth + 1, max_depth, seen)?;
+        }
+        Ok(())
+    }
+
+    fn load_repos(&self) -> anyhow::Result<Vec<[String; 3]>> {
+        Ok(self
+            .store
+            .repos()?
+            .into_iter()
+            .map(|repo| [repo.name, repo.location, repo.remote.unwrap_or_default()])
+            .collect())
+    }
+
+    pub(crate) fn list_repos(&mut self, refresh: bool) -> anyhow::Result<String> {
+        if self.config.repo_roots.is_empty() {
+            return Ok(
+                "No repo_roots configured, add some to bhop.toml to index repositories."
+                    .to_string(),
+            );
+        }
+        let mut repos = self.load_repos()?;
+        // The first listing builds the index so there's something to show.
+        if refresh || repos.is_empty() {
+            self.refresh_repos()?;
+            repos = self.load_repos()?;
+        }
+        if repos.is_empty() {
+            return Ok("No repositories found under repo_roots.".to_string());
+        }
+        self.format_map(repos);
+        Ok("".to_string())
+    }
+
+    // Repositories with the same name are told apart by preferring the shallowest one.
+    pub(crate) fn find_repo(&self, name: &str) -> Option<PathBuf> {
+        self.store
+            .repos()
+            .ok()?
+            .into_iter()
+            .filter(|repo| glob_matches(name, &repo.name))
+            .min_by_key(|repo| repo.location.len())
+            .map(|repo| PathBuf::from(repo.location))
+    }
+}
@@ -0,0 +1,153 @@
+// How `hp list` filters match: globs wrapped in `search_match_prefix`/`suffix` (the default),
+// regular expressions, or fuzzy subsequences ranked by how well they match.  All of them are
+// smart-case, ignoring case unless the filter has an uppercase letter in it.
+use regex::{Regex, RegexBuilder};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SearchMode {
+    #[default]
+    Glob,
+    Regex,
+    Fuzzy,
+}
+
+pub fn smart_case(filter: &str) -> bool {
+    filter.chars().any(char::is_uppercase)
+}
+
+pub enum Matcher {
+    All,
+    // An invalid glob matches nothing, the same as for every other glob lookup.
+    Glob(Option<glob::Pattern>, glob::MatchOptions),
+    Regex(Regex),
+    Fuzzy {
+        pattern: Vec<char>,
+        case_sensitive: bool,
+    },
+}
+
+impl Matcher {
+    pub fn new(
+        filter: Option<&str>,
+        mode: SearchMode,
+        prefix: &str,
+        suffix: &str,
+    ) -> anyhow::Result<Self> {
+        let filter = match filter {
+            Some(filter) => filter,
+            None => return Ok(Matcher::All),
+        };
+        let case_sensitive = smart_case(filter);
+        Ok(match mode {
+            SearchMode::Glob => Matcher::Glob(
+                glob::Pattern::new(&format!("{}{}{}", prefix, filter, suffix)).ok(),
+                glob::MatchOptions {
+                    case_sensitive,
+                    ..Default::default()
+                },
+            ),
+            SearchMode::Regex => Matcher::Regex(
+                RegexBuilder::new(filter)
+                    .case_insensitive(!case_sensitive)
+                    .build()
+                    .map_err(|e| anyhow::anyhow!("Invalid regex `{}`: {}", filter, e))?,
+            ),
+            SearchMode::Fuzzy => Matcher::Fuzzy {
+                pattern: filter.chars().filter(|c| !c.is_whitespace()).collect(),
+                case_sensitive,
+            },
+        })
+    }
+
+    // The score of a match against `text`, with the positions of the matched characters for
+    // fuzzy matches.  Only fuzzy matches are ranked, everything else scores 0.
+    pub fn matches(&self, text: &str) -> Option<(i64, Vec<usize>)> {
+        match self {
+            Matcher::All => Some((0, Vec::new())),
+            Matcher::Glob(pattern, options) => pattern
+                .as_ref()
+                .filter(|p| p.matches_with(text, *options))
+                .map(|_| (0, Vec::new())),
+            Matcher::Regex(regex) => regex.is_match(text).then(|| (0, Vec::new())),
+            Matcher::Fuzzy {
+                pattern,
+                case_sensitive,
+            } => fuzzy_match(pattern, text, *case_sensitive),
+        }
+    }
+
+    pub fn ranked(&self) -> bool {
+        matches!(self, Matcher::Fuzzy { .. })
+    }
+}
+
+const MATCH: i64 = 16;
+const CONSECUTIVE: i64 = 24;
+const WORD_START: i64 = 20;
+const GAP: i64 = 1;
+const MAX_GAP: i64 = 12;
+
+fn word_start(text: &[char], i: usize) -> bool {
+    i == 0
+        || matches!(text[i - 1], '/' | '\\' | '-' | '_' | '.' | ' ')
+        || (text[i - 1].is_lowercase() && text[i].is_uppercase())
+}
+
+// Matches `pattern` as a subsequence of `text`, rewarding characters that follow each other or
+// start a word and penalising the gaps between them.  Every place the first character could match
+// is tried and the best scoring one kept, so `hop` finds `hop` in `/shop/hop` rather than the
+// scattered one.
+pub fn fuzzy_match(
+    pattern: &[char],
+    text: &str,
+    case_sensitive: bool,
+) -> Option<(i64, Vec<usize>)> {
+    let fold = |c: char| match case_sensitive {
+        true => c,
+        false => c.to_lowercase().next().unwrap_or(c),
+    };
+    let chars: Vec<char> = text.chars().collect();
+    let folded: Vec<char> = chars.iter().map(|c| fold(*c)).collect();
+    let pattern: Vec<char> = pattern.iter().map(|c| fold(*c)).collect();
+    let first = match pattern.first() {
+        Some(first) => *first,
+        None => return Some((0, Vec::new())),
+    };
+    let mut best: Option<(i64, Vec<usize>)> = None;
+    for start in (0..folded.len()).filter(|i| folded[*i] == first) {
+        let mut positions = vec![start];
+        let mut i = start + 1;
+        for c in &pattern[1..] {
+            while i < folded.len() && folded[i] != *c {
+                i += 1;
+            }
+            if i == folded.len() {
+                break;
+            }
+            positions.push(i);
+            i += 1;
+        }
+        if positions.len() < pattern.len() {
+            // No later start can match the whole pattern if this one didn't.
+            break;
+        }
+        let mut score = -(start as i64).min(MAX_GAP) * GAP;
+        for (n, position) in positions.iter().enumerate() {
+            score += MATCH;
+            if word_start(&chars, *position) {
+                score += WORD_START;
+            }
+            if n > 0 {
+                let gap = (position - positions[n - 1] - 1) as i64;
+                score += match gap {
+                    0 => CONSECUTIVE,
+                    gap => -gap.min(MAX_GAP) * GAP,
+                };
+            }
+        }
+        if best.as_ref().is_none_or(|(best, _)| score > *best) {
+            best = Some((score, positions));
+        }
+    }
+    best
+}
@@ -0,0 +1,431 @@
+// `hp config get/set/list`, for reading and changing bhop.toml without opening an editor.  Values
+// are written into the file in place, so the comments and layout of everything else are left
+// as they were.
+use crate::configs::{Configs, ReadConfig};
+use crate::layers::{self, ConfigLayer, Origin};
+use crate::theme::THEME_KEYS;
+use crate::Hopper;
+use std::fs;
+use toml_edit::{Decor, DocumentMut, InlineTable, Item, Key, RawString, Table, Value};
+
+// Every field of `ReadSettings`, in the order they're listed.
+pub const SETTINGS: &[&str] = &[
+    "default_editor",
+    "ls_display_block",
+    "print_color_primary",
+    "print_color_secondary",
+    "verbose",
+    "prioritize_shortcuts",
+    "always_jump",
+    "search_match_prefix",
+    "search_match_suffix",
+    "relocate_roots",
+    "relocate_max_depth",
+    "repo_roots",
+    "repo_max_depth",
+    "project_markers",
+    "history_exclude",
+    "history_min_depth",
+    "log_file",
+    "color",
+];
+
+// Splits `settings.always_jump`, `editors.py` or `theme.name` into its table and key, with bare
+// keys belonging to `[settings]`.
+pub fn split_key(key: &str) -> anyhow::Result<(&str, &str)> {
+    let (table, name) = key.split_once('.').unwrap_or(("settings", key));
+    match table {
+        "settings" if SETTINGS.contains(&name) => Ok((table, name)),
+        "settings" => anyhow::bail!(
+            "Unknown setting `{}`, expected one of: {}.",
+            name,
+            SETTINGS.join(", ")
+        ),
+        "editors" if !name.is_empty() => Ok((table, name)),
+        "theme" if THEME_KEYS.contains(&name) => Ok((table, name)),
+        "theme" => anyhow::bail!(
+            "Unknown theme element `{}`, expected one of: {}.",
+            name,
+            THEME_KEYS.join(", ")
+        ),
+        _ => anyhow::bail!(
+            "Unknown key `{}`, only [settings], [editors] and [theme] can be changed from here.",
+            key
+        ),
+    }
+}
+
+// Checks `value` against the types the configuration is read with.
+fn check_value(table: &str, key: &str, value: &toml::Value) -> Result<(), toml::de::Error> {
+    let mut section = toml::value::Table::new();
+    section.insert(key.to_string(), value.clone());
+    let mut document = toml::value::Table::new();
+    document.insert(table.to_string(), toml::Value::Table(section));
+    toml::Value::Table(document)
+        .try_into::<ReadConfig>()
+        .map(|_| ())
+}
+
+// Turns what was typed on the command line into a TOML value, treating anything that isn't valid
+// for the setting as one (like `vim`, `code -w` or `true` for an editor) as a plain string.
+pub fn parse_value(table: &str, key: &str, value: &str) -> anyhow::Result<toml::Value> {
+    let as_string = toml::Value::String(value.to_string());
+    let parsed = toml::from_str::<toml::value::Table>(&format!("v = {}", value))
+        .ok()
+        .and_then(|mut parsed| parsed.remove("v"))
+        .unwrap_or(as_string.clone());
+    match check_value(table, key, &parsed) {
+        Ok(()) => Ok(parsed),
+        Err(_) if check_value(table, key, &as_string).is_ok() => Ok(as_string),
+        Err(e) => anyhow::bail!("Invalid value `{}` for {}.{}: {}", value, table, key, e),
+    }
+}
+
+// Whether `line` assigns `key`, ignoring whitespace around the `=`.
+fn assigns(line: &str, key: &str) -> bool {
+    line.trim_start()
+        .strip_prefix(key)
+        .map(|rest| rest.trim_start().starts_with('='))
+        .unwrap_or(false)
+}
+
+fn raw(string: Option<&RawString>) -> String {
+    string.and_then(|s| s.as_str()).unwrap_or("").to_string()
+}
+
+// Splits the comments and blank lines in `prefix` right after a commented out `assignment`.
+fn split_example(prefix: &str, assignment: &str) -> Option<(String, String)> {
+    let mut end = 0;
+    for line in prefix.split_inclusive('\n') {
+        end += line.len();
+        let example = line
+            .trim_start()
+            .strip_prefix('#')
+            .is_some_and(|commented| assigns(commented, assignment));
+        if example {
+            let mut head = prefix[..end].to_string();
+            if !head.ends_with('\n') {
+                head.push('\n');
+            }
+            return Some((head, prefix[end..].to_string()));
+        }
+    }
+    None
+}
+
+fn replace_value(old: &mut Value, mut value: Value) {
+    *value.decor_mut() = old.decor().clone();
+    *old = value;
+}
+
+// The decor before the first table header following `position`, with dotted tables (which have
+// no position) coming before every header.
+fn following_header(document: &mut DocumentMut, position: Option<usize>) -> Option<&mut Decor> {
+    let mut headers = Vec::new();
+    for (_, item) in document.iter_mut() {
+        match item {
+            Item::Table(table) => headers.push(table),
+            Item::ArrayOfTables(array) => headers.extend(array.iter_mut()),
+            _ => {}
+        }
+    }
+    headers
+        .into_iter()
+        .filter(|table| table.position().is_some() && table.position() > position)
+        .min_by_key(|table| table.position())
+        .map(|table| table.decor_mut())
+}
+
+fn set_in_table(
+    document: &mut DocumentMut,
+    name: &str,
+    key: &str,
+    mut value: Value,
+) -> anyhow::Result<()> {
+    let Some(table) = document.get_mut(name).and_then(Item::as_table_mut) else {
+        anyhow::bail!("`{}` in bhop.toml isn't a table.", name);
+    };
+    match table.get_mut(key) {
+        Some(Item::Value(old)) => {
+            replace_value(old, value);
+            return Ok(());
+        }
+        Some(Item::None) | None => {}
+        Some(_) => anyhow::bail!("`{}.{}` in bhop.toml isn't a plain value.", name, key),
+    }
+    table.set_implicit(false);
+    let example = match table.is_dotted() {
+        true => format!("{}.{}", name, key),
+        false => key.to_string(),
+    };
+    // The new assignment is spaced like the table's last one.
+    let (suffix, spacing) = table
+        .iter()
+        .filter_map(|(k, item)| Some((table.key(k)?, item.as_value()?)))
+        .last()
+        .map(|(k, v)| (raw(k.leaf_decor().suffix()), raw(v.decor().prefix())))
+        .unwrap_or_default();
+    let mut new_key = Key::new(key).with_leaf_decor(Decor::new("", suffix));
+    *value.decor_mut() = Decor::new(spacing, "");
+
+    // A commented out example among the table's assignments is followed by the new one.
+    let keys: Vec<String> = table.iter().map(|(k, _)| k.to_string()).collect();
+    for (i, k) in keys.iter().enumerate() {
+        let prefix = table.key(k).map(|k| raw(k.leaf_decor().prefix()));
+        if let Some((head, rest)) = prefix.and_then(|p| split_example(&p, &example)) {
+            let following: Vec<(Key, Item)> = keys[i..]
+                .iter()
+                .filter_map(|k| table.remove_entry(k))
+                .collect();
+            new_key.leaf_decor_mut().set_prefix(head);
+            table.insert_formatted(&new_key, Item::Value(value));
+            for (j, (mut k, item)) in following.into_iter().enumerate() {
+                if j == 0 {
+                    k.leaf_decor_mut().set_prefix(rest.clone());
+                }
+                table.insert_formatted(&k, item);
+            }
+            return Ok(());
+        }
+    }
+
+    // Or it's among the comments before the next table, or at the end of the file.  Any blank
+    // lines before the example stay in front of the next table's header.
+    let position = table.position();
+    let mut moved = None;
+    if let Some(decor) = following_header(document, position) {
+        if let Some((head, rest)) = split_example(&raw(decor.prefix()), &example) {
+            let blank = head.len() - head.trim_start_matches(['\r', '\n']).len();
+            decor.set_prefix(format!("{}{}", &head[..blank], rest));
+            moved = Some(head[blank..].to_string());
+        }
+    } else {
+        let trailing = document.trailing().as_str().unwrap_or("").to_string();
+        if let Some((head, rest)) = split_example(&trailing, &example) {
+            document.set_trailing(rest);
+            moved = Some(head);
+        }
+    }
+    if let Some(head) = moved {
+        new_key.leaf_decor_mut().set_prefix(head);
+    }
+    if let Some(table) = document.get_mut(name).and_then(Item::as_table_mut) {
+        table.insert_formatted(&new_key, Item::Value(value));
+    }
+    Ok(())
+}
+
+fn set_in_inline(table: &mut InlineTable, key: &str, mut value: Value) {
+    if let Some(old) = table.get_mut(key) {
+        return replace_value(old, value);
+    }
+    // The padding before the closing brace moves over to the new last value.
+    let padding = table
+        .iter_mut()
+        .last()
+        .map(|(_, last)| {
+            let padding = raw(last.decor().suffix());
+            last.decor_mut().set_suffix("");
+            padding
+        })
+        .unwrap_or(" ".to_string());
+    *value.decor_mut() = Decor::new(" ", padding);
+    table.insert_formatted(&Key::new(key).with_leaf_decor(Decor::new(" ", " ")), value);
+}
+
+fn add_table(document: &mut DocumentMut, name: &str, key: &str, mut value: Value) {
+    // Comments at the end of the file stay above the new table.
+    let mut prefix = document.trailing().as_str().unwrap_or("").to_string();
+    if !prefix.is_empty() && !prefix.ends_with('\n') {
+        prefix.push('\n');
+    }
+    if !document.to_string().trim().is_empty() {
+        prefix.push('\n');
+    }
+    document.set_trailing("");
+    let mut table = Table::new();
+    table.decor_mut().set_prefix(prefix);
+    *value.decor_mut() = Decor::new("", "");
+    table.insert_formatted(
+        &Key::new(key).with_leaf_decor(Decor::new("", "")),
+        Item::Value(value),
+    );
+    document.insert(name, Item::Table(table));
+}
+
+// Sets `key` in `table` of the TOML document `toml` to `value`, in whichever form the table is
+// written (a `[table]`, dotted keys or an inline table), keeping the comments around an existing
+// assignment.  New keys go after a commented out example of themselves if there is one, or else
+// after the last assignment in the table.
+pub fn set_value(
+    toml: &str,
+    table: &str,
+    key: &str,
+    value: &toml::Value,
+) -> anyhow::Result<String> {
+    let mut document: DocumentMut = toml.parse()?;
+    let value: Value = value.to_string().parse()?;
+    match document.get_mut(table) {
+        None | Some(Item::None) => add_table(&mut document, table, key, value),
+        Some(Item::Table(_)) => set_in_table(&mut document, table, key, value)?,
+        Some(Item::Value(Value::InlineTable(inline))) => set_in_inline(inline, key, value),
+        Some(_) => anyhow::bail!("`{}` in bhop.toml isn't a table.", table),
+    }
+    let updated = document.to_string();
+    if toml.contains("\r\n") {
+        Ok(updated.replace("\r\n", "\n").replace('\n', "\r\n"))
+    } else {
+        Ok(updated)
+    }
+}
+
+fn value_of<T: serde::Serialize>(value: T) -> String {
+    toml::Value::try_from(value)
+        .map(|v| v.to_string())
+        .unwrap_or_default()
+}
+
+// The value `key` resolved to after defaults are applied.
+pub fn effective_value(config: &Configs, key: &str) -> String {
+    match key {
+        "default_editor" => value_of(&config.default_editor),
+        "ls_display_block" => value_of(config.ls_display_block),
+        "print_color_primary" => value_of(config.print_color_primary),
+        "print_color_secondary" => value_of(config.print_color_secondary),
+        "verbose" => value_of(config.verbose),
+        "prioritize_shortcuts" => value_of(config.prioritize_shortcuts),
+        "always_jump" => value_of(config.always_jump),
+        "search_match_prefix" => value_of(&config.search_match_prefix),
+        "search_match_suffix" => value_of(&config.search_match_suffix),
+        "relocate_roots" => value_of(&config.relocate_roots),
+        "relocate_max_depth" => value_of(config.relocate_max_depth),
+        "repo_roots" => value_of(&config.repo_roots),
+        "repo_max_depth" => value_of(config.repo_max_depth),
+        "project_markers" => value_of(&config.project_markers),
+        "history_exclude" => value_of(&config.history_rules.exclude),
+        "history_min_depth" => value_of(config.history_rules.min_depth),
+        "log_file" => value_of(config.log_file),
+        "color" => value_of(config.color.to_string()),
+        _ => "".to_string(),
+    }
+}
+
+impl Hopper {
+    fn read_config_file(&self) -> anyhow::Result<String> {
+        match fs::read_to_string(&self.env.config_path) {
+            Ok(contents) => Ok(contents),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok("".to_string()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    // A table of bhop.toml, after checking the file reads as a configuration.
+    fn file_table(&self, table: &str) -> anyhow::Result<toml::value::Table> {
+        let contents = self.read_config_file()?;
+        toml::from_str::<ReadConfig>(&contents)?;
+        let mut document: toml::value::Table = toml::from_str(&contents)?;
+        match document.remove(table) {
+            Some(toml::Value::Table(table)) => Ok(table),
+            _ => Ok(toml::value::Table::new()),
+        }
+    }
+
+    pub(crate) fn config_get(&self, key: String) -> anyhow::Result<String> {
+        match split_key(&key)? {
+            ("settings", name) => Ok(effective_value(&self.config, name)),
+            ("theme", name) => Ok(self
+                .config
+                .theme
+                .get(name)
+                .map(|style| style.to_string())
+                .unwrap_or_default()),
+            (_, name) => match self.config.editors.get(name) {
+                Some(editor) => Ok(editor.to_string()),
+                None => Ok(format!("No editor set for `{}`.", name)),
+            },
+        }
+    }
+
+    pub(crate) fn config_set(&mut self, key: String, value: String) -> anyhow::Result<String> {
+        let (table, name) = split_key(&key)?;
+        let parsed = parse_value(table, name, &value)?;
+        let contents = set_value(&self.read_config_file()?, table, name, &parsed)?;
+        // Nothing is written unless the whole file still reads back.
+        toml::from_str::<ReadConfig>(&contents)
+            .map_err(|e| anyhow::anyhow!("Refusing to write an unreadable bhop.toml: {}", e))?;
+        let staged = self.env.config_path.with_extension("toml.new");
+        fs::write(&staged, &contents)?;
+        fs::rename(&staged, &self.env.config_path)?;
+        let layers = self.config_layers()?;
+        self.config = Configs::from_layers(&layers, &self.config_env())?;
+        // Higher layers still win over the value just written.
+        let overridden = match layers::origin(&layers, table, name) {
+            Some(origin @ (Origin::Project(_) | Origin::Env(_))) => {
+                format!("  It's overridden by {} though.", origin)
+            }
+            _ => "".to_string(),
+        };
+        Ok(format!(
+            "Set {}.{} to {}.{}",
+            table, name, parsed, overridden
+        ))
+    }
+
+    // Where the value of `key` came from.
+    fn origin(&self, layers: &[ConfigLayer], table: &str, key: &str) -> String {
+        match layers::origin(layers, table, key) {
+            Some(origin) => origin.to_string(),
+            None if key == "default_editor" && self.var("EDITOR").is_some() => {
+                "env EDITOR".to_string()
+            }
+            None => "default".to_string(),
+        }
+    }
+
+    // Without `effective` only what's set in the user's bhop.toml is listed.
+    pub(crate) fn config_list(&self, effective: bool) -> anyhow::Result<String> {
+        let mut rows = Vec::new();
+        if effective {
+            let layers = self.config_layers()?;
+            for key in SETTINGS {
+                rows.push([
+                    format!("settings.{}", key),
+                    effective_value(&self.config, key),
+                    self.origin(&layers, "settings", key),
+                ]);
+            }
+            let mut editors: Vec<_> = self.config.editors.iter().collect();
+            editors.sort();
+            for (ext, editor) in editors {
+                rows.push([
+                    format!("editors.{}", ext),
+                    toml::Value::String(editor.to_string()).to_string(),
+                    self.origin(&layers, "editors", ext),
+                ]);
+            }
+            for key in THEME_KEYS {
+                let style = self.config.theme.get(key).unwrap_or_default();
+                rows.push([
+                    format!("theme.{}", key),
+                    toml::Value::String(style.to_string()).to_string(),
+                    self.origin(&layers, "theme", key),
+                ]);
+            }
+        } else {
+            for table in ["settings", "editors", "theme"] {
+                for (key, value) in self.file_table(table)? {
+                    rows.push([
+                        format!("{}.{}", table, key),
+                        value.to_string(),
+                        "".to_string(),
+                    ]);
+                }
+            }
+        }
+        if rows.is_empty() {
+            return Ok("Nothing set in bhop.toml.".to_string());
+        }
+        self.format_map(rows);
+        Ok("".to_string())
+    }
+}
@@ -0,0 +1,80 @@
+// Plain representation of the shortcuts table used when editing every shortcut at once as a TOML
+// file with `hp edit-shortcuts`.
+use serde_derive::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct ShortcutEntry {
+    pub location: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct ShortcutFile {
+    #[serde(default)]
+    pub shortcuts: BTreeMap<String, ShortcutEntry>,
+}
+
+impl ShortcutFile {
+    pub fn to_toml(&self) -> anyhow::Result<String> {
+        Ok(format!(
+            "# Add, remove, rename or edit the shortcuts below.  Changes are applied once the\n\
+             # editor is closed.\n\n{}",
+            toml::to_string(self)?
+        ))
+    }
+
+    pub fn from_toml(toml: &str) -> anyhow::Result<Self> {
+        let mut file: ShortcutFile = toml::from_str(toml)?;
+        for (name, entry) in file.shortcuts.iter_mut() {
+            if name.is_empty() || entry.location.is_empty() {
+                anyhow::bail!("Shortcut `{}` needs both a name and a location.", name);
+            }
+            entry.tags.sort();
+            entry.tags.dedup();
+        }
+        Ok(file)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    Add(String, ShortcutEntry),
+    Update(String, ShortcutEntry),
+    Remove(String),
+}
+
+impl Change {
+    pub fn name(&self) -> &str {
+        match self {
+            Change::Add(name, _) | Change::Update(name, _) | Change::Remove(name) => name,
+        }
+    }
+}
+
+// Works out the changes needed to turn `original` into `edited`.  A renamed shortcut shows up as
+// the old name being removed and the new one added.
+pub fn diff(
+    original: &BTreeMap<String, ShortcutEntry>,
+    edited: &BTreeMap<String, ShortcutEntry>,
+) -> Vec<Change> {
+    let mut changes = Vec::new();
+    for (name, entry) in edited.iter() {
+        match original.get(name) {
+            None => changes.push(Change::Add(name.to_string(), entry.clone())),
+            Some(old) if old != entry => {
+                changes.push(Change::Update(name.to_string(), entry.clone()))
+            }
+            _ => {}
+        }
+    }
+    for name in original.keys() {
+        if !edited.contains_key(name) {
+            changes.push(Change::Remove(name.to_string()));
+        }
+    }
+    changes
+}
@@ -0,0 +1,145 @@
+// `hp stats`, a summary of how shortcuts and directories are actually used, built from the
+// timestamped shortcut uses and directory visits kept by the store.
+use crate::history::ago;
+use crate::logging::trace;
+use crate::theme::Style;
+use crate::Hopper;
+
+const DAY: i64 = 24 * 60 * 60;
+const WEEK: i64 = 7 * DAY;
+const WEEKS_SHOWN: i64 = 4;
+const TOP: usize = 5;
+
+fn week_label(weeks_back: i64) -> String {
+    match weeks_back {
+        0 => "this week".to_string(),
+        1 => "last week".to_string(),
+        n => format!("{} weeks ago", n),
+    }
+}
+
+impl Hopper {
+    pub(crate) fn record_shortcut_use(&mut self, name: &str) {
+        // Failing to count a use is no reason to fail the jump itself.
+        let now = chrono::Utc::now().timestamp();
+        if let Err(e) = self.store.record_shortcut_use(name, now) {
+            trace!(1, "unable to count a use of `{}`: {}", name, e);
+        }
+    }
+
+    fn most_used_shortcuts(&self, now: i64) -> anyhow::Result<Vec<[String; 3]>> {
+        let usage = self.store.shortcut_usage()?;
+        let mut used: Vec<(String, String, i64, i64)> = self
+            .store
+            .shortcuts()?
+            .into_iter()
+            .filter_map(|(name, entry)| {
+                let (uses, last_used) = usage.get(&name)?;
+                Some((name, entry.location, *uses, *last_used))
+            })
+            .collect();
+        used.sort_by(|a, b| b.2.cmp(&a.2).then(a.0.cmp(&b.0)));
+        Ok(used
+            .into_iter()
+            .take(TOP)
+            .map(|(name, location, uses, last_used)| {
+                [
+                    name,
+                    location,
+                    format!(
+                        "{} use{}, last {}",
+                        uses,
+                        if uses == 1 { "" } else { "s" },
+                        ago(last_used, now)
+                    ),
+                ]
+            })
+            .collect())
+    }
+
+    fn busiest_directories(&self, from: i64, to: i64) -> anyhow::Result<Vec<[String; 3]>> {
+        let mut counts: Vec<(String, i64)> =
+            self.store.visit_counts(from, to)?.into_iter().collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        Ok(counts
+            .into_iter()
+            .take(TOP)
+            .map(|(location, visits)| {
+                let name = location
+                    .rsplit('/')
+                    .find(|part| !part.is_empty())
+                    .unwrap_or("/")
+                    .to_string();
+                [
+                    name,
+                    location,
+                    format!("{} visit{}", visits, if visits == 1 { "" } else { "s" }),
+                ]
+            })
+            .collect())
+    }
+
+    // Shortcuts created more than `days` ago that haven't been used since.  Shortcuts from
+    // before creation times were recorded count as old enough.
+    fn unused_shortcuts(&self, days: i64, now: i64) -> anyhow::Result<Vec<[String; 3]>> {
+        let cutoff = now - days * DAY;
+        let usage = self.store.shortcut_usage()?;
+        let mut rows = Vec::new();
+        for (name, entry) in self.store.shortcuts()? {
+            let created_at = self.store.created_at(&name)?;
+            let last_used = usage.get(&name).map(|(_, last_used)| *last_used);
+            if created_at.unwrap_or(0) >= cutoff || last_used.unwrap_or(0) >= cutoff {
+                continue;
+            }
+            let note = match (last_used, created_at) {
+                (Some(last_used), _) => format!("last used {}", ago(last_used, now)),
+                (None, Some(created_at)) => format!("never used, added {}", ago(created_at, now)),
+                (None, None) => "never used".to_string(),
+            };
+            rows.push([name, entry.location, note]);
+        }
+        Ok(rows)
+    }
+
+    // The titled tables of `hp stats` as of `now`: the most used shortcuts, the busiest
+    // directories of each of the last few weeks and the shortcuts unused for over `days` days.
+    pub fn stats_report(
+        &self,
+        days: i64,
+        now: i64,
+    ) -> anyhow::Result<Vec<(String, Vec<[String; 3]>)>> {
+        let mut report = vec![(
+            "Most used shortcuts:".to_string(),
+            self.most_used_shortcuts(now)?,
+        )];
+        // The weeks don't overlap, only the current one also takes in visits made at `now`.
+        for weeks_back in 0..WEEKS_SHOWN {
+            let to = now - weeks_back * WEEK;
+            let end = if weeks_back == 0 { now + 1 } else { to };
+            report.push((
+                format!("Busiest directories {}:", week_label(weeks_back)),
+                self.busiest_directories(to - WEEK, end)?,
+            ));
+        }
+        report.push((
+            format!("Shortcuts unused for over {} days:", days),
+            self.unused_shortcuts(days, now)?,
+        ));
+        Ok(report)
+    }
+
+    pub(crate) fn stats(&self, days: i64) -> anyhow::Result<String> {
+        for (title, rows) in self.stats_report(days, chrono::Utc::now().timestamp())? {
+            println!(
+                "{}",
+                self.paint(&title, Style::named("bright_white").bold())
+            );
+            if rows.is_empty() {
+                println!("{}", self.paint("  nothing yet", Style::default().dim()));
+            } else {
+                self.format_map(rows);
+            }
+        }
+        Ok("".to_string())
+    }
+}
@@ -0,0 +1,924 @@
+// Storage for shortcuts, history and the undo journal.  `Hopper` only talks to its store through
+// the `Store` trait, so the SQLite database used by `hp` can be swapped for the in-memory store
+// when embedding bhop or testing without touching the disk.
+use crate::identity::Identity;
+use crate::logging::trace;
+use crate::metadata::Environment;
+use crate::shortcuts::ShortcutEntry;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+const DAY: i64 = 24 * 60 * 60;
+
+// Individual visits are kept for this long, which is plenty for scoring recent activity.  Older
+// visits still count towards an entry's `usage`.
+const VISIT_RETENTION: i64 = 90 * DAY;
+
+// History entries with their frecency score: recent visits count for more than old ones, and
+// visits from before timestamps were recorded (or that have since expired) count the least.
+// `?1` is the current time.
+const SCORED_HISTORY: &str = "SELECT h.name AS name, h.location AS location,
+        h.usage AS usage, MAX(v.visited_at) AS last_visit,
+        COALESCE(SUM(CASE
+            WHEN v.id IS NULL THEN 0.0
+            WHEN v.visited_at >= ?1 - 86400 THEN 4.0
+            WHEN v.visited_at >= ?1 - 604800 THEN 2.0
+            WHEN v.visited_at >= ?1 - 2592000 THEN 1.0
+            ELSE 0.5 END), 0.0) + MAX(h.usage - COUNT(v.id), 0) * 0.25 AS score
+    FROM history AS h LEFT JOIN visits AS v ON v.location = h.location
+    GROUP BY h.name, h.location";
+
+// The same weighting as `SCORED_HISTORY`, for stores that keep their visits in memory.
+fn frecency(usage: i64, visits: &[i64], now: i64) -> f64 {
+    let recent: f64 = visits
+        .iter()
+        .map(|visited_at| match now - visited_at {
+            age if age <= DAY => 4.0,
+            age if age <= 7 * DAY => 2.0,
+            age if age <= 30 * DAY => 1.0,
+            _ => 0.5,
+        })
+        .sum();
+    recent + (usage - visits.len() as i64).max(0) as f64 * 0.25
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryEntry {
+    pub name: String,
+    pub location: String,
+    pub usage: i64,
+    pub last_visit: Option<i64>,
+    pub score: f64,
+}
+
+// A shortcut together with everything else stored in its row, so that undoing a change puts it
+// back exactly as it was.  Changes journaled before the identity and creation time were kept
+// just leave them out.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct ShortcutRecord {
+    #[serde(flatten)]
+    pub entry: ShortcutEntry,
+    #[serde(flatten)]
+    pub identity: Identity,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<i64>,
+}
+
+// A shortcut as it was before and after a change, with `None` meaning it didn't exist.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JournalChange {
+    pub name: String,
+    pub old_entry: Option<ShortcutRecord>,
+    pub new_entry: Option<ShortcutRecord>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JournalBatch {
+    pub batch: i64,
+    pub operation: String,
+    pub changes: Vec<JournalChange>,
+}
+
+// A directory walked while indexing `repo_roots`.  `mtime` is only kept for directories whose
+// subdirectories were read, and `remote` only for repositories.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexedDir {
+    pub location: String,
+    pub parent: String,
+    pub name: String,
+    pub mtime: Option<i64>,
+    pub repo: bool,
+    pub remote: Option<String>,
+}
+
+pub trait Store {
+    fn begin(&mut self) -> anyhow::Result<()>;
+    fn commit(&mut self) -> anyhow::Result<()>;
+    fn rollback(&mut self) -> anyhow::Result<()>;
+
+    fn shortcut(&self, name: &str) -> anyhow::Result<Option<ShortcutEntry>>;
+    fn shortcuts(&self) -> anyhow::Result<BTreeMap<String, ShortcutEntry>>;
+    // Inserts or replaces the shortcut, tags included.
+    fn write_shortcut(&mut self, name: &str, entry: &ShortcutEntry) -> anyhow::Result<()>;
+    fn delete_shortcut(&mut self, name: &str) -> anyhow::Result<()>;
+    fn rename_shortcut(&mut self, old: &str, new: &str) -> anyhow::Result<()>;
+    fn identity(&self, name: &str) -> anyhow::Result<Identity>;
+    fn set_identity(&mut self, name: &str, identity: &Identity) -> anyhow::Result<()>;
+    // When the shortcut was added, if that was recorded.
+    fn created_at(&self, name: &str) -> anyhow::Result<Option<i64>>;
+    // The shortcut along with its identity and creation time.
+    fn shortcut_record(&self, name: &str) -> anyhow::Result<Option<ShortcutRecord>> {
+        Ok(match self.shortcut(name)? {
+            Some(entry) => Some(ShortcutRecord {
+                entry,
+                identity: self.identity(name)?,
+                created_at: self.created_at(name)?,
+            }),
+            None => None,
+        })
+    }
+    // Writes the shortcut back with the identity and creation time in `record`, replacing it if
+    // it exists.
+    fn restore_shortcut(&mut self, name: &str, record: &ShortcutRecord) -> anyhow::Result<()>;
+
+    fn record_shortcut_use(&mut self, name: &str, now: i64) -> anyhow::Result<()>;
+    // How many times each shortcut was used and when it was last used.
+    fn shortcut_usage(&self) -> anyhow::Result<HashMap<String, (i64, i64)>>;
+    // How many times each location was visited from `from` up to (but not including) `to`.
+    fn visit_counts(&self, from: i64, to: i64) -> anyhow::Result<HashMap<String, i64>>;
+
+    fn record_visit(&mut self, name: &str, location: &str, now: i64) -> anyhow::Result<()>;
+    // The location and time of the most recent visit.
+    fn last_visit(&self) -> anyhow::Result<Option<(String, i64)>>;
+    // Every history entry, scored as of `now`.
+    fn history(&self, now: i64) -> anyhow::Result<Vec<HistoryEntry>>;
+    // Removes the history entry at `location` along with its visits.
+    fn delete_history(&mut self, location: &str) -> anyhow::Result<()>;
+    // Points the history entry at `location` and its visits to `moved` instead, replacing any
+    // existing entry.
+    fn move_history(&mut self, location: &str, name: &str, moved: &str) -> anyhow::Result<()>;
+
+    // Records `changes` as the newest batch, keeping only the last `keep` batches.
+    fn push_journal(
+        &mut self,
+        operation: &str,
+        changes: &[JournalChange],
+        keep: i64,
+    ) -> anyhow::Result<()>;
+    // The newest batch, with its changes from last to first.
+    fn last_journal(&self) -> anyhow::Result<Option<JournalBatch>>;
+    fn drop_journal(&mut self, batch: i64) -> anyhow::Result<()>;
+
+    fn indexed_dir(&self, location: &str) -> anyhow::Result<Option<IndexedDir>>;
+    // Locations of the indexed directories directly under `parent`.
+    fn indexed_children(&self, parent: &str) -> anyhow::Result<Vec<String>>;
+    fn indexed_locations(&self) -> anyhow::Result<Vec<String>>;
+    fn write_indexed_dir(&mut self, dir: &IndexedDir) -> anyhow::Result<()>;
+    fn delete_indexed_dir(&mut self, location: &str) -> anyhow::Result<()>;
+    // The indexed repositories, by name and then location.
+    fn repos(&self) -> anyhow::Result<Vec<IndexedDir>>;
+}
+
+pub struct SqliteStore {
+    conn: sqlite::Connection,
+}
+
+impl SqliteStore {
+    pub fn new(conn: sqlite::Connection) -> Self {
+        SqliteStore { conn }
+    }
+
+    pub fn open(db_path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        Ok(SqliteStore::new(Environment::connect(db_path)?))
+    }
+
+    fn prepare<T: AsRef<str>>(&self, sql: T) -> sqlite::Result<sqlite::Statement<'_>> {
+        trace_sql(sql.as_ref());
+        self.conn.prepare(sql)
+    }
+
+    fn execute<T: AsRef<str>>(&self, sql: T) -> sqlite::Result<()> {
+        trace_sql(sql.as_ref());
+        self.conn.execute(sql)
+    }
+
+    fn delete_tags(&self, name: &str) -> anyhow::Result<()> {
+        let mut statement = self.prepare("DELETE FROM tags WHERE name = ?")?;
+        statement.bind((1, name))?;
+        statement.next()?;
+        Ok(())
+    }
+}
+
+// Statements are logged on a single line, however they're laid out in the source.
+fn trace_sql(sql: &str) {
+    trace!(
+        2,
+        "sql: {}",
+        sql.split_whitespace().collect::<Vec<_>>().join(" ")
+    );
+}
+
+fn serialize(entry: &Option<ShortcutRecord>) -> anyhow::Result<Option<String>> {
+    match entry {
+        Some(entry) => Ok(Some(toml::to_string(entry)?)),
+        None => Ok(None),
+    }
+}
+
+fn deserialize(entry: Option<String>) -> anyhow::Result<Option<ShortcutRecord>> {
+    match entry {
+        Some(entry) => Ok(Some(toml::from_str(&entry)?)),
+        None => Ok(None),
+    }
+}
+
+impl Store for SqliteStore {
+    fn begin(&mut self) -> anyhow::Result<()> {
+        self.execute("BEGIN IMMEDIATE")?;
+        Ok(())
+    }
+
+    fn commit(&mut self) -> anyhow::Result<()> {
+        self.execute("COMMIT")?;
+        Ok(())
+    }
+
+    fn rollback(&mut self) -> anyhow::Result<()> {
+        self.execute("ROLLBACK")?;
+        Ok(())
+    }
+
+    fn shortcut(&self, name: &str) -> anyhow::Result<Option<ShortcutEntry>> {
+        let mut statement =
+            self.prepare("SELECT location, description FROM shortcuts WHERE name = ?")?;
+        statement.bind((1, name))?;
+        let mut entry = match statement.next()? {
+            sqlite::State::Row => ShortcutEntry {
+                location: statement.read::<String, _>("location")?,
+                description: statement.read::<Option<String>, _>("description")?,
+                tags: Vec::new(),
+            },
+            sqlite::State::Done => return Ok(None),
+        };
+        let mut statement = self.prepare("SELECT tag FROM tags WHERE name = ? ORDER BY tag")?;
+        statement.bind((1, name))?;
+        while let sqlite::State::Row = statement.next()? {
+            entry.tags.push(statement.read::<String, _>("tag")?);
+        }
+        Ok(Some(entry))
+    }
+
+    fn shortcuts(&self) -> anyhow::Result<BTreeMap<String, ShortcutEntry>> {
+        let mut statement = self.prepare("SELECT name, location, description FROM shortcuts")?;
+        let mut entries = BTreeMap::new();
+        while let sqlite::State::Row = statement.next()? {
+            entries.insert(
+                statement.read::<String, _>("name")?,
+                ShortcutEntry {
+                    location: statement.read::<String, _>("location")?,
+                    description: statement.read::<Option<String>, _>("description")?,
+                    tags: Vec::new(),
+                },
+            );
+        }
+        let mut statement = self.prepare("SELECT name, tag FROM tags ORDER BY tag")?;
+        while let sqlite::State::Row = statement.next()? {
+            let name = statement.read::<String, _>("name")?;
+            if let Some(entry) = entries.get_mut(&name) {
+                entry.tags.push(statement.read::<String, _>("tag")?);
+            }
+        }
+        Ok(entries)
+    }
+
+    fn write_shortcut(&mut self, name: &str, entry: &ShortcutEntry) -> anyhow::Result<()> {
+        // Upsert rather than replace so the identity and creation time of an existing shortcut
+        // survive it being edited.
+        let mut statement = self.prepare(
+            "INSERT INTO shortcuts (name, location, description, created_at) VALUES (?, ?, ?, ?)
+                ON CONFLICT(name) DO UPDATE
+                SET location = excluded.location, description = excluded.description",
+        )?;
+        statement.bind((1, name))?;
+        statement.bind((2, entry.location.as_str()))?;
+        statement.bind((3, entry.description.as_deref()))?;
+        statement.bind((4, chrono::Utc::now().timestamp()))?;
+        statement.next()?;
+        self.delete_tags(name)?;
+        for tag in entry.tags.iter() {
+            let mut statement =
+                self.prepare("INSERT OR IGNORE INTO tags (name, tag) VALUES (?, ?)")?;
+            statement.bind((1, name))?;
+            statement.bind((2, tag.as_str()))?;
+            statement.next()?;
+        }
+        Ok(())
+    }
+
+    fn delete_shortcut(&mut self, name: &str) -> anyhow::Result<()> {
+        let mut statement = self.prepare("DELETE FROM shortcuts WHERE name = ?")?;
+        statement.bind((1, name))?;
+        statement.next()?;
+        self.delete_tags(name)
+    }
+
+    fn rename_shortcut(&mut self, old: &str, new: &str) -> anyhow::Result<()> {
+        for table in ["shortcuts", "tags"] {
+            let query = format!("UPDATE {} SET name = ? WHERE name = ?", table);
+            let mut statement = self.prepare(query)?;
+            statement.bind((1, new))?;
+            statement.bind((2, old))?;
+            statement.next()?;
+        }
+        Ok(())
+    }
+
+    fn identity(&self, name: &str) -> anyhow::Result<Identity> {
+        let mut statement =
+            self.prepare("SELECT device, inode, remote FROM shortcuts WHERE name = ?")?;
+        statement.bind((1, name))?;
+        match statement.next()? {
+            sqlite::State::Row => Ok(Identity {
+                device: statement.read::<Option<i64>, _>("device")?,
+                inode: statement.read::<Option<i64>, _>("inode")?,
+                remote: statement.read::<Option<String>, _>("remote")?,
+            }),
+            sqlite::State::Done => Ok(Identity::default()),
+        }
+    }
+
+    fn set_identity(&mut self, name: &str, identity: &Identity) -> anyhow::Result<()> {
+        let mut statement =
+            self.prepare("UPDATE shortcuts SET device = ?, inode = ?, remote = ? WHERE name = ?")?;
+        statement.bind((1, identity.device))?;
+        statement.bind((2, identity.inode))?;
+        statement.bind((3, identity.remote.as_deref()))?;
+        statement.bind((4, name))?;
+        statement.next()?;
+        Ok(())
+    }
+
+    fn created_at(&self, name: &str) -> anyhow::Result<Option<i64>> {
+        let mut statement = self.prepare("SELECT created_at FROM shortcuts WHERE name = ?")?;
+        statement.bind((1, name))?;
+        match statement.next()? {
+            sqlite::State::Row => Ok(statement.read::<Option<i64>, _>("created_at")?),
+            sqlite::State::Done => Ok(None),
+        }
+    }
+
+    fn restore_shortcut(&mut self, name: &str, record: &ShortcutRecord) -> anyhow::Result<()> {
+        self.write_shortcut(name, &record.entry)?;
+        self.set_identity(name, &record.identity)?;
+        let mut statement = self.prepare("UPDATE shortcuts SET created_at = ? WHERE name = ?")?;
+        statement.bind((1, record.created_at))?;
+        statement.bind((2, name))?;
+        statement.next()?;
+        Ok(())
+    }
+
+    fn record_shortcut_use(&mut self, name: &str, now: i64) -> anyhow::Result<()> {
+        let mut statement =
+            self.prepare("INSERT INTO shortcut_uses (name, used_at) VALUES (?, ?)")?;
+        statement.bind((1, name))?;
+        statement.bind((2, now))?;
+        statement.next()?;
+        Ok(())
+    }
+
+    fn shortcut_usage(&self) -> anyhow::Result<HashMap<String, (i64, i64)>> {
+        let mut statement = self.prepare(
+            "SELECT name, COUNT(*) AS uses, MAX(used_at) AS last_used
+            FROM shortcut_uses GROUP BY name",
+        )?;
+        let mut usage = HashMap::new();
+        while let sqlite::State::Row = statement.next()? {
+            usage.insert(
+                statement.read::<String, _>("name")?,
+                (
+                    statement.read::<i64, _>("uses")?,
+                    statement.read::<i64, _>("last_used")?,
+                ),
+            );
+        }
+        Ok(usage)
+    }
+
+    fn visit_counts(&self, from: i64, to: i64) -> anyhow::Result<HashMap<String, i64>> {
+        let mut statement = self.prepare(
+            "SELECT location, COUNT(*) AS visits FROM visits
+            WHERE visited_at >= ? AND visited_at < ? GROUP BY location",
+        )?;
+        statement.bind((1, from))?;
+        statement.bind((2, to))?;
+        let mut counts = HashMap::new();
+        while let sqlite::State::Row = statement.next()? {
+            counts.insert(
+                statement.read::<String, _>("location")?,
+                statement.read::<i64, _>("visits")?,
+            );
+        }
+        Ok(counts)
+    }
+
+    fn record_visit(&mut self, name: &str, location: &str, now: i64) -> anyhow::Result<()> {
+        let mut statement = self.prepare(
+            "INSERT INTO history (name, location, usage) VALUES (?, ?, 1)
+            ON CONFLICT(name, location) DO UPDATE SET usage = usage + 1",
+        )?;
+        statement.bind((1, name))?;
+        statement.bind((2, location))?;
+        statement.next()?;
+        let mut statement =
+            self.prepare("INSERT INTO visits (location, visited_at) VALUES (?, ?)")?;
+        statement.bind((1, location))?;
+        statement.bind((2, now))?;
+        statement.next()?;
+        let mut statement = self.prepare("DELETE FROM visits WHERE visited_at < ?")?;
+        statement.bind((1, now - VISIT_RETENTION))?;
+        statement.next()?;
+        Ok(())
+    }
+
+    fn last_visit(&self) -> anyhow::Result<Option<(String, i64)>> {
+        let mut statement = self.prepare(
+            "SELECT location, visited_at FROM visits ORDER BY visited_at DESC, rowid DESC LIMIT 1",
+        )?;
+        match statement.next()? {
+            sqlite::State::Row => Ok(Some((
+                statement.read::<String, _>("location")?,
+                statement.read::<i64, _>("visited_at")?,
+            ))),
+            sqlite::State::Done => Ok(None),
+        }
+    }
+
+    fn history(&self, now: i64) -> anyhow::Result<Vec<HistoryEntry>> {
+        let mut statement = self.prepare(format!(
+            "{} ORDER BY score DESC, usage DESC, name",
+            SCORED_HISTORY
+        ))?;
+        statement.bind((1, now))?;
+        let mut history = Vec::new();
+        while let sqlite::State::Row = statement.next()? {
+            history.push(HistoryEntry {
+                name: statement.read::<String, _>("name")?,
+                location: statement.read::<String, _>("location")?,
+                usage: statement.read::<i64, _>("usage")?,
+                last_visit: statement.read::<Option<i64>, _>("last_visit")?,
+                score: statement.read::<f64, _>("score")?,
+            });
+        }
+        Ok(history)
+    }
+
+    fn delete_history(&mut self, location: &str) -> anyhow::Result<()> {
+        for table in ["history", "visits"] {
+            let mut statement =
+                self.prepare(format!("DELETE FROM {} WHERE location = ?", table))?;
+            statement.bind((1, location))?;
+            statement.next()?;
+        }
+        Ok(())
+    }
+
+    fn move_history(&mut self, location: &str, name: &str, moved: &str) -> anyhow::Result<()> {
+        let mut statement = self
+            .prepare("UPDATE OR REPLACE history SET name = ?, location = ? WHERE location = ?")?;
+        statement.bind((1, name))?;
+        statement.bind((2, moved))?;
+        statement.bind((3, location))?;
+        statement.next()?;
+        let mut statement = self.prepare("UPDATE visits SET location = ? WHERE location = ?")?;
+        statement.bind((1, moved))?;
+        statement.bind((2, location))?;
+        statement.next()?;
+        Ok(())
+    }
+
+    fn push_journal(
+        &mut self,
+        operation: &str,
+        changes: &[JournalChange],
+        keep: i64,
+    ) -> anyhow::Result<()> {
+        let mut statement =
+            self.prepare("SELECT COALESCE(MAX(batch), 0) + 1 AS batch FROM journal")?;
+        statement.next()?;
+        let batch = statement.read::<i64, _>("batch")?;
+        let now = chrono::Utc::now().timestamp();
+        for change in changes {
+            let mut statement = self.prepare(
+                "INSERT INTO journal (batch, operation, name, old_entry, new_entry, created_at)
+                    VALUES (?, ?, ?, ?, ?, ?)",
+            )?;
+            statement.bind((1, batch))?;
+            statement.bind((2, operation))?;
+            statement.bind((3, change.name.as_str()))?;
+            statement.bind((4, serialize(&change.old_entry)?.as_deref()))?;
+            statement.bind((5, serialize(&change.new_entry)?.as_deref()))?;
+            statement.bind((6, now))?;
+            statement.next()?;
+        }
+        self.execute(format!(
+            "DELETE FROM journal WHERE batch <= (SELECT MAX(batch) FROM journal) - {}",
+            keep
+        ))?;
+        Ok(())
+    }
+
+    fn last_journal(&self) -> anyhow::Result<Option<JournalBatch>> {
+        let mut statement =
+            self.prepare("SELECT batch, operation FROM journal ORDER BY batch DESC LIMIT 1")?;
+        let (batch, operation) = match statement.next()? {
+            sqlite::State::Row => (
+                statement.read::<i64, _>("batch")?,
+                statement.read::<String, _>("operation")?,
+            ),
+            sqlite::State::Done => return Ok(None),
+        };
+        let mut statement = self.prepare(
+            "SELECT name, old_entry, new_entry FROM journal WHERE batch = ? ORDER BY id DESC",
+        )?;
+        statement.bind((1, batch))?;
+        let mut changes = Vec::new();
+        while let sqlite::State::Row = statement.next()? {
+            changes.push(JournalChange {
+                name: statement.read::<String, _>("name")?,
+                old_entry: deserialize(statement.read::<Option<String>, _>("old_entry")?)?,
+                new_entry: deserialize(statement.read::<Option<String>, _>("new_entry")?)?,
+            });
+        }
+        Ok(Some(JournalBatch {
+            batch,
+            operation,
+            changes,
+        }))
+    }
+
+    fn drop_journal(&mut self, batch: i64) -> anyhow::Result<()> {
+        let mut statement = self.prepare("DELETE FROM journal WHERE batch = ?")?;
+        statement.bind((1, batch))?;
+        statement.next()?;
+        Ok(())
+    }
+
+    fn indexed_dir(&self, location: &str) -> anyhow::Result<Option<IndexedDir>> {
+        let mut statement = self.prepare(format!("{} WHERE location = ?", INDEXED_DIRS))?;
+        statement.bind((1, location))?;
+        match statement.next()? {
+            sqlite::State::Row => Ok(Some(read_indexed_dir(&statement)?)),
+            sqlite::State::Done => Ok(None),
+        }
+    }
+
+    fn indexed_children(&self, parent: &str) -> anyhow::Result<Vec<String>> {
+        let mut statement = self.prepare("SELECT location FROM repo_index WHERE parent = ?")?;
+        statement.bind((1, parent))?;
+        let mut children = Vec::new();
+        while let sqlite::State::Row = statement.next()? {
+            children.push(statement.read::<String, _>("location")?);
+        }
+        Ok(children)
+    }
+
+    fn indexed_locations(&self) -> anyhow::Result<Vec<String>> {
+        let mut statement = self.prepare("SELECT location FROM repo_index")?;
+        let mut locations = Vec::new();
+        while let sqlite::State::Row = statement.next()? {
+            locations.push(statement.read::<String, _>("location")?);
+        }
+        Ok(locations)
+    }
+
+    fn write_indexed_dir(&mut self, dir: &IndexedDir) -> anyhow::Result<()> {
+        let mut statement = self.prepare(
+            "INSERT INTO repo_index (location, parent, name, mtime, repo, remote)
+            VALUES (?, ?, ?, ?, ?, ?)
+            ON CONFLICT(location) DO UPDATE SET parent = excluded.parent, name = excluded.name,
+            mtime = excluded.mtime, repo = excluded.repo, remote = excluded.remote",
+        )?;
+        statement.bind((1, dir.location.as_str()))?;
+        statement.bind((2, dir.parent.as_str()))?;
+        statement.bind((3, dir.name.as_str()))?;
+        statement.bind((4, dir.mtime))?;
+        statement.bind((5, dir.repo as i64))?;
+        statement.bind((6, dir.remote.as_deref()))?;
+        statement.next()?;
+        Ok(())
+    }
+
+    fn delete_indexed_dir(&mut self, location: &str) -> anyhow::Result<()> {
+        let mut statement = self.prepare("DELETE FROM repo_index WHERE location = ?")?;
+        statement.bind((1, location))?;
+        statement.next()?;
+        Ok(())
+    }
+
+    fn repos(&self) -> anyhow::Result<Vec<IndexedDir>> {
+        let mut statement = self.prepare(format!(
+            "{} WHERE repo = 1 ORDER BY name, location",
+            INDEXED_DIRS
+        ))?;
+        let mut repos = Vec::new();
+        while let sqlite::State::Row = statement.next()? {
+            repos.push(read_indexed_dir(&statement)?);
+        }
+        Ok(repos)
+    }
+}
+
+const INDEXED_DIRS: &str = "SELECT location, parent, name, mtime, repo, remote FROM repo_index";
+
+fn read_indexed_dir(statement: &sqlite::Statement) -> anyhow::Result<IndexedDir> {
+    Ok(IndexedDir {
+        location: statement.read::<String, _>("location")?,
+        parent: statement.read::<String, _>("parent")?,
+        name: statement.read::<String, _>("name")?,
+        mtime: statement.read::<Option<i64>, _>("mtime")?,
+        repo: statement.read::<i64, _>("repo")? == 1,
+        remote: statement.read::<Option<String>, _>("remote")?,
+    })
+}
+
+#[derive(Debug, Clone)]
+struct MemoryShortcut {
+    entry: ShortcutEntry,
+    identity: Identity,
+    created_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct MemoryState {
+    shortcuts: BTreeMap<String, MemoryShortcut>,
+    uses: Vec<(String, i64)>,
+    // Keyed by location, holding the name and usage count.
+    history: BTreeMap<String, (String, i64)>,
+    visits: Vec<(String, i64)>,
+    journal: Vec<JournalBatch>,
+    repo_index: BTreeMap<String, IndexedDir>,
+}
+
+// Keeps everything in plain collections, with transactions rolled back from a copy taken when
+// they begin.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStore {
+    state: MemoryState,
+    saved: Option<MemoryState>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        MemoryStore::default()
+    }
+}
+
+impl Store for MemoryStore {
+    fn begin(&mut self) -> anyhow::Result<()> {
+        if self.saved.is_some() {
+            anyhow::bail!("cannot start a transaction within a transaction");
+        }
+        self.saved = Some(self.state.clone());
+        Ok(())
+    }
+
+    fn commit(&mut self) -> anyhow::Result<()> {
+        self.saved = None;
+        Ok(())
+    }
+
+    fn rollback(&mut self) -> anyhow::Result<()> {
+        if let Some(saved) = self.saved.take() {
+            self.state = saved;
+        }
+        Ok(())
+    }
+
+    fn shortcut(&self, name: &str) -> anyhow::Result<Option<ShortcutEntry>> {
+        Ok(self
+            .state
+            .shortcuts
+            .get(name)
+            .map(|shortcut| shortcut.entry.clone()))
+    }
+
+    fn shortcuts(&self) -> anyhow::Result<BTreeMap<String, ShortcutEntry>> {
+        Ok(self
+            .state
+            .shortcuts
+            .iter()
+            .map(|(name, shortcut)| (name.to_string(), shortcut.entry.clone()))
+            .collect())
+    }
+
+    fn write_shortcut(&mut self, name: &str, entry: &ShortcutEntry) -> anyhow::Result<()> {
+        let mut entry = entry.clone();
+        entry.tags.sort();
+        entry.tags.dedup();
+        match self.state.shortcuts.get_mut(name) {
+            Some(shortcut) => shortcut.entry = entry,
+            None => {
+                let shortcut = MemoryShortcut {
+                    entry,
+                    identity: Identity::default(),
+                    created_at: Some(chrono::Utc::now().timestamp()),
+                };
+                self.state.shortcuts.insert(name.to_string(), shortcut);
+            }
+        }
+        Ok(())
+    }
+
+    fn delete_shortcut(&mut self, name: &str) -> anyhow::Result<()> {
+        self.state.shortcuts.remove(name);
+        Ok(())
+    }
+
+    fn rename_shortcut(&mut self, old: &str, new: &str) -> anyhow::Result<()> {
+        if let Some(shortcut) = self.state.shortcuts.remove(old) {
+            self.state.shortcuts.insert(new.to_string(), shortcut);
+        }
+        Ok(())
+    }
+
+    fn identity(&self, name: &str) -> anyhow::Result<Identity> {
+        Ok(self
+            .state
+            .shortcuts
+            .get(name)
+            .map(|shortcut| shortcut.identity.clone())
+            .unwrap_or_default())
+    }
+
+    fn set_identity(&mut self, name: &str, identity: &Identity) -> anyhow::Result<()> {
+        if let Some(shortcut) = self.state.shortcuts.get_mut(name) {
+            shortcut.identity = identity.clone();
+        }
+        Ok(())
+    }
+
+    fn created_at(&self, name: &str) -> anyhow::Result<Option<i64>> {
+        Ok(self
+            .state
+            .shortcuts
+            .get(name)
+            .and_then(|shortcut| shortcut.created_at))
+    }
+
+    fn restore_shortcut(&mut self, name: &str, record: &ShortcutRecord) -> anyhow::Result<()> {
+        let mut entry = record.entry.clone();
+        entry.tags.sort();
+        entry.tags.dedup();
+        let shortcut = MemoryShortcut {
+            entry,
+            identity: record.identity.clone(),
+            created_at: record.created_at,
+        };
+        self.state.shortcuts.insert(name.to_string(), shortcut);
+        Ok(())
+    }
+
+    fn record_shortcut_use(&mut self, name: &str, now: i64) -> anyhow::Result<()> {
+        self.state.uses.push((name.to_string(), now));
+        Ok(())
+    }
+
+    fn shortcut_usage(&self) -> anyhow::Result<HashMap<String, (i64, i64)>> {
+        let mut usage: HashMap<String, (i64, i64)> = HashMap::new();
+        for (name, used_at) in self.state.uses.iter() {
+            let (uses, last_used) = usage.entry(name.to_string()).or_insert((0, *used_at));
+            *uses += 1;
+            *last_used = (*last_used).max(*used_at);
+        }
+        Ok(usage)
+    }
+
+    fn visit_counts(&self, from: i64, to: i64) -> anyhow::Result<HashMap<String, i64>> {
+        let mut counts = HashMap::new();
+        for (location, visited_at) in self.state.visits.iter() {
+            if (from..to).contains(visited_at) {
+                *counts.entry(location.to_string()).or_insert(0) += 1;
+            }
+        }
+        Ok(counts)
+    }
+
+    fn record_visit(&mut self, name: &str, location: &str, now: i64) -> anyhow::Result<()> {
+        self.state
+            .history
+            .entry(location.to_string())
+            .or_insert((name.to_string(), 0))
+            .1 += 1;
+        self.state.visits.push((location.to_string(), now));
+        self.state
+            .visits
+            .retain(|(_, visited_at)| *visited_at >= now - VISIT_RETENTION);
+        Ok(())
+    }
+
+    fn last_visit(&self) -> anyhow::Result<Option<(String, i64)>> {
+        Ok(self.state.visits.last().cloned())
+    }
+
+    fn history(&self, now: i64) -> anyhow::Result<Vec<HistoryEntry>> {
+        let mut history: Vec<HistoryEntry> = self
+            .state
+            .history
+            .iter()
+            .map(|(location, (name, usage))| {
+                let visits: Vec<i64> = self
+                    .state
+                    .visits
+                    .iter()
+                    .filter(|(visited, _)| visited == location)
+                    .map(|(_, visited_at)| *visited_at)
+                    .collect();
+                HistoryEntry {
+                    name: name.to_string(),
+                    location: location.to_string(),
+                    usage: *usage,
+                    last_visit: visits.iter().max().copied(),
+                    score: frecency(*usage, &visits, now),
+                }
+            })
+            .collect();
+        history.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then(b.usage.cmp(&a.usage))
+                .then(a.name.cmp(&b.name))
+        });
+        Ok(history)
+    }
+
+    fn delete_history(&mut self, location: &str) -> anyhow::Result<()> {
+        self.state.history.remove(location);
+        self.state.visits.retain(|(visited, _)| visited != location);
+        Ok(())
+    }
+
+    fn move_history(&mut self, location: &str, name: &str, moved: &str) -> anyhow::Result<()> {
+        if let Some((_, usage)) = self.state.history.remove(location) {
+            self.state
+                .history
+                .insert(moved.to_string(), (name.to_string(), usage));
+        }
+        for (visited, _) in self.state.visits.iter_mut() {
+            if visited == location {
+                *visited = moved.to_string();
+            }
+        }
+        Ok(())
+    }
+
+    fn push_journal(
+        &mut self,
+        operation: &str,
+        changes: &[JournalChange],
+        keep: i64,
+    ) -> anyhow::Result<()> {
+        let batch = self.state.journal.last().map(|b| b.batch).unwrap_or(0) + 1;
+        if !changes.is_empty() {
+            self.state.journal.push(JournalBatch {
+                batch,
+                operation: operation.to_string(),
+                changes: changes.iter().rev().cloned().collect(),
+            });
+        }
+        self.state.journal.retain(|b| b.batch > batch - keep);
+        Ok(())
+    }
+
+    fn last_journal(&self) -> anyhow::Result<Option<JournalBatch>> {
+        Ok(self.state.journal.last().cloned())
+    }
+
+    fn drop_journal(&mut self, batch: i64) -> anyhow::Result<()> {
+        self.state.journal.retain(|b| b.batch != batch);
+        Ok(())
+    }
+
+    fn indexed_dir(&self, location: &str) -> anyhow::Result<Option<IndexedDir>> {
+        Ok(self.state.repo_index.get(location).cloned())
+    }
+
+    fn indexed_children(&self, parent: &str) -> anyhow::Result<Vec<String>> {
+        Ok(self
+            .state
+            .repo_index
+            .values()
+            .filter(|dir| dir.parent == parent)
+            .map(|dir| dir.location.to_string())
+            .collect())
+    }
+
+    fn indexed_locations(&self) -> anyhow::Result<Vec<String>> {
+        Ok(self.state.repo_index.keys().cloned().collect())
+    }
+
+    fn write_indexed_dir(&mut self, dir: &IndexedDir) -> anyhow::Result<()> {
+        self.state
+            .repo_index
+            .insert(dir.location.to_string(), dir.clone());
+        Ok(())
+    }
+
+    fn delete_indexed_dir(&mut self, location: &str) -> anyhow::Result<()> {
+        self.state.repo_index.remove(location);
+        Ok(())
+    }
+
+    fn repos(&self) -> anyhow::Result<Vec<IndexedDir>> {
+        let mut repos: Vec<IndexedDir> = self
+            .state
+            .repo_index
+            .values()
+            .filter(|dir| dir.repo)
+            .cloned()
+            .collect();
+        repos.sort_by(|a, b| a.name.cmp(&b.name).then(a.location.cmp(&b.location)));
+        Ok(repos)
+    }
+}
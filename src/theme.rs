@@ -0,0 +1,458 @@
+// Colors and layout for the tables printed by `hp list` and friends.  Styles are written as a
+// string of space separated words, e.g. `"bold #33ffff"` or `"dim italic"`, and rendered with as
+// many colors as the terminal supports, or none at all when output isn't going to a terminal,
+// `NO_COLOR` is set or `--color=never` is given.
+use serde_derive::Deserialize;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorMode {
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl FromStr for ColorMode {
+    type Err = anyhow::Error;
+
+    fn from_str(mode: &str) -> anyhow::Result<Self> {
+        match mode {
+            "auto" => Ok(ColorMode::Auto),
+            "always" => Ok(ColorMode::Always),
+            "never" => Ok(ColorMode::Never),
+            _ => anyhow::bail!("Unknown color mode `{}`, use auto, always or never.", mode),
+        }
+    }
+}
+
+impl fmt::Display for ColorMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ColorMode::Auto => write!(f, "auto"),
+            ColorMode::Always => write!(f, "always"),
+            ColorMode::Never => write!(f, "never"),
+        }
+    }
+}
+
+// How many colors the terminal can show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorDepth {
+    Ansi16,
+    Ansi256,
+    TrueColor,
+}
+
+const NAMES: &[&str] = &[
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+// The usual xterm values for the 16 named colors, used to find the closest one to a true color.
+const PALETTE: [[u8; 3]; 16] = [
+    [0, 0, 0],
+    [205, 0, 0],
+    [0, 205, 0],
+    [205, 205, 0],
+    [0, 0, 238],
+    [205, 0, 205],
+    [0, 205, 205],
+    [229, 229, 229],
+    [127, 127, 127],
+    [255, 0, 0],
+    [0, 255, 0],
+    [255, 255, 0],
+    [92, 92, 255],
+    [255, 0, 255],
+    [0, 255, 255],
+    [255, 255, 255],
+];
+
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    // One of the 16 named colors, with 8 and up being the bright ones.
+    Named(u8),
+    Ansi256(u8),
+    Rgb([u8; 3]),
+}
+
+fn distance(a: [u8; 3], b: [u8; 3]) -> u32 {
+    a.iter()
+        .zip(b.iter())
+        .map(|(x, y)| (*x as i32 - *y as i32).pow(2) as u32)
+        .sum()
+}
+
+fn nearest_level(value: u8) -> usize {
+    (0..CUBE_LEVELS.len())
+        .min_by_key(|&i| (CUBE_LEVELS[i] as i32 - value as i32).abs())
+        .unwrap_or(0)
+}
+
+impl Color {
+    fn rgb(self) -> [u8; 3] {
+        match self {
+            Color::Rgb(rgb) => rgb,
+            Color::Named(n) => PALETTE[n as usize % 16],
+            Color::Ansi256(n) if n < 16 => PALETTE[n as usize],
+            Color::Ansi256(n) if n >= 232 => [8 + 10 * (n - 232); 3],
+            Color::Ansi256(n) => {
+                let n = n - 16;
+                [
+                    CUBE_LEVELS[(n / 36) as usize],
+                    CUBE_LEVELS[(n / 6 % 6) as usize],
+                    CUBE_LEVELS[(n % 6) as usize],
+                ]
+            }
+        }
+    }
+
+    // The closest color of the 6x6x6 cube or the grey ramp.
+    fn to_256(self) -> u8 {
+        match self {
+            Color::Named(n) => n,
+            Color::Ansi256(n) => n,
+            Color::Rgb(rgb) => {
+                let [r, g, b] = rgb.map(nearest_level);
+                let cube = (16 + 36 * r + 6 * g + b) as u8;
+                let average = (rgb.iter().map(|c| *c as u32).sum::<u32>() / 3) as u8;
+                let grey = 232 + (average.saturating_sub(3) / 10).min(23);
+                if distance(Color::Ansi256(grey).rgb(), rgb)
+                    < distance(Color::Ansi256(cube).rgb(), rgb)
+                {
+                    grey
+                } else {
+                    cube
+                }
+            }
+        }
+    }
+
+    fn to_16(self) -> u8 {
+        match self {
+            Color::Named(n) => n,
+            color => {
+                let rgb = color.rgb();
+                (0..16u8)
+                    .min_by_key(|&i| distance(PALETTE[i as usize], rgb))
+                    .unwrap_or(7)
+            }
+        }
+    }
+
+    fn code(self, depth: ColorDepth) -> String {
+        match (self, depth) {
+            (Color::Rgb([r, g, b]), ColorDepth::TrueColor) => format!("38;2;{};{};{}", r, g, b),
+            (Color::Named(_), _) | (_, ColorDepth::Ansi16) => match self.to_16() {
+                n if n < 8 => format!("{}", 30 + n),
+                n => format!("{}", 90 + n - 8),
+            },
+            (color, _) => format!("38;5;{}", color.to_256()),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(try_from = "String")]
+pub struct Style {
+    pub color: Option<Color>,
+    pub bold: bool,
+    pub dim: bool,
+    pub italic: bool,
+    pub underline: bool,
+}
+
+// `cyan` or `bright_red`, as one of the 16 named colors.
+fn named_color(name: &str) -> Option<Color> {
+    let (bright, base) = match name.strip_prefix("bright_") {
+        Some(base) => (8, base),
+        None => (0, name),
+    };
+    let i = NAMES.iter().position(|n| *n == base)?;
+    Some(Color::Named(i as u8 + bright))
+}
+
+impl FromStr for Style {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, String> {
+        let mut style = Style::default();
+        for word in spec.split_whitespace() {
+            let word = word.to_lowercase();
+            match word.as_str() {
+                "bold" => style.bold = true,
+                "dim" => style.dim = true,
+                "italic" => style.italic = true,
+                "underline" => style.underline = true,
+                "plain" | "none" => {}
+                hex if hex.starts_with('#') && hex.len() == 7 => {
+                    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16);
+                    match (channel(1), channel(3), channel(5)) {
+                        (Ok(r), Ok(g), Ok(b)) => style.color = Some(Color::Rgb([r, g, b])),
+                        _ => return Err(format!("`{}` isn't a valid hex color", hex)),
+                    }
+                }
+                name => {
+                    style.color = match (named_color(name), name.parse()) {
+                        (Some(color), _) => Some(color),
+                        (None, Ok(n)) => Some(Color::Ansi256(n)),
+                        (None, Err(_)) => {
+                            return Err(format!(
+                                "unknown style `{}`, use bold, dim, italic, underline, a color \
+                                 name like cyan or bright_red, #rrggbb or a number up to 255",
+                                name
+                            ))
+                        }
+                    };
+                }
+            }
+        }
+        Ok(style)
+    }
+}
+
+impl TryFrom<String> for Style {
+    type Error = String;
+
+    fn try_from(spec: String) -> Result<Self, String> {
+        spec.parse()
+    }
+}
+
+impl fmt::Display for Style {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut words = Vec::new();
+        for (set, word) in [
+            (self.bold, "bold"),
+            (self.dim, "dim"),
+            (self.italic, "italic"),
+            (self.underline, "underline"),
+        ] {
+            if set {
+                words.push(word.to_string());
+            }
+        }
+        match self.color {
+            Some(Color::Named(n)) if n >= 8 => {
+                words.push(format!("bright_{}", NAMES[n as usize - 8]))
+            }
+            Some(Color::Named(n)) => words.push(NAMES[n as usize].to_string()),
+            Some(Color::Ansi256(n)) => words.push(n.to_string()),
+            Some(Color::Rgb([r, g, b])) => words.push(format!("#{:02x}{:02x}{:02x}", r, g, b)),
+            None => {}
+        }
+        if words.is_empty() {
+            write!(f, "plain")
+        } else {
+            write!(f, "{}", words.join(" "))
+        }
+    }
+}
+
+impl Style {
+    pub fn rgb(rgb: [u8; 3]) -> Self {
+        Style {
+            color: Some(Color::Rgb(rgb)),
+            ..Default::default()
+        }
+    }
+
+    // One of the 16 named colors, falling back to no color for an unknown name.
+    pub fn named(name: &str) -> Self {
+        Style {
+            color: named_color(name),
+            ..Default::default()
+        }
+    }
+
+    pub fn bold(self) -> Self {
+        Style { bold: true, ..self }
+    }
+
+    pub fn dim(self) -> Self {
+        Style { dim: true, ..self }
+    }
+
+    // `text` with the escape codes for this style, or as is without a color depth.
+    pub fn paint(&self, text: &str, depth: Option<ColorDepth>) -> String {
+        let depth = match depth {
+            Some(depth) if !text.is_empty() => depth,
+            _ => return text.to_string(),
+        };
+        let mut codes = Vec::new();
+        for (set, code) in [
+            (self.bold, "1"),
+            (self.dim, "2"),
+            (self.italic, "3"),
+            (self.underline, "4"),
+        ] {
+            if set {
+                codes.push(code.to_string());
+            }
+        }
+        codes.extend(self.color.map(|color| color.code(depth)));
+        if codes.is_empty() {
+            return text.to_string();
+        }
+        format!("\x1b[{}m{}\x1b[0m", codes.join(";"), text)
+    }
+}
+
+#[derive(Deserialize, PartialEq, Eq, Debug, Default, Clone)]
+pub struct ReadTheme {
+    pub name: Option<Style>,
+    pub arrow: Option<Style>,
+    pub location: Option<Style>,
+    pub notes: Option<Style>,
+    pub source: Option<Style>,
+    pub matched: Option<Style>,
+}
+
+// Every key of the `[theme]` table.
+pub const THEME_KEYS: &[&str] = &["name", "arrow", "location", "notes", "source", "matched"];
+
+// Styles for each part of a listing: the name, the `->` between it and the location, the
+// location, the notes after it (tags, descriptions, visit counts), the headings naming where
+// entries came from and the characters of a name matched by a fuzzy search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    pub name: Style,
+    pub arrow: Style,
+    pub location: Style,
+    pub notes: Style,
+    pub source: Style,
+    pub matched: Style,
+}
+
+impl Theme {
+    // Anything left out of `[theme]` falls back to the print colors from `[settings]`.
+    pub fn from_read(theme: ReadTheme, primary: [u8; 3], secondary: [u8; 3]) -> Self {
+        let white = Style {
+            color: Some(Color::Named(15)),
+            bold: true,
+            ..Default::default()
+        };
+        let name = theme.name.unwrap_or(Style::rgb(primary).bold());
+        Theme {
+            name,
+            arrow: theme.arrow.unwrap_or(white),
+            location: theme.location.unwrap_or(Style::rgb(secondary).bold()),
+            notes: theme.notes.unwrap_or(Style {
+                dim: true,
+                ..Default::default()
+            }),
+            source: theme.source.unwrap_or(white),
+            matched: theme.matched.unwrap_or(Style {
+                underline: true,
+                ..name
+            }),
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<Style> {
+        match key {
+            "name" => Some(self.name),
+            "arrow" => Some(self.arrow),
+            "location" => Some(self.location),
+            "notes" => Some(self.notes),
+            "source" => Some(self.source),
+            "matched" => Some(self.matched),
+            _ => None,
+        }
+    }
+}
+
+// Paints the characters at the `matched` positions of `text` with `highlight` and the rest with
+// `style`, a run at a time.
+pub fn paint_matched(
+    text: &str,
+    matched: &[usize],
+    style: Style,
+    highlight: Style,
+    depth: Option<ColorDepth>,
+) -> String {
+    let mut painted = String::new();
+    let mut run = String::new();
+    let mut in_match = false;
+    for (i, c) in text.chars().enumerate() {
+        if matched.contains(&i) != in_match && !run.is_empty() {
+            let style = if in_match { highlight } else { style };
+            painted.push_str(&style.paint(&run, depth));
+            run.clear();
+        }
+        in_match = matched.contains(&i);
+        run.push(c);
+    }
+    let style = if in_match { highlight } else { style };
+    painted.push_str(&style.paint(&run, depth));
+    painted
+}
+
+// Shortens `location` to at most `width` characters, first by writing the home directory as `~`
+// and then by cutting out the middle, since both ends of a path tend to say the most about it.
+pub fn fit(location: &str, width: usize, home: Option<&str>) -> String {
+    let mut location = location.to_string();
+    if location.chars().count() <= width {
+        return location;
+    }
+    if let Some(home) = home.filter(|h| !h.is_empty()) {
+        if let Some(rest) = location.strip_prefix(home) {
+            if rest.is_empty() || rest.starts_with('/') || rest.starts_with('\\') {
+                location = format!("~{}", rest);
+            }
+        }
+    }
+    let chars: Vec<char> = location.chars().collect();
+    if chars.len() <= width || width < 5 {
+        return location;
+    }
+    let tail = (width - 1) * 2 / 3;
+    let head = width - 1 - tail;
+    format!(
+        "{}…{}",
+        chars[..head].iter().collect::<String>(),
+        chars[chars.len() - tail..].iter().collect::<String>()
+    )
+}
+
+fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+    let mut cut: String = text.chars().take(width.saturating_sub(1)).collect();
+    cut.push('…');
+    cut
+}
+
+// Locations are kept at least this wide before anything else gives way.
+const MIN_LOCATION: usize = 24;
+
+// Fits the location and notes of a row into `width` characters: the notes are shortened first,
+// and then the location as well when even it alone is too long.
+pub fn fit_row(location: &str, notes: &str, width: usize, home: Option<&str>) -> (String, String) {
+    let notes_width = match notes.chars().count() {
+        0 => 0,
+        n => n + 1,
+    };
+    if location.chars().count() + notes_width <= width {
+        return (location.to_string(), notes.to_string());
+    }
+    let location = fit(
+        location,
+        width
+            .saturating_sub(notes_width)
+            .max(width.min(MIN_LOCATION)),
+        home,
+    );
+    let room = width.saturating_sub(location.chars().count() + 1);
+    let notes = if room < 2 {
+        "".to_string()
+    } else {
+        truncate(notes, room)
+    };
+    (location, notes)
+}
@@ -0,0 +1,235 @@
+// The different ways `hp list` can lay out its entries: the default table, a directory tree
+// grouping entries by their common path prefixes, or one table per tag.
+use crate::api::{Entry, Kind};
+use crate::theme::{ColorDepth, Theme};
+use crate::Hopper;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ListView {
+    #[default]
+    Table,
+    Tree,
+    ByTag,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SortBy {
+    #[default]
+    Name,
+    Location,
+    Usage,
+    Recent,
+}
+
+impl FromStr for SortBy {
+    type Err = anyhow::Error;
+
+    fn from_str(sort: &str) -> anyhow::Result<Self> {
+        match sort {
+            "name" => Ok(SortBy::Name),
+            "location" => Ok(SortBy::Location),
+            "usage" => Ok(SortBy::Usage),
+            "recent" => Ok(SortBy::Recent),
+            _ => anyhow::bail!(
+                "Unknown sort `{}`, use name, location, usage or recent.",
+                sort
+            ),
+        }
+    }
+}
+
+// Most used and most recently used entries come first, with ties kept in name order.
+pub fn sort_entries(entries: &mut [Entry], sort: SortBy) {
+    match sort {
+        SortBy::Name => entries.sort_by(|a, b| a.name.cmp(&b.name)),
+        SortBy::Location => entries.sort_by(|a, b| a.location.cmp(&b.location)),
+        SortBy::Usage => entries.sort_by(|a, b| b.usage.cmp(&a.usage).then(a.name.cmp(&b.name))),
+        SortBy::Recent => {
+            entries.sort_by(|a, b| b.last_used.cmp(&a.last_used).then(a.name.cmp(&b.name)))
+        }
+    }
+}
+
+// The location as listed, with a trailing `/` for directories the way `ls -F` marks them.
+pub fn display_location(entry: &Entry) -> String {
+    match Kind::of(&entry.location) {
+        Kind::Dir if !entry.location.ends_with('/') => format!("{}/", entry.location),
+        _ => entry.location.to_string(),
+    }
+}
+
+pub fn notes(entry: &Entry) -> String {
+    let missing = (Kind::of(&entry.location) == Kind::Missing).then(|| "(missing)".to_string());
+    let tags = (!entry.tags.is_empty()).then(|| format!("[{}]", entry.tags.join(", ")));
+    [missing, tags, entry.description.clone()]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+#[derive(Debug, Default)]
+struct Node {
+    names: Vec<String>,
+    file: bool,
+    missing: bool,
+    children: BTreeMap<String, Node>,
+}
+
+impl Node {
+    fn insert(&mut self, segments: &[&str], entry: &Entry) {
+        match segments.split_first() {
+            Some((segment, rest)) => self
+                .children
+                .entry(segment.to_string())
+                .or_default()
+                .insert(rest, entry),
+            None => {
+                self.names.push(entry.name.to_string());
+                match Kind::of(&entry.location) {
+                    Kind::File => self.file = true,
+                    Kind::Missing => self.missing = true,
+                    Kind::Dir => {}
+                }
+            }
+        }
+    }
+
+    // Folds directories with nothing of their own and a single child into that child, so only
+    // the prefixes entries actually share become levels of the tree.
+    fn compress(self, label: String) -> (String, Node) {
+        let mut node = self;
+        let mut label = label;
+        while node.names.is_empty() && node.children.len() == 1 {
+            let (child_label, child) = node.children.into_iter().next().unwrap_or_default();
+            label = format!("{}/{}", label, child_label);
+            node = child;
+        }
+        (label, node)
+    }
+}
+
+// One line per directory in the tree, e.g. `├── src/ <- hop-src`, with the names of the
+// entries pointing at it after the arrow.
+pub fn tree_lines(entries: &[Entry], theme: &Theme, depth: Option<ColorDepth>) -> Vec<String> {
+    let mut root = Node::default();
+    for entry in entries {
+        let location = entry.location.replace('\\', "/");
+        let segments: Vec<&str> = location.trim_end_matches('/').split('/').collect();
+        root.insert(&segments, entry);
+    }
+    let mut lines = Vec::new();
+    for (label, node) in root.children {
+        // Absolute paths start with an empty segment for the root.
+        let (label, node) = node.compress(label);
+        let label = if label.is_empty() {
+            "/".to_string()
+        } else {
+            label
+        };
+        render(&label, node, "", "", theme, depth, &mut lines);
+    }
+    lines
+}
+
+fn render(
+    label: &str,
+    node: Node,
+    branch: &str,
+    indent: &str,
+    theme: &Theme,
+    depth: Option<ColorDepth>,
+    lines: &mut Vec<String>,
+) {
+    // Files are the only thing without the trailing `/`, the way `display_location` has it.
+    let label = match node.file {
+        true => label.to_string(),
+        false => format!("{}/", label.trim_end_matches('/')),
+    };
+    let mut line = format!("{}{}", branch, theme.location.paint(&label, depth));
+    if !node.names.is_empty() {
+        line.push_str(&format!(
+            " {} {}",
+            theme.arrow.paint("<-", depth),
+            theme.name.paint(&node.names.join(", "), depth)
+        ));
+    }
+    if node.missing {
+        line.push_str(&format!(" {}", theme.notes.paint("(missing)", depth)));
+    }
+    lines.push(line);
+    let count = node.children.len();
+    for (i, (child_label, child)) in node.children.into_iter().enumerate() {
+        let last = i + 1 == count;
+        let (child_label, child) = child.compress(child_label);
+        let branch = format!("{}{}", indent, if last { "└── " } else { "├── " });
+        let indent = format!("{}{}", indent, if last { "    " } else { "│   " });
+        render(&child_label, child, &branch, &indent, theme, depth, lines);
+    }
+}
+
+// Shortcuts under each of their tags, with the untagged ones last.
+pub fn by_tag(entries: Vec<Entry>) -> Vec<(Option<String>, Vec<Entry>)> {
+    let mut tagged: BTreeMap<String, Vec<Entry>> = BTreeMap::new();
+    let mut untagged = Vec::new();
+    for entry in entries {
+        if entry.tags.is_empty() {
+            untagged.push(entry);
+            continue;
+        }
+        for tag in &entry.tags {
+            tagged
+                .entry(tag.to_string())
+                .or_default()
+                .push(entry.clone());
+        }
+    }
+    let mut groups: Vec<(Option<String>, Vec<Entry>)> = tagged
+        .into_iter()
+        .map(|(tag, entries)| (Some(tag), entries))
+        .collect();
+    if !untagged.is_empty() {
+        groups.push((None, untagged));
+    }
+    groups
+}
+
+impl Hopper {
+    pub(crate) fn print_tree(&self, heading: &str, entries: &[Entry]) {
+        let depth = self.color_depth();
+        println!("{}", self.config.theme.source.paint(heading, depth));
+        let lines = tree_lines(entries, &self.config.theme, depth);
+        if !lines.is_empty() {
+            println!("{}", lines.join("\n"));
+        }
+    }
+
+    pub(crate) fn print_by_tag(&self, entries: Vec<Entry>) {
+        let depth = self.color_depth();
+        for (tag, entries) in by_tag(entries) {
+            let heading = match tag {
+                Some(tag) => format!("@{}:", tag),
+                None => "Untagged:".to_string(),
+            };
+            println!("{}", self.config.theme.source.paint(&heading, depth));
+            self.format_matches(rows(entries));
+        }
+    }
+}
+
+// Table rows for the entries, along with the characters of their names to highlight.
+pub fn rows(entries: Vec<Entry>) -> Vec<([String; 3], Vec<usize>)> {
+    entries
+        .into_iter()
+        .map(|entry| {
+            let row = [
+                entry.name.to_string(),
+                display_location(&entry),
+                notes(&entry),
+            ];
+            (row, entry.matched)
+        })
+        .collect()
+}
@@ -0,0 +1,123 @@
+use bhop::api::{Action, Kind, Source};
+use bhop::args::Request;
+use bhop::configs::Configs;
+use bhop::store::MemoryStore;
+use bhop::{Hopper, ListOptions};
+use std::fs;
+use tempfile::tempdir;
+
+fn memory_hopper(config: Configs) -> Hopper {
+    Hopper::builder()
+        .config(config)
+        .store(MemoryStore::new())
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn resolve_and_act_on_shortcuts() {
+    let project = tempdir().unwrap();
+    let notes = project.path().join("notes.md");
+    fs::write(&notes, "").unwrap();
+    let mut hopper = memory_hopper(Configs {
+        always_jump: false,
+        ..Default::default()
+    });
+    let location = project.path().display().to_string();
+    for (target, name) in [
+        (location.clone(), "proj"),
+        (notes.display().to_string(), "notes"),
+    ] {
+        hopper
+            .execute(Request::Add(target, Some(name.to_string())))
+            .unwrap();
+    }
+
+    let resolution = hopper.resolve("proj").unwrap();
+    assert_eq!(resolution.path, project.path());
+    assert_eq!(resolution.kind, Kind::Dir);
+    assert_eq!(resolution.source, Source::Shortcut);
+    assert_eq!(resolution.score, None);
+    assert_eq!(hopper.resolve("notes").unwrap().kind, Kind::File);
+    assert!(hopper.resolve("nothing-by-this-name").is_none());
+
+    assert_eq!(
+        hopper.action("proj", false).unwrap(),
+        Action::Cd(location.clone().into())
+    );
+    match hopper.action("notes:3", false).unwrap() {
+        Action::Edit { dir, cmd } => {
+            assert_eq!(dir, None);
+            assert!(cmd.contains("notes.md"));
+        }
+        action => panic!("expected an edit, got {:?}", action),
+    }
+    assert!(hopper.action("nothing-by-this-name", false).is_err());
+}
+
+#[test]
+fn group_actions_and_search() {
+    let project = tempdir().unwrap();
+    fs::write(project.path().join(".bhop"), "test = \"cargo test\"\n").unwrap();
+    let mut hopper = memory_hopper(Configs::default());
+    let location = project.path().display().to_string();
+    hopper
+        .execute(Request::Add(location.clone(), Some("proj".to_string())))
+        .unwrap();
+    hopper
+        .execute(Request::Tag("proj".to_string(), vec!["work".to_string()]))
+        .unwrap();
+
+    assert_eq!(
+        hopper.group_action("proj", Some("test")).unwrap(),
+        Action::Run {
+            dir: location.clone().into(),
+            cmd: "cargo test".to_string()
+        }
+    );
+    assert!(hopper.group_action("proj", Some("missing")).is_err());
+
+    let options = ListOptions {
+        tag: Some("work".to_string()),
+        ..Default::default()
+    };
+    let entries = hopper.search(None, &options).unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].name, "proj");
+    assert_eq!(entries[0].location, location);
+    assert_eq!(entries[0].source, Source::Shortcut);
+    assert_eq!(entries[0].tags, vec!["work".to_string()]);
+}
+
+#[test]
+fn resolve_has_no_side_effects() {
+    let project = tempdir().unwrap();
+    let mut hopper = memory_hopper(Configs::default());
+    let location = project.path().display().to_string();
+    let gone = project.path().join("gone").display().to_string();
+    for (target, name) in [(location, "proj"), (gone.clone(), "gone")] {
+        hopper
+            .execute(Request::Add(target, Some(name.to_string())))
+            .unwrap();
+    }
+    let usage = |hopper: &Hopper, name: &str| {
+        hopper
+            .search(Some(name), &ListOptions::default())
+            .unwrap()
+            .into_iter()
+            .find(|entry| entry.name == name)
+            .map(|entry| entry.usage)
+            .unwrap()
+    };
+
+    let resolution = hopper.resolve("gone").unwrap();
+    assert_eq!(resolution.kind, Kind::Missing);
+    assert_eq!(resolution.path, std::path::PathBuf::from(&gone));
+    assert_eq!(resolution.shortcut, Some("gone".to_string()));
+    hopper.resolve("proj").unwrap();
+    hopper.execute(Request::Find("proj".to_string())).unwrap();
+    assert_eq!(usage(&hopper, "proj"), 0);
+
+    hopper.action("proj", false).unwrap();
+    assert_eq!(usage(&hopper, "proj"), 1);
+}
@@ -1,4 +1,8 @@
 use bhop::args::Request;
+use bhop::configs::Configs;
+use bhop::search::SearchMode;
+use bhop::store::MemoryStore;
+use bhop::{Hopper, ListOptions};
 use serial_test::serial;
 use std::env;
 
@@ -33,3 +37,145 @@ fn test_request_parse_help() {
     let request = Request::parse();
     assert_eq!(request, Request::Passthrough("__bhop_help__".to_string()));
 }
+
+#[test]
+#[serial]
+fn test_request_parse_dry_run() {
+    setup_args(&["hp", "group", "shortcut", "dev", "--dry-run"]);
+    let request = Request::parse();
+    assert_eq!(
+        request,
+        Request::DryRun(Box::new(Request::Group(
+            "shortcut".to_string(),
+            Some("dev".to_string())
+        )))
+    );
+}
+
+#[test]
+#[serial]
+fn test_request_parse_tag() {
+    setup_args(&["hp", "tag", "shortcut", "work", "infra"]);
+    let request = Request::parse();
+    assert_eq!(
+        request,
+        Request::Tag(
+            "shortcut".to_string(),
+            vec!["work".to_string(), "infra".to_string()]
+        )
+    );
+}
+
+#[test]
+#[serial]
+fn test_request_parse_list_by_tag() {
+    setup_args(&["hp", "__bhop_list__", "--tag", "infra", "*hop*"]);
+    let request = Request::parse();
+    assert_eq!(
+        request,
+        Request::Search(
+            Some("*hop*".to_string()),
+            ListOptions {
+                tag: Some("infra".to_string()),
+                ..Default::default()
+            }
+        )
+    );
+    setup_args(&["hp", "@infra/"]);
+    let request = Request::parse();
+    assert_eq!(
+        request,
+        Request::Passthrough("__bhop_list__ --tag infra".to_string())
+    );
+}
+
+#[test]
+#[serial]
+fn test_request_parse_rename() {
+    setup_args(&["hp", "rename", "old", "new"]);
+    let request = Request::parse();
+    assert_eq!(
+        request,
+        Request::Rename("old".to_string(), "new".to_string())
+    );
+}
+
+#[test]
+#[serial]
+fn test_request_parse_history_and_stats() {
+    setup_args(&["hp", "history", "prune"]);
+    assert_eq!(Request::parse(), Request::PruneHistory);
+    setup_args(&["hp", "__bhop_history__", "src"]);
+    assert_eq!(Request::parse(), Request::History(Some("src".to_string())));
+    setup_args(&["hp", "__bhop_stats__", "--days", "14"]);
+    assert_eq!(Request::parse(), Request::Stats(14));
+    setup_args(&["hp", "__bhop_stats__"]);
+    assert_eq!(Request::parse(), Request::Stats(30));
+}
+
+#[test]
+#[serial]
+fn test_request_parse_completions() {
+    setup_args(&["hp", "completions", "zsh"]);
+    assert_eq!(
+        Request::parse(),
+        Request::Passthrough("__bhop_completions__ zsh".to_string())
+    );
+    setup_args(&["hp", "__bhop_completions__", "zsh"]);
+    assert_eq!(Request::parse(), Request::Completions("zsh".to_string()));
+    setup_args(&["hp", "__complete", "rm", "wo"]);
+    assert_eq!(
+        Request::parse(),
+        Request::Complete(vec!["rm".to_string(), "wo".to_string()])
+    );
+}
+
+// What the runner does with a passthrough: split off the directory at the first separator and
+// run the rest through `sh -c`, which here just prints the arguments bhop would be called with.
+#[cfg(unix)]
+fn runner_args(line: &str) -> Vec<String> {
+    let (dir, cmd) = line.split_once('|').unwrap();
+    assert_eq!(dir, ".");
+    let (_, args) = cmd.split_once(' ').unwrap();
+    let output = std::process::Command::new("sh")
+        .arg("-c")
+        .arg(format!("printf '%s\\n' {}", args))
+        .output()
+        .unwrap();
+    String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|l| l.to_string())
+        .collect()
+}
+
+#[test]
+#[cfg(unix)]
+fn test_passthrough_arguments_survive_the_runner() {
+    let mut hopper = Hopper::builder()
+        .config(Configs::default())
+        .store(MemoryStore::new())
+        .build()
+        .unwrap();
+    let line = hopper
+        .respond(Request::parse_from([
+            "hp",
+            "ls",
+            "^a|b$(touch x)*",
+            "--regex",
+        ]))
+        .unwrap();
+    let args = runner_args(&line);
+    assert_eq!(args, vec!["__bhop_list__", "^a|b$(touch x)*", "--regex"]);
+    let args = ["hp"].into_iter().chain(args.iter().map(|a| a.as_str()));
+    assert!(matches!(
+        Request::parse_from(args),
+        Request::Search(Some(pattern), ListOptions { mode: SearchMode::Regex, .. })
+            if pattern == "^a|b$(touch x)*"
+    ));
+
+    let line = hopper
+        .respond(Request::parse_from(["hp", "@it's/"]))
+        .unwrap();
+    assert_eq!(runner_args(&line), vec!["__bhop_list__", "--tag", "it's"]);
+}
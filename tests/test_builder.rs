@@ -0,0 +1,134 @@
+use bhop::args::Request;
+use bhop::configs::Configs;
+use bhop::history::HistoryRules;
+use bhop::store::MemoryStore;
+use bhop::{sanitize, Hopper};
+use serial_test::serial;
+use std::env;
+use std::fs;
+use tempfile::tempdir;
+
+fn run(hopper: &mut Hopper, args: &[&str]) -> String {
+    let args = ["hp"].iter().chain(args.iter()).copied();
+    hopper.respond(Request::parse_from(args)).unwrap()
+}
+
+#[test]
+fn parse_from_arguments() {
+    assert_eq!(
+        Request::parse_from(["hp", "add", "proj"]),
+        Request::Add("proj".to_string(), None)
+    );
+    assert_eq!(
+        Request::parse_from(vec!["hp".to_string(), "rm".to_string(), "t*".to_string()]),
+        Request::Remove("t*".to_string())
+    );
+    assert_eq!(
+        Request::parse_from(["hp", "rm", "t*", "--dry-run"]),
+        Request::DryRun(Box::new(Request::Remove("t*".to_string())))
+    );
+}
+
+#[test]
+fn hermetic_command_flow() {
+    let project = tempdir().unwrap();
+    fs::create_dir(project.path().join("src")).unwrap();
+    fs::write(project.path().join(".bhop"), "test = \"cargo test\"\n").unwrap();
+    let root = sanitize(project.path()).unwrap();
+    let config = Configs {
+        history_rules: HistoryRules {
+            exclude: Vec::new(),
+            min_depth: 0,
+        },
+        ..Default::default()
+    };
+    let mut hopper = Hopper::builder()
+        .config(config)
+        .store(MemoryStore::new())
+        .config_dir(project.path().join("config"))
+        .cwd(project.path())
+        .env([("BHOP_CMD_SEPARATOR", "#")])
+        .build()
+        .unwrap();
+
+    // Relative paths are resolved from the builder's working directory.
+    assert_eq!(run(&mut hopper, &["add", "src", "code"]), "");
+    assert_eq!(run(&mut hopper, &["code"]), format!("{}/src#", root));
+    assert_eq!(run(&mut hopper, &["find", "src"]), format!("{}/src", root));
+    assert_eq!(
+        run(&mut hopper, &["g", "/", "test"]),
+        format!("{}#cargo test", root)
+    );
+    assert_eq!(run(&mut hopper, &["undo"]), "Undid add of code.");
+    assert!(!project.path().join("config").exists());
+}
+
+#[test]
+fn layered_defaults_come_from_the_builder_env() {
+    let temp_dir = tempdir().unwrap();
+    let config_dir = temp_dir.path().join("config");
+    fs::create_dir(&config_dir).unwrap();
+    fs::write(
+        config_dir.join("bhop.toml"),
+        "[settings]\nrepo_roots = [\"~/src\"]\nhistory_exclude = [\"~/scratch\"]\n",
+    )
+    .unwrap();
+    let home = temp_dir.path().join("home");
+    let hopper = Hopper::builder()
+        .store(MemoryStore::new())
+        .config_dir(&config_dir)
+        .cwd(temp_dir.path())
+        .env([
+            ("EDITOR", "kak".to_string()),
+            ("HOME", home.display().to_string()),
+        ])
+        .build()
+        .unwrap();
+
+    assert_eq!(hopper.config.default_editor, "kak");
+    assert_eq!(hopper.config.relocate_roots, vec![home.clone()]);
+    assert_eq!(hopper.config.repo_roots, vec![home.join("src")]);
+    assert_eq!(
+        hopper.config.history_rules.exclude,
+        vec![home.join("scratch").display().to_string()]
+    );
+
+    // Without either, the defaults don't fall back on the process's.
+    let hopper = Hopper::builder()
+        .store(MemoryStore::new())
+        .config_dir(temp_dir.path().join("empty"))
+        .cwd(temp_dir.path())
+        .env([("TERM", "dumb")])
+        .build()
+        .unwrap();
+    let fallback = if cfg!(windows) { "notepad" } else { "vi" };
+    assert_eq!(hopper.config.default_editor, fallback);
+    assert!(hopper.config.relocate_roots.is_empty());
+}
+
+#[test]
+#[serial]
+fn config_dir_comes_from_the_builder_env() {
+    let temp_dir = tempdir().unwrap();
+    let bogus = temp_dir.path().join("bogus");
+    env::set_var("BHOP_CONFIG_DIRECTORY", &bogus);
+
+    let config_dir = temp_dir.path().join("config");
+    Hopper::builder()
+        .cwd(temp_dir.path())
+        .env([("BHOP_CONFIG_DIRECTORY", config_dir.display().to_string())])
+        .build()
+        .unwrap();
+    assert!(config_dir.join("db").join("bhop.db").exists());
+
+    // Without `BHOP_CONFIG_DIRECTORY`, the default is under the instance's home.
+    let home = temp_dir.path().join("home");
+    Hopper::builder()
+        .cwd(temp_dir.path())
+        .env([("HOME", home.display().to_string())])
+        .build()
+        .unwrap();
+    env::remove_var("BHOP_CONFIG_DIRECTORY");
+    assert!(home.join(".config/bhop/db/bhop.db").exists());
+    assert!(!bogus.exists());
+}
@@ -0,0 +1,82 @@
+use bhop::args::Request;
+use bhop::completions::{complete_in, script, SHELLS};
+use bhop::configs::Configs;
+use bhop::store::{MemoryStore, SqliteStore};
+use bhop::Hopper;
+use std::fs;
+use tempfile::tempdir;
+
+fn complete(hopper: &Hopper, words: &[&str]) -> Vec<String> {
+    let words = words.iter().map(|w| w.to_string()).collect();
+    hopper
+        .complete(words)
+        .unwrap()
+        .lines()
+        .map(|l| l.to_string())
+        .collect()
+}
+
+#[test]
+fn test_scripts() {
+    for shell in SHELLS {
+        let script = script(shell).unwrap();
+        assert!(script.contains("bhop __complete"));
+        assert!(!script.contains("__FUNCTION_ALIAS__"));
+        assert!(!script.contains("__HOPPERCMD__"));
+    }
+    assert!(script("tcsh").is_none());
+}
+
+#[test]
+fn test_complete() {
+    let project = tempdir().unwrap();
+    fs::write(project.path().join(".bhop"), "test = \"cargo test\"\n").unwrap();
+    let mut hopper = Hopper::builder()
+        .config(Configs::default())
+        .store(MemoryStore::new())
+        .build()
+        .unwrap();
+    let location = project.path().to_string_lossy().to_string();
+    hopper
+        .execute(Request::Add(location, Some("work".to_string())))
+        .unwrap();
+
+    assert_eq!(complete(&hopper, &["wo"]), vec!["work"]);
+    assert!(complete(&hopper, &["re"]).contains(&"remove".to_string()));
+    assert_eq!(complete(&hopper, &["rm", ""]), vec!["work"]);
+    assert_eq!(complete(&hopper, &["g", "work", ""]), vec!["test"]);
+    assert_eq!(complete(&hopper, &["history", "p"]), vec!["prune"]);
+    assert_eq!(complete(&hopper, &["completions", "z"]), vec!["zsh"]);
+}
+
+#[test]
+fn test_complete_without_setup() {
+    let temp_dir = tempdir().unwrap();
+    let config_dir = temp_dir.path().join("bhop");
+    let candidates = complete_in(&config_dir, vec!["hi".to_string()]).unwrap();
+    assert_eq!(candidates, "history");
+    assert!(!config_dir.exists());
+
+    let project = tempdir().unwrap();
+    fs::write(project.path().join(".bhop"), "test = \"cargo test\"\n").unwrap();
+    let env = bhop::metadata::Environment::setup(&config_dir);
+    let mut hopper = Hopper::builder()
+        .config(Configs::default())
+        .store(SqliteStore::open(&env.db_path).unwrap())
+        .config_dir(&config_dir)
+        .build()
+        .unwrap();
+    hopper
+        .execute(Request::Add(
+            project.path().display().to_string(),
+            Some("work".to_string()),
+        ))
+        .unwrap();
+    let words = ["g", "work", "t"].map(|w| w.to_string()).to_vec();
+    assert_eq!(complete_in(&config_dir, words).unwrap(), "test");
+    let usage = hopper
+        .search(Some("work"), &bhop::ListOptions::default())
+        .unwrap()[0]
+        .usage;
+    assert_eq!(usage, 0);
+}
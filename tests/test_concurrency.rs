@@ -0,0 +1,55 @@
+use bhop::args::Request;
+use bhop::configs::Configs;
+use bhop::Hopper;
+use std::fs;
+use std::path::Path;
+use std::thread;
+use tempfile::tempdir;
+
+const WORKERS: usize = 8;
+const VISITS: usize = 25;
+
+// Each one sets up and opens the database under `config_dir` on its own, like separate shells.
+fn sqlite_hopper(config_dir: &Path) -> Hopper {
+    Hopper::builder()
+        .config(Configs::default())
+        .config_dir(config_dir)
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn concurrent_hoppers_share_the_database() {
+    let temp_dir = tempdir().unwrap();
+    let config_dir = temp_dir.path().join("bhop");
+    let target = temp_dir.path().join("target");
+    fs::create_dir_all(&target).unwrap();
+    let target = target.display().to_string();
+
+    let workers: Vec<_> = (0..WORKERS)
+        .map(|i| {
+            let config_dir = config_dir.clone();
+            let target = target.clone();
+            thread::spawn(move || {
+                let mut hopper = sqlite_hopper(&config_dir);
+                // Jumps are always recorded, unlike the directory changes reported right after.
+                for _ in 0..VISITS {
+                    hopper.action(&target, false).unwrap();
+                }
+                hopper
+                    .execute(Request::Add(target, Some(format!("worker{}", i))))
+                    .unwrap();
+            })
+        })
+        .collect();
+    for worker in workers {
+        worker.join().unwrap();
+    }
+
+    let hopper = sqlite_hopper(&config_dir);
+    let history = hopper.store.history(0).unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].location, target);
+    assert_eq!(history[0].usage, (WORKERS * VISITS) as i64);
+    assert_eq!(hopper.store.shortcuts().unwrap().len(), WORKERS);
+}
@@ -1,4 +1,4 @@
-use ::bhop::configs::{Configs, ReadConfig, ReadSettings};
+use ::bhop::configs::{Configs, EditorSource, ReadConfig, ReadSettings, RuleKind};
 use std::collections::HashMap;
 use std::env;
 use std::fs::File;
@@ -37,6 +37,8 @@ fn test_read_config_from_file() {
         ReadConfig {
             settings: Some(expected_settings),
             editors: Some(expected_editors),
+            editor_rules: Some(Vec::new()),
+            theme: Some(Default::default()),
         }
     );
 }
@@ -51,7 +53,7 @@ fn test_configs_uses_defaults() {
 
     assert_eq!(configs.ls_display_block, 0);
     assert_eq!(configs.print_color_primary, [51, 255, 255]);
-    assert_eq!(configs.verbose, false);
+    assert!(!configs.verbose);
 }
 
 #[test]
@@ -74,7 +76,7 @@ fn test_configs_uses_values_from_file() {
     let configs = Configs::new(&config_path);
 
     assert_eq!(configs.ls_display_block, 10);
-    assert_eq!(configs.verbose, true);
+    assert!(configs.verbose);
     assert_eq!(
         configs.editors,
         [("python".to_string(), "python3".to_string())]
@@ -95,3 +97,67 @@ fn test_configs_uses_system_editor() {
 
     assert_eq!(configs.default_editor, "nano");
 }
+
+#[test]
+fn test_editor_rules_take_precedence() {
+    let dir = tempdir().unwrap();
+    let config_path = dir.path().join("config.toml");
+    let mut file = File::create(&config_path).unwrap();
+    write!(
+        file,
+        r#"
+        [settings]
+        default_editor = "vi"
+        [editors]
+        ts = "nvim"
+        [[editor_rules]]
+        glob = "*.test.ts"
+        editor = "code"
+        [[editor_rules]]
+        filename = "Makefile"
+        editor = "make-editor"
+        [[editor_rules]]
+        kind = "dir"
+        editor = "dir-editor"
+        [[editor_rules]]
+        shebang = "python"
+        editor = "py-editor"
+        "#,
+    )
+    .unwrap();
+    let script_path = dir.path().join("script");
+    let mut script = File::create(&script_path).unwrap();
+    writeln!(script, "#!/usr/bin/env python3").unwrap();
+
+    let configs = Configs::new(&config_path);
+
+    assert_eq!(configs.editor_for("src/app.test.ts").0, "code");
+    assert_eq!(
+        configs.editor_for("src/app.ts"),
+        (
+            "nvim".to_string(),
+            EditorSource::Extension("ts".to_string())
+        )
+    );
+    assert_eq!(configs.editor_for("Makefile").0, "make-editor");
+    assert_eq!(configs.editor_for(dir.path()).0, "dir-editor");
+    assert_eq!(configs.editor_for(&script_path).0, "py-editor");
+    assert_eq!(
+        configs.editor_for("notes"),
+        ("vi".to_string(), EditorSource::Default)
+    );
+}
+
+#[test]
+fn test_editor_rule_kinds() {
+    let config: ReadConfig =
+        toml::from_str("[[editor_rules]]\nkind = \"directory\"\neditor = \"code\"\n").unwrap();
+    assert_eq!(config.editor_rules.unwrap()[0].kind, Some(RuleKind::Dir));
+
+    let error =
+        toml::from_str::<ReadConfig>("[[editor_rules]]\nkind = \"folder\"\neditor = \"code\"\n")
+            .unwrap_err()
+            .to_string();
+    assert!(error.contains("unknown variant `folder`"));
+    assert!(error.contains("`file` or `dir`"));
+}
@@ -0,0 +1,165 @@
+use bhop::args::Request;
+use bhop::configs::Configs;
+use bhop::doctor::{check_path, PathProblem};
+use bhop::shortcuts::ShortcutEntry;
+use bhop::store::MemoryStore;
+use bhop::{sanitize, Hopper};
+use std::fs;
+use std::path::Path;
+use tempfile::tempdir;
+
+#[test]
+fn test_check_path_existing() {
+    let dir = tempdir().unwrap();
+    assert_eq!(check_path(dir.path()), None);
+}
+
+#[test]
+fn test_check_path_missing() {
+    let dir = tempdir().unwrap();
+    assert_eq!(
+        check_path(dir.path().join("gone")),
+        Some(PathProblem::Missing)
+    );
+}
+
+fn memory_hopper() -> Hopper {
+    Hopper::builder()
+        .config(Configs::default())
+        .store(MemoryStore::new())
+        .build()
+        .unwrap()
+}
+
+// Shortcuts `ok`, `dup` pointing at the same directory as `ok`, `gone` whose directory moved to
+// one in the history, and `lost` with nowhere to go, plus a history entry that's missing.
+fn broken_setup(root: &Path) -> (Hopper, String) {
+    let mut hopper = memory_hopper();
+    let ok = root.join("ok");
+    let moved = root.join("new").join("project");
+    fs::create_dir_all(&ok).unwrap();
+    fs::create_dir_all(&moved).unwrap();
+    fs::write(ok.join(".bhop"), "test = [").unwrap();
+    for (name, location) in [
+        ("ok", ok.clone()),
+        ("dup", ok.clone()),
+        ("gone", root.join("old").join("project")),
+        ("lost", root.join("lost")),
+    ] {
+        hopper
+            .store
+            .write_shortcut(
+                name,
+                &ShortcutEntry {
+                    location: sanitize(&location).unwrap_or(location.display().to_string()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+    }
+    let moved = sanitize(&moved).unwrap();
+    let now = chrono::Utc::now().timestamp();
+    hopper.store.record_visit("project", &moved, now).unwrap();
+    hopper
+        .store
+        .record_visit("stale", &root.join("stale").display().to_string(), now)
+        .unwrap();
+    (hopper, moved)
+}
+
+fn labeled(report: &str, label: &str) -> Vec<String> {
+    report
+        .lines()
+        .filter_map(|line| line.strip_prefix(&format!("[{}] ", label)))
+        .map(|line| line.to_string())
+        .collect()
+}
+
+#[test]
+fn test_doctor_report() {
+    let root = tempdir().unwrap();
+    let (mut hopper, moved) = broken_setup(root.path());
+    let base = root.path().display().to_string();
+
+    let report = hopper.respond(Request::Doctor(false)).unwrap();
+    assert_eq!(
+        labeled(&report, "missing"),
+        vec![
+            format!("shortcut gone -> {}/old/project", base),
+            format!("shortcut lost -> {}/lost", base),
+            format!("history stale -> {}/stale", base),
+        ]
+    );
+    assert_eq!(
+        labeled(&report, "duplicate"),
+        vec![format!("dup, ok all point to {}/ok", base)]
+    );
+    assert_eq!(labeled(&report, "broken").len(), 1);
+    assert_eq!(
+        labeled(&report, "candidates"),
+        vec![format!("shortcut gone may have moved to {}", moved)]
+    );
+    assert!(report.ends_with("Run `hp doctor --fix` to retarget, remove or prune missing entries."));
+    // Nothing is changed without `--fix`.
+    assert_eq!(hopper.store.shortcuts().unwrap().len(), 4);
+}
+
+#[test]
+fn test_doctor_fix_confirmed() {
+    let root = tempdir().unwrap();
+    let (mut hopper, moved) = broken_setup(root.path());
+    let base = root.path().display().to_string();
+    // A second candidate for `lost` leaves the choice to the user.
+    let other = root.path().join("elsewhere").join("lost");
+    fs::create_dir_all(&other).unwrap();
+    let other = sanitize(&other).unwrap();
+    let now = chrono::Utc::now().timestamp();
+    hopper.store.record_visit("lost", &other, now).unwrap();
+    hopper
+        .store
+        .record_visit("lost", &format!("{}/ok/lost", base), now)
+        .unwrap();
+    fs::create_dir_all(root.path().join("ok").join("lost")).unwrap();
+
+    let report = hopper
+        .respond(Request::Confirmed(Box::new(Request::Doctor(true))))
+        .unwrap();
+    assert_eq!(
+        labeled(&report, "fixed"),
+        vec![
+            format!("retargeted shortcut gone -> {}", moved),
+            format!("pruned history {}/stale", base),
+        ]
+    );
+    assert_eq!(
+        labeled(&report, "skipped"),
+        vec!["shortcut lost has several candidates, use `hp retarget` to pick one".to_string()]
+    );
+    let shortcuts = hopper.store.shortcuts().unwrap();
+    assert_eq!(shortcuts["gone"].location, moved);
+    assert!(shortcuts.contains_key("lost"));
+    assert!(hopper
+        .store
+        .history(now)
+        .unwrap()
+        .iter()
+        .all(|entry| !entry.location.ends_with("/stale")));
+
+    hopper.execute(Request::Undo).unwrap();
+    assert_eq!(
+        hopper.store.shortcuts().unwrap()["gone"].location,
+        format!("{}/old/project", base)
+    );
+}
+
+#[test]
+fn test_doctor_fix_removes_without_candidates() {
+    let root = tempdir().unwrap();
+    let (mut hopper, _) = broken_setup(root.path());
+
+    let report = hopper
+        .respond(Request::Confirmed(Box::new(Request::Doctor(true))))
+        .unwrap();
+    assert!(labeled(&report, "fixed").contains(&"removed shortcut lost".to_string()));
+    assert!(!hopper.store.shortcuts().unwrap().contains_key("lost"));
+}
@@ -6,7 +6,7 @@ fn test_from_str_cmd_string() {
     let toml = r#"
     test = "command"
     "#;
-    let bhop_group = BhopGroup::from_str("test", toml).unwrap();
+    let bhop_group = BhopGroup::from_str("test", toml).unwrap().unwrap();
     assert_eq!(bhop_group.cmd, Some("command".to_string()));
     assert_eq!(bhop_group.editor, None);
     assert_eq!(bhop_group.files, None);
@@ -19,7 +19,7 @@ fn test_from_str_with_table() {
     editor = "vim"
     files = ["file1.rs", "file2.rs"]
     "#;
-    let bhop_group = BhopGroup::from_str("test_group", toml).unwrap();
+    let bhop_group = BhopGroup::from_str("test_group", toml).unwrap().unwrap();
     assert_eq!(bhop_group.cmd, None);
     assert_eq!(bhop_group.editor, Some("vim".to_string()));
     assert_eq!(
@@ -35,7 +35,7 @@ fn test_from_str_no_group() {
     editor = "vim"
     files = ["file1.rs", "file2.rs"]
     "#;
-    let bhop_group = BhopGroup::from_str("test_group", toml);
+    let bhop_group = BhopGroup::from_str("test_group", toml).unwrap();
     assert_eq!(bhop_group, None);
 }
 
@@ -43,7 +43,7 @@ fn test_from_str_no_group() {
 fn test_from_file() {
     let path = Path::new("test.toml");
     fs::write(
-        &path,
+        path,
         r#"
     [test_group]
     editor = "vim"
@@ -52,12 +52,33 @@ fn test_from_file() {
     )
     .unwrap();
 
-    let bhop_group = BhopGroup::from("test_group", &path).unwrap();
+    let bhop_group = BhopGroup::from("test_group", path).unwrap().unwrap();
     assert_eq!(bhop_group.cmd, None);
     assert_eq!(bhop_group.editor, Some("vim".to_string()));
     assert_eq!(
         bhop_group.files,
         Some(vec!["file1.rs".to_string(), "file2.rs".to_string()])
     );
-    fs::remove_file(&path).unwrap();
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_validate() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join(".bhop");
+    fs::write(&path, "test = \"cargo test\"\n[dev]\nlayout = \"tmux\"\n").unwrap();
+    assert_eq!(BhopGroup::validate(&path), Ok(()));
+    fs::write(&path, "[dev]\nlayout = \"screen\"\n").unwrap();
+    assert!(BhopGroup::validate(&path).is_err());
+    fs::write(&path, "test = [").unwrap();
+    assert!(BhopGroup::validate(&path).is_err());
+}
+
+#[test]
+fn test_names() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join(".bhop");
+    fs::write(&path, "test = \"cargo test\"\n[dev]\nlayout = \"tmux\"\n").unwrap();
+    assert_eq!(BhopGroup::names(&path), vec!["dev", "test"]);
+    assert!(BhopGroup::names(dir.path().join("missing")).is_empty());
 }
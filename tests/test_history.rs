@@ -0,0 +1,257 @@
+use bhop::args::Request;
+use bhop::configs::Configs;
+use bhop::history::{ago, closest, record_in, shared_depth, HistoryRules};
+use bhop::metadata::Environment;
+use bhop::store::{MemoryStore, SqliteStore, Store};
+use bhop::Hopper;
+use std::fs;
+use tempfile::tempdir;
+
+fn history(store: &dyn Store) -> Vec<(String, i64)> {
+    let mut rows: Vec<(String, i64)> = store
+        .history(0)
+        .unwrap()
+        .into_iter()
+        .map(|entry| (entry.name, entry.usage))
+        .collect();
+    rows.sort();
+    rows
+}
+
+// Without any `history_exclude`, so the temporary directories under /tmp are recorded.
+fn memory_hopper() -> Hopper {
+    Hopper::builder()
+        .config(Configs::default())
+        .store(MemoryStore::new())
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn history_rules_exclude_and_min_depth() {
+    assert!(HistoryRules::default().allows("/tmp"));
+    let rules = HistoryRules {
+        exclude: ["/tmp", "/tmp/*", "*/node_modules/*", "*/target"]
+            .map(|p| p.to_string())
+            .to_vec(),
+        min_depth: 0,
+    };
+    assert!(rules.allows("/home/you/src/hop"));
+    assert!(!rules.allows("/tmp"));
+    assert!(!rules.allows("/tmp/scratch"));
+    assert!(!rules.allows("/home/you/src/app/node_modules/react"));
+    assert!(!rules.allows("/home/you/src/hop/target"));
+
+    let rules = HistoryRules {
+        exclude: Vec::new(),
+        min_depth: 2,
+    };
+    assert!(!rules.allows("/home"));
+    assert!(rules.allows("/home/you"));
+}
+
+#[test]
+fn record_counts_visits() {
+    let temp_dir = tempdir().unwrap();
+    let mut hopper = memory_hopper();
+    let project = temp_dir.path().join("project");
+    fs::create_dir_all(&project).unwrap();
+    let project = project.display().to_string();
+
+    let other = temp_dir.path().display().to_string();
+    for location in [&project, &other, &project] {
+        hopper.execute(Request::Record(location.clone())).unwrap();
+    }
+    assert!(history(hopper.store.as_ref()).contains(&("project".to_string(), 2)));
+}
+
+#[test]
+fn record_in_follows_the_history_settings() {
+    let temp_dir = tempdir().unwrap();
+    let config_dir = temp_dir.path().join("bhop");
+    let project = temp_dir.path().join("project");
+    let scratch = temp_dir.path().join("scratch");
+    fs::create_dir_all(&project).unwrap();
+    fs::create_dir_all(&scratch).unwrap();
+    // Nothing is recorded before bhop has set up its database.
+    record_in(&config_dir, &project).unwrap();
+    assert!(!config_dir.exists());
+
+    fs::create_dir_all(&config_dir).unwrap();
+    fs::write(
+        config_dir.join("bhop.toml"),
+        "[settings]\nhistory_exclude = [\"*/scratch\"]\n",
+    )
+    .unwrap();
+    let env = Environment::setup(&config_dir);
+    record_in(&config_dir, &project).unwrap();
+    record_in(&config_dir, &scratch).unwrap();
+    let store = SqliteStore::open(&env.db_path).unwrap();
+    assert_eq!(history(&store), vec![("project".to_string(), 1)]);
+}
+
+#[test]
+fn history_rules_come_from_every_layer_but_the_project() {
+    let temp_dir = tempdir().unwrap();
+    let config_dir = temp_dir.path().join("bhop");
+    fs::create_dir_all(&config_dir).unwrap();
+    fs::write(
+        config_dir.join("bhop.toml"),
+        "[settings]\nhistory_min_depth = 2\n",
+    )
+    .unwrap();
+    let system_path = temp_dir.path().join("system.toml");
+    fs::write(
+        &system_path,
+        "[settings]\nhistory_exclude = [\"*/scratch\"]\nhistory_min_depth = 1\n",
+    )
+    .unwrap();
+    let system = system_path.display().to_string();
+    let rules = HistoryRules::load_with(&config_dir, |key| match key {
+        "BHOP_SYSTEM_CONFIG" => Some(system.clone()),
+        _ => None,
+    });
+    assert_eq!(rules.exclude, vec!["*/scratch".to_string()]);
+    assert_eq!(rules.min_depth, 2);
+
+    let rules = HistoryRules::load_with(&config_dir, |key| match key {
+        "BHOP_SYSTEM_CONFIG" => Some(system.clone()),
+        "BHOP_SETTING_HISTORY_MIN_DEPTH" => Some("4".to_string()),
+        _ => None,
+    });
+    assert_eq!(rules.min_depth, 4);
+}
+
+#[test]
+fn jumps_are_recorded_once() {
+    let temp_dir = tempdir().unwrap();
+    let mut hopper = memory_hopper();
+    let project = temp_dir.path().join("project");
+    fs::create_dir_all(&project).unwrap();
+    fs::write(project.join("notes.md"), "").unwrap();
+    let location = project.display().to_string();
+    hopper
+        .execute(Request::Add(location.clone(), Some("proj".to_string())))
+        .unwrap();
+
+    // Jumps are recorded for shells without a hook, and the directory change the hook reports
+    // right after isn't counted again.
+    hopper.action("proj", false).unwrap();
+    assert_eq!(
+        history(hopper.store.as_ref()),
+        vec![("project".to_string(), 1)]
+    );
+    hopper.execute(Request::Record(location)).unwrap();
+    assert_eq!(
+        history(hopper.store.as_ref()),
+        vec![("project".to_string(), 1)]
+    );
+
+    hopper
+        .action(&project.join("notes.md").display().to_string(), false)
+        .unwrap();
+    assert_eq!(
+        history(hopper.store.as_ref()),
+        vec![("notes.md".to_string(), 1), ("project".to_string(), 1)]
+    );
+}
+
+#[test]
+fn prune_and_forget_history() {
+    let temp_dir = tempdir().unwrap();
+    let mut hopper = memory_hopper();
+    for name in ["keep", "scratch", "node_modules", "old"] {
+        let dir = temp_dir.path().join(name);
+        fs::create_dir_all(&dir).unwrap();
+        hopper
+            .execute(Request::Record(dir.display().to_string()))
+            .unwrap();
+    }
+
+    hopper.config.history_rules = HistoryRules {
+        exclude: vec!["*/node_modules".to_string(), "*/scratch".to_string()],
+        min_depth: 0,
+    };
+    hopper
+        .execute(Request::DryRun(Box::new(Request::PruneHistory)))
+        .unwrap();
+    assert_eq!(history(hopper.store.as_ref()).len(), 4);
+    hopper.execute(Request::PruneHistory).unwrap();
+    hopper
+        .execute(Request::ForgetHistory("ol*".to_string()))
+        .unwrap();
+    assert_eq!(
+        history(hopper.store.as_ref()),
+        vec![("keep".to_string(), 1)]
+    );
+}
+
+#[test]
+fn ago_formats_elapsed_time() {
+    let now = 1_700_000_000;
+    assert_eq!(ago(now - 5, now), "just now");
+    assert_eq!(ago(now - 5 * 60, now), "5 min ago");
+    assert_eq!(ago(now - 3 * 60 * 60, now), "3 h ago");
+    assert_eq!(ago(now - 9 * 24 * 60 * 60, now), "9 days ago");
+}
+
+#[test]
+fn closest_prefers_shared_prefix_then_score() {
+    let candidates = vec![
+        ("/home/you/work/api/src".to_string(), 10.0),
+        ("/home/you/src/hop/src".to_string(), 2.0),
+        ("/home/you/src/web/src".to_string(), 3.0),
+    ];
+    assert_eq!(
+        closest("/home/you/src/hop/tests", &candidates),
+        Some("/home/you/src/hop/src".to_string())
+    );
+    assert_eq!(
+        closest("/home/you/src", &candidates),
+        Some("/home/you/src/web/src".to_string())
+    );
+    assert_eq!(
+        closest("/etc", &candidates),
+        Some("/home/you/work/api/src".to_string())
+    );
+    assert_eq!(shared_depth("/home/you/src", "/home/me"), 2);
+}
+
+// Forgetting a directory takes its visits along, so it's gone from the stats and starts over
+// with a fresh score when visited again.
+fn forget_drops_visits(mut hopper: Hopper) {
+    let now = chrono::Utc::now().timestamp();
+    for _ in 0..3 {
+        hopper.store.record_visit("old", "/srv/old", now).unwrap();
+    }
+    hopper
+        .execute(Request::ForgetHistory("old".to_string()))
+        .unwrap();
+    let report = hopper.stats_report(30, now).unwrap();
+    assert!(report
+        .iter()
+        .flat_map(|(_, rows)| rows.iter())
+        .all(|[name, _, _]| !name.contains("/srv/old")));
+
+    hopper.store.record_visit("old", "/srv/old", now).unwrap();
+    hopper.store.record_visit("new", "/srv/new", now).unwrap();
+    let history = hopper.store.history(now).unwrap();
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0].score, history[1].score);
+    assert!(history.iter().all(|entry| entry.usage == 1));
+}
+
+#[test]
+fn forget_history_drops_its_visits() {
+    forget_drops_visits(memory_hopper());
+    let dir = tempdir().unwrap();
+    let env = Environment::setup(dir.path());
+    forget_drops_visits(
+        Hopper::builder()
+            .config(Configs::default())
+            .store(SqliteStore::open(&env.db_path).unwrap())
+            .config_dir(dir.path())
+            .build()
+            .unwrap(),
+    );
+}
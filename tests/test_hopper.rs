@@ -1,4 +1,9 @@
-use bhop::sanitize;
+use bhop::args::Request;
+use bhop::configs::Configs;
+use bhop::store::MemoryStore;
+use bhop::{fill_editor, sanitize, split_position, Hopper, Position};
+use std::fs;
+use tempfile::tempdir;
 
 #[test]
 fn sanitize_correctly_replaces_backslashes() {
@@ -17,3 +22,139 @@ fn sanitize_correctly_replaces_backslashes() {
         }
     );
 }
+
+#[test]
+fn split_position_parses_line_and_col() {
+    assert_eq!(
+        split_position("src/lib.rs:120:4"),
+        Some((
+            "src/lib.rs".to_string(),
+            Position {
+                line: 120,
+                col: Some(4)
+            }
+        ))
+    );
+    assert_eq!(
+        split_position("src/lib.rs:120"),
+        Some((
+            "src/lib.rs".to_string(),
+            Position {
+                line: 120,
+                col: None
+            }
+        ))
+    );
+    assert_eq!(split_position("src/lib.rs"), None);
+    assert_eq!(
+        split_position("C:/src/lib.rs:7"),
+        Some(("C:/src/lib.rs".to_string(), Position { line: 7, col: None }))
+    );
+}
+
+#[test]
+fn fill_editor_uses_placeholders() {
+    let position = Position {
+        line: 12,
+        col: Some(3),
+    };
+    assert_eq!(
+        fill_editor("nvim +{line} {}", "lib.rs", Some(&position)),
+        "nvim +12 lib.rs"
+    );
+    assert_eq!(
+        fill_editor("code -g {}:{line}:{col}", "lib.rs", Some(&position)),
+        "code -g lib.rs:12:3"
+    );
+    assert_eq!(fill_editor("vi", "lib.rs", Some(&position)), "vi lib.rs");
+    assert_eq!(
+        fill_editor("nvim +{line} {}", "lib.rs", None),
+        "nvim +1 lib.rs"
+    );
+}
+
+fn memory_hopper() -> Hopper {
+    Hopper::builder()
+        .config(Configs::default())
+        .store(MemoryStore::new())
+        .build()
+        .unwrap()
+}
+
+fn shortcut_names(hopper: &Hopper) -> Vec<String> {
+    hopper.store.shortcuts().unwrap().into_keys().collect()
+}
+
+#[test]
+fn remove_and_undo_shortcuts() {
+    let temp_dir = tempdir().unwrap();
+    let mut hopper = memory_hopper();
+    let target = temp_dir.path().display().to_string();
+    for name in ["t1", "t2"] {
+        hopper
+            .execute(Request::Add(target.clone(), Some(name.to_string())))
+            .unwrap();
+    }
+
+    hopper
+        .execute(Request::DryRun(Box::new(Request::Remove("t*".to_string()))))
+        .unwrap();
+    assert_eq!(shortcut_names(&hopper), vec!["t1", "t2"]);
+
+    hopper
+        .execute(Request::Confirmed(Box::new(Request::Remove(
+            "t*".to_string(),
+        ))))
+        .unwrap();
+    assert!(shortcut_names(&hopper).is_empty());
+
+    hopper.execute(Request::Undo).unwrap();
+    assert_eq!(shortcut_names(&hopper), vec!["t1", "t2"]);
+    hopper.execute(Request::Undo).unwrap();
+    assert_eq!(shortcut_names(&hopper), vec!["t1"]);
+}
+
+#[test]
+fn move_tree_rewrites_locations_under_prefix() {
+    let temp_dir = tempdir().unwrap();
+    let mut hopper = memory_hopper();
+    let old = temp_dir.path().join("old");
+    let new = temp_dir.path().join("new");
+    fs::create_dir_all(old.join("inner")).unwrap();
+    fs::create_dir_all(temp_dir.path().join("older")).unwrap();
+    for (name, path) in [
+        ("top", old.clone()),
+        ("inner", old.join("inner")),
+        ("older", temp_dir.path().join("older")),
+    ] {
+        hopper
+            .execute(Request::Add(
+                path.display().to_string(),
+                Some(name.to_string()),
+            ))
+            .unwrap();
+    }
+    hopper
+        .execute(Request::MoveTree(
+            old.display().to_string(),
+            new.display().to_string(),
+        ))
+        .unwrap();
+
+    let locations: Vec<String> = hopper
+        .store
+        .shortcuts()
+        .unwrap()
+        .into_values()
+        .map(|entry| entry.location)
+        .collect();
+    let base = sanitize(temp_dir.path()).unwrap();
+    assert_eq!(
+        locations,
+        vec![
+            format!("{}/new/inner", base),
+            format!("{}/older", base),
+            format!("{}/new", base),
+        ]
+    );
+}
@@ -0,0 +1,66 @@
+use bhop::identity::{git_remote, Identity};
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn git_remote_prefers_origin() {
+    let temp_dir = tempdir().unwrap();
+    fs::create_dir_all(temp_dir.path().join(".git")).unwrap();
+    fs::write(
+        temp_dir.path().join(".git").join("config"),
+        "[core]\n\tbare = false\n\
+         [remote \"upstream\"]\n\turl = git@example.com:up/hop.git\n\
+         [remote \"origin\"]\n\turl = git@example.com:me/hop.git\n\
+         \tfetch = +refs/heads/*:refs/remotes/origin/*\n",
+    )
+    .unwrap();
+    assert_eq!(
+        git_remote(temp_dir.path()),
+        Some("git@example.com:me/hop.git".to_string())
+    );
+    assert_eq!(git_remote(temp_dir.path().join("missing")), None);
+}
+
+#[cfg(unix)]
+#[test]
+fn identity_finds_moved_directory() {
+    let temp_dir = tempdir().unwrap();
+    let before = temp_dir.path().join("a").join("project");
+    let after = temp_dir.path().join("b").join("c").join("project");
+    fs::create_dir_all(&before).unwrap();
+    fs::create_dir_all(after.parent().unwrap()).unwrap();
+    let identity = Identity::of(&before);
+    fs::rename(&before, &after).unwrap();
+
+    assert_eq!(
+        identity.find_in(&before, &[temp_dir.path()], 4),
+        Some(after)
+    );
+    assert_eq!(identity.find_in(&before, &[temp_dir.path()], 1), None);
+}
+
+#[cfg(unix)]
+#[test]
+fn identity_needs_a_second_signal() {
+    let temp_dir = tempdir().unwrap();
+    let before = temp_dir.path().join("project");
+    let renamed = temp_dir.path().join("renamed");
+    fs::create_dir_all(before.join(".git")).unwrap();
+    let identity = Identity::of(&before);
+    fs::rename(&before, &renamed).unwrap();
+
+    // The same inode under a different name and without a remote could be anything.
+    assert_eq!(identity.find_in(&before, &[temp_dir.path()], 2), None);
+
+    fs::write(
+        renamed.join(".git").join("config"),
+        "[remote \"origin\"]\n\turl = git@example.com:me/hop.git\n",
+    )
+    .unwrap();
+    let identity = Identity::of(&renamed);
+    fs::rename(&renamed, temp_dir.path().join("elsewhere")).unwrap();
+    assert_eq!(
+        identity.find_in(&renamed, &[temp_dir.path()], 2),
+        Some(temp_dir.path().join("elsewhere"))
+    );
+}
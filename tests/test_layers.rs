@@ -0,0 +1,200 @@
+use bhop::groups::BhopGroup;
+use bhop::layers::{self, ConfigLayer, Origin};
+use bhop::store::MemoryStore;
+use bhop::Hopper;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use tempfile::tempdir;
+
+fn layer(origin: Origin, toml: &str) -> ConfigLayer {
+    ConfigLayer::parse(origin, toml).unwrap()
+}
+
+#[test]
+fn test_merge_precedence() {
+    let layers = vec![
+        layer(
+            Origin::System(PathBuf::from("/etc/bhop/bhop.toml")),
+            "[settings]\nalways_jump = true\nrepo_max_depth = 5\n[editors]\nmd = \"glow\"\n\
+             [[editor_rules]]\nfilename = \"Makefile\"\neditor = \"vim\"\n",
+        ),
+        layer(
+            Origin::User(PathBuf::from("bhop.toml")),
+            "[settings]\nrepo_max_depth = 2\n[editors]\nmd = \"less\"\n\
+             [[editor_rules]]\nglob = \"*.toml\"\neditor = \"helix\"\n",
+        ),
+        layer(
+            Origin::Project(PathBuf::from(".bhop")),
+            "build = \"make\"\n[settings]\nalways_jump = false\n\
+             [[editor_rules]]\nglob = \"*.rs\"\neditor = \"nvim\"\n",
+        ),
+    ];
+    let merged = layers::merge(&layers).unwrap();
+    let settings = merged.settings.unwrap();
+    assert_eq!(settings.always_jump, Some(false));
+    assert_eq!(settings.repo_max_depth, Some(2));
+    assert_eq!(merged.editors.unwrap()["md"], "less");
+    // Rules of higher layers are tried first, while those of project files are left out.
+    let rules = merged.editor_rules.unwrap();
+    assert_eq!(rules.len(), 2);
+    assert_eq!(rules[0].editor, "helix");
+    assert_eq!(rules[1].editor, "vim");
+    assert!(layers[2]
+        .warning
+        .as_ref()
+        .is_some_and(|w| w.starts_with("Ignoring [editor_rules] in .bhop")));
+
+    assert!(matches!(
+        layers::origin(&layers, "settings", "always_jump"),
+        Some(Origin::Project(_))
+    ));
+    assert!(matches!(
+        layers::origin(&layers, "settings", "repo_max_depth"),
+        Some(Origin::User(_))
+    ));
+    assert_eq!(layers::origin(&layers, "settings", "verbose"), None);
+    // Groups in a project file aren't part of its configuration.
+    assert!(!layers[2].document.contains_key("build"));
+}
+
+#[test]
+fn test_load_layers() {
+    let dir = tempdir().unwrap();
+    let system = dir.path().join("system.toml");
+    let user = dir.path().join("bhop.toml");
+    let project = dir.path().join("proj");
+    fs::create_dir_all(project.join("src")).unwrap();
+    fs::write(&system, "[settings]\nverbose = true\nrepo_max_depth = 5\n").unwrap();
+    fs::write(&user, "[settings]\nrepo_max_depth = 2\n").unwrap();
+    fs::write(
+        project.join(".bhop"),
+        "test = \"cargo test\"\n[settings]\nrepo_max_depth = 1\n",
+    )
+    .unwrap();
+    let vars = HashMap::from([
+        (
+            "BHOP_SYSTEM_CONFIG".to_string(),
+            system.display().to_string(),
+        ),
+        ("BHOP_SETTING_ALWAYS_JUMP".to_string(), "true".to_string()),
+    ]);
+
+    let found = layers::load(&user, &project.join("src"), ".bhop", |k| {
+        vars.get(k).cloned()
+    })
+    .unwrap();
+    let origins: Vec<String> = found.iter().map(|l| l.origin.to_string()).collect();
+    assert_eq!(
+        origins,
+        vec![
+            "system".to_string(),
+            "user".to_string(),
+            format!("project {}", project.join(".bhop").display()),
+            "env BHOP_SETTING_ALWAYS_JUMP".to_string(),
+        ]
+    );
+    let settings = layers::merge(&found).unwrap().settings.unwrap();
+    assert_eq!(settings.verbose, Some(true));
+    assert_eq!(settings.repo_max_depth, Some(1));
+    assert_eq!(settings.always_jump, Some(true));
+
+    // Outside of the project, with a broken environment override.
+    let vars = HashMap::from([(
+        "BHOP_SETTING_REPO_MAX_DEPTH".to_string(),
+        "deep".to_string(),
+    )]);
+    assert!(layers::load(&user, dir.path(), ".bhop", |k| vars.get(k).cloned()).is_err());
+
+    // The settings of a .bhop file don't show up as a group.
+    assert_eq!(
+        BhopGroup::names(project.join(".bhop")),
+        vec!["test".to_string()]
+    );
+    assert_eq!(
+        BhopGroup::from("settings", project.join(".bhop")).unwrap(),
+        None
+    );
+}
+
+#[test]
+fn test_builder_layers() {
+    let dir = tempdir().unwrap();
+    let system = dir.path().join("system.toml");
+    fs::write(
+        &system,
+        "[settings]\nalways_jump = true\nrepo_max_depth = 5\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("bhop.toml"),
+        "[settings]\nrepo_max_depth = 2\n",
+    )
+    .unwrap();
+    let hopper = Hopper::builder()
+        .config_dir(dir.path())
+        .cwd(dir.path())
+        .store(MemoryStore::new())
+        .env([
+            ("BHOP_SYSTEM_CONFIG", system.display().to_string()),
+            ("BHOP_SETTING_PRIORITIZE_SHORTCUTS", "true".to_string()),
+        ])
+        .build()
+        .unwrap();
+    assert!(hopper.config.always_jump);
+    assert!(hopper.config.prioritize_shortcuts);
+    assert_eq!(hopper.config.repo_max_depth, 2);
+}
+
+#[test]
+fn test_project_layers_are_restricted() {
+    let dir = tempdir().unwrap();
+    let user = dir.path().join("bhop.toml");
+    fs::write(&user, "[settings]\ndefault_editor = \"vim\"\n").unwrap();
+    let project = dir.path().join("proj");
+    fs::create_dir_all(&project).unwrap();
+    fs::write(
+        project.join(".bhop"),
+        "[settings]\ndefault_editor = \"sh -c evil\"\nalways_jump = true\n\
+         [editors]\nrs = \"sh -c evil\"\n",
+    )
+    .unwrap();
+    let hopper = Hopper::builder()
+        .config_dir(dir.path())
+        .cwd(&project)
+        .store(MemoryStore::new())
+        .env([(
+            "BHOP_SYSTEM_CONFIG",
+            dir.path().join("none").display().to_string(),
+        )])
+        .build()
+        .unwrap();
+    assert_eq!(hopper.config.default_editor, "vim");
+    assert!(hopper.config.editors.is_empty());
+    assert!(hopper.config.always_jump);
+    assert_eq!(
+        hopper.warnings,
+        vec![format!(
+            "Ignoring settings.default_editor, [editors] in {}, only bhop.toml can choose the \
+             editors that are run.",
+            project.join(".bhop").display()
+        )]
+    );
+
+    // A project file that isn't configuration, like one with a group called `settings` from
+    // before the layers, is skipped.
+    fs::write(project.join(".bhop"), "settings = \"make settings\"\n").unwrap();
+    let hopper = Hopper::builder()
+        .config_dir(dir.path())
+        .cwd(&project)
+        .store(MemoryStore::new())
+        .env([(
+            "BHOP_SYSTEM_CONFIG",
+            dir.path().join("none").display().to_string(),
+        )])
+        .build()
+        .unwrap();
+    assert_eq!(hopper.config.default_editor, "vim");
+    assert_eq!(hopper.warnings.len(), 1);
+    assert!(hopper.warnings[0].ends_with(", skipping it."));
+}
@@ -0,0 +1,140 @@
+use bhop::groups::BhopGroup;
+use bhop::layouts::{render, Layout, Multiplexer, Session, Window};
+
+fn windows() -> Vec<Window> {
+    vec![
+        Window {
+            name: "editor".to_string(),
+            cmd: Some("nvim src/lib.rs".to_string()),
+        },
+        Window {
+            name: "shell".to_string(),
+            cmd: None,
+        },
+    ]
+}
+
+fn layout(panes: bool) -> Layout {
+    Layout {
+        multiplexer: Multiplexer::Tmux,
+        session: None,
+        windows: vec!["editor".to_string(), "shell".to_string()],
+        panes,
+    }
+}
+
+#[test]
+fn test_layout_from_group() {
+    let toml = r#"
+    [dev]
+    layout = "zellij"
+    session = "work"
+    windows = ["editor", "test"]
+    "#;
+    let bhop_group = BhopGroup::from_str("dev", toml).unwrap().unwrap();
+    assert_eq!(
+        bhop_group.layout,
+        Some(Layout {
+            multiplexer: Multiplexer::Zellij,
+            session: Some("work".to_string()),
+            windows: vec!["editor".to_string(), "test".to_string()],
+            panes: false,
+        })
+    );
+}
+
+#[test]
+fn test_unknown_layout_is_an_error() {
+    let toml = r#"
+    [dev]
+    layout = "screen"
+    windows = ["editor"]
+    "#;
+    let error = BhopGroup::from_str("dev", toml).unwrap_err().to_string();
+    assert!(error.contains("`dev`"));
+    assert!(error.contains("\"screen\""));
+    assert!(error.contains("tmux, zellij"));
+}
+
+#[test]
+fn test_session_name_from_dir() {
+    let session = Session::new(&layout(false), "/home/you/my.project", windows());
+    assert_eq!(session.name, "my_project");
+}
+
+#[test]
+fn test_window_names_are_tmux_targets() {
+    let windows = vec![
+        Window {
+            name: "web.dev".to_string(),
+            cmd: Some("npm start".to_string()),
+        },
+        Window {
+            name: "db:local".to_string(),
+            cmd: None,
+        },
+    ];
+    let session = Session::new(&layout(false), "/home/you/hop", windows);
+    let cmds: Vec<String> = session.tmux_setup().iter().map(|c| render(c)).collect();
+    assert_eq!(
+        cmds,
+        vec![
+            "tmux new-session -d -s hop -n web_dev -c /home/you/hop",
+            "tmux send-keys -t hop:web_dev 'npm start' Enter",
+            "tmux new-window -t hop -n db_local -c /home/you/hop",
+            "tmux select-window -t hop:web_dev",
+        ]
+    );
+}
+
+#[test]
+fn test_tmux_setup_windows() {
+    let session = Session::new(&layout(false), "/home/you/hop", windows());
+    let cmds: Vec<String> = session.tmux_setup().iter().map(|c| render(c)).collect();
+    assert_eq!(
+        cmds,
+        vec![
+            "tmux new-session -d -s hop -n editor -c /home/you/hop",
+            "tmux send-keys -t hop:editor 'nvim src/lib.rs' Enter",
+            "tmux new-window -t hop -n shell -c /home/you/hop",
+            "tmux select-window -t hop:editor",
+        ]
+    );
+    assert_eq!(
+        render(&session.tmux_attach(false)),
+        "tmux attach-session -t hop"
+    );
+    assert_eq!(
+        render(&session.tmux_attach(true)),
+        "tmux switch-client -t hop"
+    );
+}
+
+#[test]
+fn test_tmux_setup_panes() {
+    let session = Session::new(&layout(true), "/home/you/hop", windows());
+    let cmds: Vec<String> = session.tmux_setup().iter().map(|c| render(c)).collect();
+    assert_eq!(
+        cmds,
+        vec![
+            "tmux new-session -d -s hop -n editor -c /home/you/hop",
+            "tmux send-keys -t hop 'nvim src/lib.rs' Enter",
+            "tmux split-window -t hop -c /home/you/hop",
+            "tmux select-layout -t hop tiled",
+        ]
+    );
+}
+
+#[test]
+fn test_zellij_layout() {
+    let session = Session::new(&layout(false), "/home/you/hop", windows());
+    let kdl = session.zellij_layout();
+    assert!(kdl.starts_with("layout {\n"));
+    assert!(kdl.contains("tab name=\"editor\" cwd=\"/home/you/hop\""));
+    assert!(kdl.contains("args \"-c\" \"nvim src/lib.rs\""));
+    assert!(kdl.contains("pane name=\"shell\"\n"));
+    assert_eq!(
+        render(&session.zellij_launch("/tmp/hop.kdl", false)),
+        "zellij --session hop --layout /tmp/hop.kdl"
+    );
+}
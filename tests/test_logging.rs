@@ -0,0 +1,76 @@
+use bhop::args::Request;
+use bhop::configs::Configs;
+use bhop::logging;
+use bhop::store::MemoryStore;
+use bhop::Hopper;
+use serial_test::serial;
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn test_parse_verbosity() {
+    let request = Request::parse_from(["hp", "-v", "proj"]);
+    assert_eq!(
+        request,
+        Request::Verbose(1, Box::new(Request::Use("proj".to_string(), None)))
+    );
+    assert_eq!(request.verbosity(), 1);
+    assert_eq!(Request::parse_from(["hp", "proj", "-vv"]).verbosity(), 2);
+    assert_eq!(Request::parse_from(["hp", "proj"]).verbosity(), 0);
+}
+
+#[test]
+#[serial]
+fn test_log_file() {
+    let state = tempdir().unwrap();
+    let config = Configs {
+        log_file: true,
+        ..Default::default()
+    };
+    let mut hopper = Hopper::builder()
+        .config(config)
+        .config_dir(state.path())
+        .cwd(state.path())
+        .store(MemoryStore::new())
+        .env([("BHOP_STATE_DIRECTORY", state.path().display().to_string())])
+        .build()
+        .unwrap();
+    // Nothing goes to stderr without -v, but the log file still gets the resolution steps.
+    assert_eq!(logging::level(), 0);
+    assert!(hopper.resolve("nowhere").is_none());
+    let log = fs::read_to_string(state.path().join("bhop.log")).unwrap();
+    assert!(log.contains("`nowhere` has no shortcut match"));
+    assert!(!log.contains("sql:"));
+
+    // Commands run through the runners again carry the flag along.
+    let response = hopper
+        .respond(Request::parse_from(["hp", "ls", "-vv"]))
+        .unwrap();
+    assert!(response.ends_with("__bhop_list__ -vv"));
+    assert_eq!(logging::level(), 2);
+}
+
+#[test]
+#[serial]
+fn test_level_follows_the_latest_instance() {
+    let build = |verbose: bool, verbosity: u8| {
+        Hopper::builder()
+            .config(Configs {
+                verbose,
+                ..Default::default()
+            })
+            .store(MemoryStore::new())
+            .env([("TERM", "dumb")])
+            .verbosity(verbosity)
+            .build()
+            .unwrap()
+    };
+    build(true, 0);
+    assert_eq!(logging::level(), 1);
+    build(false, 0);
+    assert_eq!(logging::level(), 0);
+    build(true, 2);
+    assert_eq!(logging::level(), 2);
+    build(false, 0);
+    assert_eq!(logging::level(), 0);
+}
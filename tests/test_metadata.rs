@@ -2,6 +2,7 @@ use bhop::metadata::Environment;
 use serial_test::serial;
 use std::env;
 use std::path::PathBuf;
+use std::time::{Duration, Instant};
 use tempfile::tempdir;
 
 #[test]
@@ -77,3 +78,106 @@ fn test_environment_creates_database() {
     assert!(!shortcuts.is_empty());
     assert!(!history.is_empty());
 }
+
+#[test]
+#[serial]
+fn test_environment_migrates_database() {
+    let temp_dir = tempdir().unwrap();
+    env::set_var("BHOP_CONFIG_DIRECTORY", temp_dir.path());
+
+    let _environment = Environment::new();
+    let conn = sqlite::open(temp_dir.path().join("db").join("bhop.db")).unwrap();
+
+    conn.execute("INSERT INTO shortcuts (name, location, description) VALUES ('a', '/a', 'b')")
+        .unwrap();
+    conn.execute("INSERT INTO tags (name, tag) VALUES ('a', 'work')")
+        .unwrap();
+    let mut version = conn.prepare("PRAGMA user_version").unwrap();
+    assert_eq!(version.next().unwrap(), sqlite::State::Row);
+    assert_eq!(version.read::<i64, _>(0).unwrap(), 5);
+    drop(version);
+
+    assert_eq!(
+        columns(&conn, "shortcuts"),
+        vec![
+            "name",
+            "location",
+            "description",
+            "device",
+            "inode",
+            "remote",
+            "created_at"
+        ]
+    );
+    assert_eq!(columns(&conn, "tags"), vec!["name", "tag"]);
+    assert_eq!(
+        columns(&conn, "journal"),
+        vec![
+            "id",
+            "batch",
+            "operation",
+            "name",
+            "old_entry",
+            "new_entry",
+            "created_at"
+        ]
+    );
+    assert_eq!(
+        columns(&conn, "repo_index"),
+        vec!["location", "parent", "name", "mtime", "repo", "remote"]
+    );
+    assert_eq!(
+        columns(&conn, "visits"),
+        vec!["id", "location", "visited_at"]
+    );
+    assert_eq!(
+        columns(&conn, "shortcut_uses"),
+        vec!["id", "name", "used_at"]
+    );
+}
+
+// An up to date database is only read from, so setting up again doesn't wait on a writer.
+#[test]
+fn test_environment_setup_leaves_current_database_alone() {
+    let temp_dir = tempdir().unwrap();
+    let environment = Environment::setup(temp_dir.path());
+    let conn = Environment::connect(&environment.db_path).unwrap();
+    conn.execute("BEGIN IMMEDIATE").unwrap();
+
+    let started = Instant::now();
+    Environment::setup(temp_dir.path());
+    assert!(started.elapsed() < Duration::from_secs(2));
+    conn.execute("COMMIT").unwrap();
+}
+
+fn columns(conn: &sqlite::Connection, table: &str) -> Vec<String> {
+    let mut statement = conn
+        .prepare(format!("PRAGMA table_info({})", table))
+        .unwrap();
+    let mut columns = Vec::new();
+    while let Ok(sqlite::State::Row) = statement.next() {
+        columns.push(statement.read::<String, _>("name").unwrap());
+    }
+    columns
+}
+
+#[test]
+fn test_migration_leaves_created_at_unset() {
+    let temp_dir = tempdir().unwrap();
+    let db_path = temp_dir.path().join("bhop.db");
+    let conn = Environment::connect(&db_path).unwrap();
+    conn.execute(
+        "CREATE TABLE shortcuts (name TEXT PRIMARY KEY, location TEXT NOT NULL);
+        CREATE TABLE history (name TEXT NOT NULL, location TEXT NOT NULL,
+            usage INTEGER NOT NULL, PRIMARY KEY (name, location));
+        INSERT INTO shortcuts (name, location) VALUES ('old', '/srv/old');",
+    )
+    .unwrap();
+    Environment::migrate_database(&db_path).unwrap();
+
+    let mut statement = conn
+        .prepare("SELECT created_at FROM shortcuts WHERE name = 'old'")
+        .unwrap();
+    assert_eq!(statement.next().unwrap(), sqlite::State::Row);
+    assert_eq!(statement.read::<Option<i64>, _>(0).unwrap(), None);
+}
@@ -0,0 +1,53 @@
+use bhop::configs::Configs;
+use bhop::project::{project_reference, project_root};
+use bhop::store::MemoryStore;
+use bhop::Hopper;
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn project_reference_splits_off_root() {
+    assert_eq!(project_reference("/"), Some(""));
+    assert_eq!(project_reference("//"), Some(""));
+    assert_eq!(project_reference("//src/bin"), Some("src/bin"));
+    assert_eq!(project_reference("/usr/bin"), None);
+    assert_eq!(project_reference("src"), None);
+}
+
+#[test]
+fn project_root_finds_nearest_marker() {
+    let temp_dir = tempdir().unwrap();
+    let root = temp_dir.path().join("hop");
+    let nested = root.join("crates").join("core");
+    fs::create_dir_all(root.join(".git")).unwrap();
+    fs::create_dir_all(nested.join("src")).unwrap();
+    fs::write(nested.join("Cargo.toml"), "").unwrap();
+
+    assert_eq!(
+        project_root(nested.join("src"), &[".git", "Cargo.toml"]),
+        Some(nested.clone())
+    );
+    assert_eq!(project_root(nested.join("src"), &[".git"]), Some(root));
+    assert_eq!(project_root(temp_dir.path(), &["no-such-marker"]), None);
+}
+
+#[test]
+fn project_root_with_glob_characters() {
+    let temp_dir = tempdir().unwrap();
+    let root = temp_dir.path().join("hop [wip]");
+    fs::create_dir_all(root.join(".git")).unwrap();
+    fs::create_dir_all(root.join("src").join("bin")).unwrap();
+    let hopper = Hopper::builder()
+        .config(Configs {
+            project_markers: vec![".git".to_string()],
+            ..Default::default()
+        })
+        .store(MemoryStore::new())
+        .cwd(root.join("src"))
+        .env([("TERM", "dumb")])
+        .build()
+        .unwrap();
+
+    let found = hopper.resolve("//src/b*").unwrap();
+    assert_eq!(found.path, root.join("src").join("bin"));
+}
@@ -0,0 +1,45 @@
+use bhop::args::Request;
+use bhop::configs::Configs;
+use bhop::store::MemoryStore;
+use bhop::Hopper;
+use std::fs;
+use tempfile::tempdir;
+
+fn repo_names(hopper: &Hopper) -> Vec<String> {
+    let mut names: Vec<String> = hopper
+        .store
+        .repos()
+        .unwrap()
+        .into_iter()
+        .map(|dir| dir.name)
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn repos_are_indexed_and_refreshed() {
+    let temp_dir = tempdir().unwrap();
+    let root = temp_dir.path().join("src");
+    fs::create_dir_all(root.join("hop").join(".git")).unwrap();
+    fs::create_dir_all(root.join("work").join("api").join(".git")).unwrap();
+    fs::create_dir_all(root.join("hop").join("vendored").join(".git")).unwrap();
+    fs::create_dir_all(root.join("a").join("b").join("c").join("deep").join(".git")).unwrap();
+    let mut hopper = Hopper::builder()
+        .config(Configs {
+            repo_roots: vec![root.clone()],
+            repo_max_depth: 3,
+            ..Default::default()
+        })
+        .store(MemoryStore::new())
+        .build()
+        .unwrap();
+
+    hopper.execute(Request::Repos(true)).unwrap();
+    assert_eq!(repo_names(&hopper), vec!["api", "hop"]);
+
+    fs::create_dir_all(root.join("work").join("web").join(".git")).unwrap();
+    fs::remove_dir_all(root.join("hop")).unwrap();
+    hopper.execute(Request::Repos(true)).unwrap();
+    assert_eq!(repo_names(&hopper), vec!["api", "web"]);
+}
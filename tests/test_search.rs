@@ -0,0 +1,110 @@
+use bhop::args::Request;
+use bhop::configs::Configs;
+use bhop::search::{fuzzy_match, Matcher, SearchMode};
+use bhop::store::MemoryStore;
+use bhop::theme::{paint_matched, ColorDepth, Style};
+use bhop::{Hopper, ListOptions};
+use tempfile::tempdir;
+
+fn chars(pattern: &str) -> Vec<char> {
+    pattern.chars().collect()
+}
+
+#[test]
+fn test_matchers() {
+    let glob = Matcher::new(Some("hop"), SearchMode::Glob, "*", "*").unwrap();
+    assert!(glob.matches("/home/you/Bhop").is_some());
+    let glob = Matcher::new(Some("Hop"), SearchMode::Glob, "*", "*").unwrap();
+    assert!(glob.matches("/home/you/bhop").is_none());
+    assert!(glob.matches("/home/you/bHop").is_some());
+
+    let regex = Matcher::new(Some("^(hop|src)$"), SearchMode::Regex, "*", "*").unwrap();
+    assert!(regex.matches("SRC").is_some());
+    assert!(regex.matches("bhop").is_none());
+    assert!(Matcher::new(Some("(hop"), SearchMode::Regex, "*", "*").is_err());
+
+    assert_eq!(
+        fuzzy_match(&chars("hc"), "hop-conf", false),
+        Some((16 + 20 + 16 + 20 - 3, vec![0, 4]))
+    );
+    assert_eq!(fuzzy_match(&chars("hx"), "hop-conf", false), None);
+    // The tight match at the end beats the scattered one from the first `h`.
+    let (_, matched) = fuzzy_match(&chars("hop"), "/home/shop/hop", false).unwrap();
+    assert_eq!(matched, vec![11, 12, 13]);
+    assert!(fuzzy_match(&chars("HC"), "hop-conf", true).is_none());
+}
+
+#[test]
+fn test_paint_matched() {
+    let style: Style = "bold".parse().unwrap();
+    let highlight: Style = "underline".parse().unwrap();
+    assert_eq!(
+        paint_matched("hop", &[1], style, highlight, Some(ColorDepth::Ansi16)),
+        "\x1b[1mh\x1b[0m\x1b[4mo\x1b[0m\x1b[1mp\x1b[0m"
+    );
+    assert_eq!(paint_matched("hop", &[1], style, highlight, None), "hop");
+}
+
+#[test]
+fn test_fuzzy_search() {
+    let dir = tempdir().unwrap();
+    let mut hopper = Hopper::builder()
+        .config(Configs::default())
+        .store(MemoryStore::new())
+        .config_dir(dir.path())
+        .build()
+        .unwrap();
+    for (name, location) in [
+        ("hop-conf", "/srv/bhop/bhop.toml"),
+        ("hopper", "/srv/hopper"),
+        ("shop", "/srv/shop"),
+        ("infra", "/srv/deploy"),
+    ] {
+        hopper
+            .execute(Request::Add(location.to_string(), Some(name.to_string())))
+            .unwrap();
+    }
+    hopper
+        .execute(Request::Tag("infra".to_string(), vec!["Ops".to_string()]))
+        .unwrap();
+
+    let options = ListOptions {
+        mode: SearchMode::Fuzzy,
+        ..Default::default()
+    };
+    let names = |filter: &str, options: &ListOptions| {
+        hopper
+            .search(Some(filter), options)
+            .unwrap()
+            .into_iter()
+            .map(|entry| (entry.name, entry.matched))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        names("hop", &options),
+        vec![
+            ("hop-conf".to_string(), vec![0, 1, 2]),
+            ("hopper".to_string(), vec![0, 1, 2]),
+            ("shop".to_string(), vec![1, 2, 3]),
+        ]
+    );
+    // Only names are highlighted, though locations are searched as well.
+    assert_eq!(
+        names("dpl", &options),
+        vec![("infra".to_string(), Vec::new())]
+    );
+    // Tags are searched too, ignoring case unless the filter has capitals in it.
+    assert_eq!(
+        names("ops", &options),
+        vec![("infra".to_string(), Vec::new())]
+    );
+    assert!(names("OPS", &options).is_empty());
+    assert_eq!(
+        names("OPS", &ListOptions::default()),
+        Vec::<(String, Vec<usize>)>::new()
+    );
+    assert_eq!(
+        names("ops", &ListOptions::default()),
+        vec![("infra".to_string(), Vec::new())]
+    );
+}
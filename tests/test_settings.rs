@@ -0,0 +1,147 @@
+use bhop::args::Request;
+use bhop::settings::{parse_value, set_value, split_key};
+use bhop::store::MemoryStore;
+use bhop::Hopper;
+use std::fs;
+use tempfile::tempdir;
+
+const TOML: &str = r#"[settings]
+# Open files in their directory.
+always_jump = false # jump first
+
+# repo_roots=["~/src"]
+project_markers=[
+    ".git",
+    "Cargo.toml",
+]
+
+[editors]
+# py="code"
+"#;
+
+#[test]
+fn test_set_value_in_place() {
+    let value = toml::Value::Boolean(true);
+    let updated = set_value(TOML, "settings", "always_jump", &value).unwrap();
+    assert!(updated.contains("# Open files in their directory.\nalways_jump = true # jump first\n"));
+
+    // Arrays spanning several lines are replaced as a whole.
+    let value = parse_value("settings", "project_markers", r#"[".hg"]"#).unwrap();
+    let updated = set_value(TOML, "settings", "project_markers", &value).unwrap();
+    assert!(updated.contains("project_markers=[\".hg\"]\n\n[editors]"));
+    assert!(!updated.contains("Cargo.toml"));
+
+    // New keys go after their commented out example, or after the table's last assignment.
+    let value = parse_value("settings", "repo_roots", r#"["~/work"]"#).unwrap();
+    let updated = set_value(TOML, "settings", "repo_roots", &value).unwrap();
+    assert!(updated.contains("# repo_roots=[\"~/src\"]\nrepo_roots=[\"~/work\"]\n"));
+    let value = toml::Value::Integer(2);
+    let updated = set_value(TOML, "settings", "repo_max_depth", &value).unwrap();
+    assert!(updated.contains("]\nrepo_max_depth=2\n\n[editors]"));
+    let updated = set_value("", "settings", "repo_max_depth", &value).unwrap();
+    assert_eq!(updated, "[settings]\nrepo_max_depth=2\n");
+}
+
+// The settings table of `toml`, checking the key is only assigned once along the way.
+fn settings(toml: &str) -> toml::value::Table {
+    let mut document: toml::value::Table = toml::from_str(toml).unwrap();
+    match document.remove("settings") {
+        Some(toml::Value::Table(table)) => table,
+        _ => panic!("no settings in {}", toml),
+    }
+}
+
+#[test]
+fn test_set_value_in_other_shapes() {
+    let value = toml::Value::Boolean(true);
+
+    // A `[` inside of an array spanning several lines isn't a table header.
+    let toml = "[settings]\nproject_markers = [\n  \".git\",\n  \"[x]\",\n]\nalways_jump = false\n";
+    let updated = set_value(toml, "settings", "always_jump", &value).unwrap();
+    assert!(updated.contains("  \"[x]\",\n]\nalways_jump = true\n"));
+    assert_eq!(settings(&updated)["always_jump"], value);
+
+    let toml = "[settings]\n\"always_jump\" = false # quoted\n";
+    let updated = set_value(toml, "settings", "always_jump", &value).unwrap();
+    assert_eq!(updated, "[settings]\n\"always_jump\" = true # quoted\n");
+
+    let toml = "settings.always_jump = false\n# settings.verbose = false\nsettings.color = \"auto\"\n\n[editors]\n";
+    let updated = set_value(toml, "settings", "always_jump", &value).unwrap();
+    assert!(updated.starts_with("settings.always_jump = true\n"));
+    let updated = set_value(toml, "settings", "verbose", &value).unwrap();
+    assert!(updated.contains("# settings.verbose = false\nsettings.verbose = true\n"));
+    assert_eq!(settings(&updated)["verbose"], value);
+
+    let toml = "settings = { always_jump = false }\n";
+    let updated = set_value(toml, "settings", "always_jump", &value).unwrap();
+    assert_eq!(updated, "settings = { always_jump = true }\n");
+    let updated = set_value(toml, "settings", "verbose", &value).unwrap();
+    assert_eq!(
+        updated,
+        "settings = { always_jump = false, verbose = true }\n"
+    );
+
+    // Comments after the last table stay above a new one, and line endings are kept.
+    let updated = set_value("[editors]\r\n# last\r\n", "settings", "verbose", &value).unwrap();
+    assert_eq!(
+        updated,
+        "[editors]\r\n# last\r\n\r\n[settings]\r\nverbose=true\r\n"
+    );
+
+    assert!(set_value("settings = 1\n", "settings", "verbose", &value).is_err());
+}
+
+#[test]
+fn test_parse_and_validate_values() {
+    assert_eq!(
+        split_key("always_jump").unwrap(),
+        ("settings", "always_jump")
+    );
+    assert_eq!(split_key("editors.py").unwrap(), ("editors", "py"));
+    assert!(split_key("settings.nope").is_err());
+    assert!(split_key("editor_rules.x").is_err());
+
+    assert_eq!(
+        parse_value("settings", "always_jump", "true").unwrap(),
+        toml::Value::Boolean(true)
+    );
+    assert!(parse_value("settings", "always_jump", "yes").is_err());
+    assert!(parse_value("settings", "repo_max_depth", "-1").is_err());
+    // Anything that isn't valid for a string setting is taken as the string itself.
+    assert_eq!(
+        parse_value("editors", "py", "code -w").unwrap(),
+        toml::Value::String("code -w".to_string())
+    );
+    assert_eq!(
+        parse_value("settings", "default_editor", "true").unwrap(),
+        toml::Value::String("true".to_string())
+    );
+}
+
+#[test]
+fn test_config_commands() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("bhop.toml"), TOML).unwrap();
+    let mut hopper = Hopper::builder()
+        .config_dir(dir.path())
+        .store(MemoryStore::new())
+        .env([("EDITOR", "nano")])
+        .build()
+        .unwrap();
+    let mut run = |args: &[&str]| {
+        let args = ["hp"].iter().chain(args.iter()).copied();
+        hopper.respond(Request::parse_from(args))
+    };
+
+    assert_eq!(run(&["config", "get", "always_jump"]).unwrap(), "false");
+    run(&["config", "set", "settings.always_jump", "true"]).unwrap();
+    assert_eq!(run(&["config", "get", "always_jump"]).unwrap(), "true");
+    run(&["config", "set", "editors.py", "code", "-w"]).unwrap();
+    assert_eq!(run(&["config", "get", "editors.py"]).unwrap(), "code -w");
+    assert!(run(&["config", "set", "verbose", "loud"]).is_err());
+
+    let written = fs::read_to_string(dir.path().join("bhop.toml")).unwrap();
+    assert!(written.contains("always_jump = true # jump first"));
+    assert!(written.contains("# py=\"code\"\npy=\"code -w\"\n"));
+    assert!(!written.contains("verbose"));
+}
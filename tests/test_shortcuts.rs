@@ -0,0 +1,56 @@
+use bhop::shortcuts::{diff, Change, ShortcutEntry, ShortcutFile};
+use std::collections::BTreeMap;
+
+fn entry(location: &str) -> ShortcutEntry {
+    ShortcutEntry {
+        location: location.to_string(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_shortcut_file_round_trip() {
+    let mut shortcuts = BTreeMap::new();
+    shortcuts.insert(
+        "hop".to_string(),
+        ShortcutEntry {
+            location: "/home/you/hop".to_string(),
+            description: Some("bunnyhop".to_string()),
+            tags: vec!["rust".to_string(), "work".to_string()],
+        },
+    );
+    shortcuts.insert("my.notes".to_string(), entry("/home/you/notes"));
+    let file = ShortcutFile { shortcuts };
+    let toml = file.to_toml().unwrap();
+    assert_eq!(ShortcutFile::from_toml(&toml).unwrap(), file);
+}
+
+#[test]
+fn test_shortcut_file_requires_location() {
+    let toml = r#"
+    [shortcuts.hop]
+    description = "no location"
+    "#;
+    assert!(ShortcutFile::from_toml(toml).is_err());
+}
+
+#[test]
+fn test_diff_shortcuts() {
+    let mut original = BTreeMap::new();
+    original.insert("old".to_string(), entry("/a"));
+    original.insert("same".to_string(), entry("/b"));
+    original.insert("moved".to_string(), entry("/c"));
+    let mut edited = BTreeMap::new();
+    edited.insert("new".to_string(), entry("/a"));
+    edited.insert("same".to_string(), entry("/b"));
+    edited.insert("moved".to_string(), entry("/d"));
+
+    assert_eq!(
+        diff(&original, &edited),
+        vec![
+            Change::Update("moved".to_string(), entry("/d")),
+            Change::Add("new".to_string(), entry("/a")),
+            Change::Remove("old".to_string()),
+        ]
+    );
+}
@@ -0,0 +1,192 @@
+use bhop::args::Request;
+use bhop::configs::Configs;
+use bhop::metadata::Environment;
+use bhop::store::SqliteStore;
+use bhop::Hopper;
+use std::fs;
+use std::path::Path;
+use tempfile::tempdir;
+
+const DAY: i64 = 24 * 60 * 60;
+
+fn sqlite_hopper(config_dir: &Path) -> (Hopper, sqlite::Connection) {
+    let env = Environment::setup(config_dir);
+    let hopper = Hopper::builder()
+        .config(Configs::default())
+        .store(SqliteStore::open(&env.db_path).unwrap())
+        .config_dir(config_dir)
+        .build()
+        .unwrap();
+    (hopper, Environment::connect(&env.db_path).unwrap())
+}
+
+fn notes(rows: &[[String; 3]]) -> Vec<[&str; 2]> {
+    rows.iter()
+        .map(|[name, _, note]| [name.as_str(), note.as_str()])
+        .collect()
+}
+
+#[test]
+fn test_stats_report() {
+    let temp_dir = tempdir().unwrap();
+    let (mut hopper, conn) = sqlite_hopper(&temp_dir.path().join("bhop"));
+    let now = chrono::Utc::now().timestamp();
+    for name in ["a", "b", "c", "d", "e", "f"] {
+        let dir = temp_dir.path().join(name);
+        fs::create_dir_all(&dir).unwrap();
+        hopper
+            .execute(Request::Add(
+                dir.display().to_string(),
+                Some(name.to_string()),
+            ))
+            .unwrap();
+    }
+    // `d` was added before shortcuts had a creation time.
+    conn.execute(format!(
+        "UPDATE shortcuts SET created_at = {} WHERE name IN ('a', 'b', 'c', 'f');
+        UPDATE shortcuts SET created_at = NULL WHERE name = 'd';",
+        now - 60 * DAY
+    ))
+    .unwrap();
+    for (name, ago) in [("a", 0), ("a", 1), ("a", 2), ("b", 2), ("f", 40)] {
+        hopper
+            .store
+            .record_shortcut_use(name, now - ago * DAY)
+            .unwrap();
+    }
+    for (location, ago) in [
+        ("/srv/hop", 0),
+        ("/srv/hop", 2),
+        ("/srv/web", 3),
+        ("/srv/web", 8),
+        ("/srv/old", 22),
+    ] {
+        let name = location.rsplit('/').next().unwrap();
+        hopper
+            .store
+            .record_visit(name, location, now - ago * DAY - 60)
+            .unwrap();
+    }
+
+    let report = hopper.stats_report(30, now).unwrap();
+    let titles: Vec<&str> = report.iter().map(|(title, _)| title.as_str()).collect();
+    assert_eq!(
+        titles,
+        vec![
+            "Most used shortcuts:",
+            "Busiest directories this week:",
+            "Busiest directories last week:",
+            "Busiest directories 2 weeks ago:",
+            "Busiest directories 3 weeks ago:",
+            "Shortcuts unused for over 30 days:",
+        ]
+    );
+    assert_eq!(
+        notes(&report[0].1),
+        vec![
+            ["a", "3 uses, last just now"],
+            ["b", "1 use, last 2 days ago"],
+            ["f", "1 use, last 40 days ago"],
+        ]
+    );
+    assert_eq!(
+        notes(&report[1].1),
+        vec![["hop", "2 visits"], ["web", "1 visit"]]
+    );
+    assert_eq!(report[1].1[0][1], "/srv/hop");
+    assert_eq!(notes(&report[2].1), vec![["web", "1 visit"]]);
+    assert!(report[3].1.is_empty());
+    assert_eq!(notes(&report[4].1), vec![["old", "1 visit"]]);
+    // `e` was only just added, and `a` and `b` were used recently.
+    assert_eq!(
+        notes(&report[5].1),
+        vec![
+            ["c", "never used, added 60 days ago"],
+            ["d", "never used"],
+            ["f", "last used 40 days ago"],
+        ]
+    );
+}
+
+#[test]
+fn test_stats_weeks_do_not_overlap() {
+    let temp_dir = tempdir().unwrap();
+    let (mut hopper, _conn) = sqlite_hopper(&temp_dir.path().join("bhop"));
+    let now = chrono::Utc::now().timestamp();
+    for (location, at) in [
+        ("/srv/now", now),
+        ("/srv/edge", now - 7 * DAY),
+        ("/srv/older", now - 14 * DAY),
+    ] {
+        let name = location.rsplit('/').next().unwrap();
+        hopper.store.record_visit(name, location, at).unwrap();
+    }
+
+    let report = hopper.stats_report(30, now).unwrap();
+    assert_eq!(
+        notes(&report[1].1),
+        vec![["edge", "1 visit"], ["now", "1 visit"]]
+    );
+    assert_eq!(notes(&report[2].1), vec![["older", "1 visit"]]);
+    assert!(report[3].1.is_empty());
+}
+
+#[test]
+fn test_history_rows() {
+    let temp_dir = tempdir().unwrap();
+    let (mut hopper, _) = sqlite_hopper(&temp_dir.path().join("bhop"));
+    let now = chrono::Utc::now().timestamp();
+    for (name, location, ago) in [
+        ("hop", "/srv/hop", 3),
+        ("hop", "/srv/hop", 0),
+        ("src", "/srv/hop/src", 9),
+        ("web", "/srv/web", 1),
+    ] {
+        hopper
+            .store
+            .record_visit(name, location, now - ago * DAY)
+            .unwrap();
+    }
+
+    let mut rows = hopper.history_rows(None, now).unwrap();
+    rows.sort();
+    assert_eq!(
+        rows,
+        vec![
+            [
+                "hop".to_string(),
+                "/srv/hop".to_string(),
+                "2 visits, last just now".to_string()
+            ],
+            [
+                "src".to_string(),
+                "/srv/hop/src".to_string(),
+                "1 visit, last 9 days ago".to_string()
+            ],
+            [
+                "web".to_string(),
+                "/srv/web".to_string(),
+                "1 visit, last 1 days ago".to_string()
+            ],
+        ]
+    );
+    // Filters match the location as well as the name.
+    let mut names: Vec<String> = hopper
+        .history_rows(Some("hop"), now)
+        .unwrap()
+        .into_iter()
+        .map(|[name, _, _]| name)
+        .collect();
+    names.sort();
+    assert_eq!(names, vec!["hop", "src"]);
+    assert!(hopper
+        .history_rows(Some("nothing"), now)
+        .unwrap()
+        .is_empty());
+    assert_eq!(
+        hopper
+            .respond(Request::History(Some("nothing".to_string())))
+            .unwrap(),
+        "No history found."
+    );
+}
@@ -0,0 +1,225 @@
+use bhop::api::Source;
+use bhop::args::Request;
+use bhop::configs::Configs;
+use bhop::history::HistoryRules;
+use bhop::identity::Identity;
+use bhop::metadata::Environment;
+use bhop::shortcuts::ShortcutEntry;
+use bhop::store::{JournalChange, MemoryStore, ShortcutRecord, SqliteStore, Store};
+use bhop::{Hopper, ListOptions};
+use tempfile::tempdir;
+
+// Nothing here touches the environment or the disk, so these can run alongside each other.
+fn memory_hopper() -> Hopper {
+    let config = Configs {
+        history_rules: HistoryRules {
+            exclude: Vec::new(),
+            min_depth: 0,
+        },
+        ..Default::default()
+    };
+    Hopper::builder()
+        .config(config)
+        .store(MemoryStore::new())
+        .build()
+        .unwrap()
+}
+
+fn entry(location: &str) -> ShortcutEntry {
+    ShortcutEntry {
+        location: location.to_string(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn memory_store_shortcuts_and_transactions() {
+    let mut store = MemoryStore::new();
+    store.write_shortcut("a", &entry("/a")).unwrap();
+    store.rename_shortcut("a", "b").unwrap();
+    assert_eq!(store.shortcut("a").unwrap(), None);
+    assert_eq!(store.shortcut("b").unwrap(), Some(entry("/a")));
+
+    store.begin().unwrap();
+    store.delete_shortcut("b").unwrap();
+    store.rollback().unwrap();
+    assert!(store.shortcut("b").unwrap().is_some());
+
+    store.record_visit("src", "/x/src", 100).unwrap();
+    store.record_visit("src", "/x/src", 100).unwrap();
+    assert_eq!(store.history(100).unwrap()[0].score, 8.0);
+    store.move_history("/x/src", "code", "/y/code").unwrap();
+    let history = store.history(100).unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].location, "/y/code");
+    assert_eq!(history[0].usage, 2);
+}
+
+#[test]
+fn memory_store_journal() {
+    let mut store = MemoryStore::new();
+    for i in 0..3 {
+        let change = JournalChange {
+            name: format!("s{}", i),
+            old_entry: None,
+            new_entry: Some(ShortcutRecord {
+                entry: entry("/s"),
+                ..Default::default()
+            }),
+        };
+        store.push_journal("add", &[change], 2).unwrap();
+    }
+    let last = store.last_journal().unwrap().unwrap();
+    assert_eq!(last.changes[0].name, "s2");
+    store.drop_journal(last.batch).unwrap();
+    assert_eq!(store.last_journal().unwrap().unwrap().changes[0].name, "s1");
+    store.drop_journal(2).unwrap();
+    // Only the last two batches were kept.
+    assert_eq!(store.last_journal().unwrap(), None);
+}
+
+#[test]
+fn hopper_with_memory_store() {
+    let dir = tempdir().unwrap();
+    let location = dir.path().display().to_string();
+    let mut hopper = memory_hopper();
+    hopper
+        .execute(Request::Add(location.clone(), Some("proj".to_string())))
+        .unwrap();
+    hopper
+        .execute(Request::Tag("proj".to_string(), vec!["work".to_string()]))
+        .unwrap();
+    assert_eq!(
+        hopper.store.shortcut("proj").unwrap().unwrap().tags,
+        vec!["work".to_string()]
+    );
+    hopper.execute(Request::Undo).unwrap();
+    assert!(hopper
+        .store
+        .shortcut("proj")
+        .unwrap()
+        .unwrap()
+        .tags
+        .is_empty());
+
+    assert_eq!(hopper.resolve("proj").unwrap().source, Source::Shortcut);
+    hopper.action("proj", false).unwrap();
+    hopper.execute(Request::Record(location.clone())).unwrap();
+    let history = hopper.store.history(0).unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].location, location);
+
+    hopper.execute(Request::Undo).unwrap();
+    assert_eq!(hopper.store.shortcut("proj").unwrap(), None);
+}
+
+#[test]
+fn sqlite_store_binds_tags() {
+    let temp_dir = tempdir().unwrap();
+    let env = Environment::setup(temp_dir.path().join("bhop"));
+    let mut hopper = Hopper::builder()
+        .config(Configs::default())
+        .store(SqliteStore::open(&env.db_path).unwrap())
+        .config_dir(temp_dir.path().join("bhop"))
+        .build()
+        .unwrap();
+    let tags = vec![
+        "o'brien".to_string(),
+        "x'); DROP TABLE tags; --".to_string(),
+    ];
+    hopper
+        .execute(Request::Add(
+            temp_dir.path().display().to_string(),
+            Some("it's".to_string()),
+        ))
+        .unwrap();
+    hopper
+        .execute(Request::Tag("it's".to_string(), tags.clone()))
+        .unwrap();
+
+    let mut stored = hopper.store.shortcut("it's").unwrap().unwrap().tags;
+    stored.sort();
+    assert_eq!(stored, tags);
+    let options = ListOptions {
+        tag: Some("o'brien".to_string()),
+        ..Default::default()
+    };
+    let entries = hopper.search(None, &options).unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].name, "it's");
+}
+
+#[test]
+fn undo_restores_the_whole_row() {
+    let temp_dir = tempdir().unwrap();
+    let env = Environment::setup(temp_dir.path().join("bhop"));
+    let mut hopper = Hopper::builder()
+        .config(Configs::default())
+        .store(SqliteStore::open(&env.db_path).unwrap())
+        .config_dir(temp_dir.path().join("bhop"))
+        .build()
+        .unwrap();
+    let record = ShortcutRecord {
+        entry: ShortcutEntry {
+            location: temp_dir.path().display().to_string(),
+            description: Some("it's old".to_string()),
+            tags: vec!["work".to_string()],
+        },
+        identity: Identity {
+            device: Some(1),
+            inode: Some(2),
+            remote: Some("git@example.com:you/hop.git".to_string()),
+        },
+        created_at: Some(1_000),
+    };
+    hopper.store.restore_shortcut("old", &record).unwrap();
+    assert_eq!(
+        hopper.store.shortcut_record("old").unwrap(),
+        Some(record.clone())
+    );
+
+    hopper
+        .execute(Request::Confirmed(Box::new(Request::Remove(
+            "old".to_string(),
+        ))))
+        .unwrap();
+    assert_eq!(hopper.store.shortcut_record("old").unwrap(), None);
+    hopper.execute(Request::Undo).unwrap();
+    assert_eq!(hopper.store.shortcut_record("old").unwrap(), Some(record));
+}
+
+#[test]
+fn shortcut_records_read_older_journal_entries() {
+    let record: ShortcutRecord = toml::from_str("location = \"/a\"\ntags = [\"work\"]\n").unwrap();
+    assert_eq!(
+        record,
+        ShortcutRecord {
+            entry: ShortcutEntry {
+                location: "/a".to_string(),
+                description: None,
+                tags: vec!["work".to_string()],
+            },
+            ..Default::default()
+        }
+    );
+}
+
+// A moved directory keeps its recent visits, and with them its score and place in the stats.
+fn moves_visits(store: &mut dyn Store) {
+    store.record_visit("src", "/x/src", 100).unwrap();
+    store.record_visit("src", "/x/src", 100).unwrap();
+    let score = store.history(100).unwrap()[0].score;
+    store.move_history("/x/src", "code", "/y/code").unwrap();
+    assert_eq!(store.history(100).unwrap()[0].score, score);
+    let counts = store.visit_counts(0, 101).unwrap();
+    assert_eq!(counts.get("/y/code"), Some(&2));
+    assert!(!counts.contains_key("/x/src"));
+}
+
+#[test]
+fn moved_history_keeps_its_visits() {
+    moves_visits(&mut MemoryStore::new());
+    let dir = tempdir().unwrap();
+    let env = Environment::setup(dir.path());
+    moves_visits(&mut SqliteStore::open(&env.db_path).unwrap());
+}
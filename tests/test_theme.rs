@@ -0,0 +1,128 @@
+use bhop::args::Request;
+use bhop::configs::{Configs, ReadConfig};
+use bhop::store::MemoryStore;
+use bhop::theme::{fit, fit_row, ColorDepth, ColorMode, Style};
+use bhop::Hopper;
+
+#[test]
+fn test_styles() {
+    let style: Style = "bold #33ffff".parse().unwrap();
+    assert_eq!(style.to_string(), "bold #33ffff");
+    assert_eq!(
+        style.paint("hop", Some(ColorDepth::TrueColor)),
+        "\x1b[1;38;2;51;255;255mhop\x1b[0m"
+    );
+    assert_eq!(
+        style.paint("hop", Some(ColorDepth::Ansi256)),
+        "\x1b[1;38;5;87mhop\x1b[0m"
+    );
+    assert_eq!(
+        style.paint("hop", Some(ColorDepth::Ansi16)),
+        "\x1b[1;96mhop\x1b[0m"
+    );
+    assert_eq!(style.paint("hop", None), "hop");
+
+    let style: Style = "dim bright_red".parse().unwrap();
+    assert_eq!(
+        style.paint("hop", Some(ColorDepth::TrueColor)),
+        "\x1b[2;91mhop\x1b[0m"
+    );
+    let style: Style = "italic 208".parse().unwrap();
+    assert_eq!(
+        style.paint("hop", Some(ColorDepth::Ansi256)),
+        "\x1b[3;38;5;208mhop\x1b[0m"
+    );
+    assert!("sparkly".parse::<Style>().is_err());
+    assert!("#12345g".parse::<Style>().is_err());
+}
+
+#[test]
+fn test_theme_from_config() {
+    let read: ReadConfig = toml::from_str(
+        "[settings]\ncolor = \"never\"\nprint_color_secondary = [1, 2, 3]\n\
+         [theme]\nname = \"underline magenta\"\n",
+    )
+    .unwrap();
+    let config = Configs::from_read(read);
+    assert_eq!(config.color, ColorMode::Never);
+    assert_eq!(config.theme.name.to_string(), "underline magenta");
+    assert_eq!(config.theme.location.to_string(), "bold #010203");
+    assert!(toml::from_str::<ReadConfig>("[theme]\narrow = \"blinking\"\n").is_err());
+    assert!(toml::from_str::<ReadConfig>("[settings]\ncolor = \"maybe\"\n").is_err());
+
+    assert_eq!(
+        Request::parse_from(["hp", "ls", "--color=never"]),
+        Request::Color(
+            ColorMode::Never,
+            Box::new(Request::Passthrough("__bhop_list__".to_string()))
+        )
+    );
+}
+
+fn colored_hopper(color: ColorMode) -> Hopper {
+    Hopper::builder()
+        .config(Configs {
+            color,
+            ..Configs::default()
+        })
+        .store(MemoryStore::new())
+        .env([("TERM", "xterm-256color")])
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn test_color_follows_the_instance() {
+    let mut always = colored_hopper(ColorMode::Always);
+    let mut never = colored_hopper(ColorMode::Never);
+    let which = || Request::WhichEditor("notes.md".to_string());
+    assert!(always
+        .respond(which())
+        .unwrap()
+        .starts_with("\x1b[1;97meditor:\x1b[0m "));
+    assert!(never.respond(which()).unwrap().starts_with("editor: "));
+    // Building the second instance didn't turn colors off for the first.
+    assert!(always
+        .respond(Request::Version)
+        .unwrap()
+        .starts_with("\x1b[1;36mBhop"));
+
+    // On auto, only a terminal gets colors, which the runners say is there with `BHOP_TERMINAL`.
+    let mut auto = Hopper::builder()
+        .config(Configs::default())
+        .store(MemoryStore::new())
+        .env([("TERM", "xterm-256color"), ("BHOP_TERMINAL", "1")])
+        .build()
+        .unwrap();
+    assert!(auto
+        .respond(which())
+        .unwrap()
+        .starts_with("\x1b[1;97meditor:"));
+
+    let version = always
+        .respond(Request::Color(ColorMode::Never, Box::new(Request::Version)))
+        .unwrap();
+    assert!(version.starts_with("Bhop 🐇 v."));
+}
+
+#[test]
+fn test_fit_to_width() {
+    let home = Some("/home/foo");
+    assert_eq!(fit("/home/foo/src/hop", 20, home), "/home/foo/src/hop");
+    assert_eq!(fit("/home/foo/src/hop/src", 20, home), "~/src/hop/src");
+    assert_eq!(fit("/home/foobar/src", 10, home), "/ho…ar/src");
+    assert_eq!(
+        fit("/srv/projects/hop/src/defaults", 16, None),
+        "/srv/…c/defaults"
+    );
+
+    // Notes give way before the location does.
+    let (location, notes) = fit_row("/srv/hop", "[rust] the hop source tree", 25, None);
+    assert_eq!(
+        (location.as_str(), notes.as_str()),
+        ("/srv/hop", "[rust] the hop …")
+    );
+    let (location, notes) = fit_row("/srv/projects/hop/src/defaults", "[rust]", 24, None);
+    assert_eq!(location.chars().count(), 24);
+    assert_eq!(notes, "");
+}
@@ -0,0 +1,143 @@
+use bhop::api::{Entry, Source};
+use bhop::args::Request;
+use bhop::configs::Configs;
+use bhop::search::SearchMode;
+use bhop::views::{by_tag, rows, sort_entries, tree_lines, ListView, SortBy};
+use bhop::ListOptions;
+use std::fs;
+use tempfile::tempdir;
+
+fn entry(name: &str, location: &str, usage: i64, last_used: Option<i64>) -> Entry {
+    Entry {
+        name: name.to_string(),
+        location: location.to_string(),
+        source: Source::Shortcut,
+        tags: Vec::new(),
+        description: None,
+        usage,
+        last_used,
+        matched: Vec::new(),
+    }
+}
+
+#[test]
+fn test_tree() {
+    let dir = tempdir().unwrap();
+    let root = dir.path().display().to_string().replace('\\', "/");
+    fs::create_dir_all(dir.path().join("projects/hop/src")).unwrap();
+    fs::create_dir_all(dir.path().join("projects/other")).unwrap();
+    fs::write(dir.path().join("projects/hop/README.md"), "").unwrap();
+    let entries = vec![
+        entry("hop", &format!("{}/projects/hop", root), 0, None),
+        entry("src", &format!("{}/projects/hop/src", root), 0, None),
+        entry(
+            "readme",
+            &format!("{}/projects/hop/README.md", root),
+            0,
+            None,
+        ),
+        entry("other", &format!("{}/projects/other", root), 0, None),
+        entry("gone", &format!("{}/projects/gone", root), 0, None),
+    ];
+    let lines = tree_lines(&entries, &Configs::default().theme, None);
+    assert_eq!(
+        lines,
+        vec![
+            format!("{}/projects/", root),
+            "├── gone/ <- gone (missing)".to_string(),
+            "├── hop/ <- hop".to_string(),
+            "│   ├── README.md <- readme".to_string(),
+            "│   └── src/ <- src".to_string(),
+            "└── other/ <- other".to_string(),
+        ]
+    );
+
+    let table = rows(entries);
+    assert_eq!(table[0].0[1], format!("{}/projects/hop/", root));
+    assert_eq!(table[2].0[1], format!("{}/projects/hop/README.md", root));
+    assert_eq!(table[4].0[2], "(missing)");
+}
+
+#[test]
+fn test_sort_and_group() {
+    let mut entries = vec![
+        entry("b", "/srv/b", 3, Some(10)),
+        entry("a", "/srv/c", 3, None),
+        entry("c", "/srv/a", 7, Some(20)),
+    ];
+    let names = |entries: &[Entry]| entries.iter().map(|e| e.name.clone()).collect::<Vec<_>>();
+    sort_entries(&mut entries, SortBy::Location);
+    assert_eq!(names(&entries), vec!["c", "b", "a"]);
+    sort_entries(&mut entries, SortBy::Usage);
+    assert_eq!(names(&entries), vec!["c", "a", "b"]);
+    sort_entries(&mut entries, SortBy::Recent);
+    assert_eq!(names(&entries), vec!["c", "b", "a"]);
+    sort_entries(&mut entries, SortBy::Name);
+    assert_eq!(names(&entries), vec!["a", "b", "c"]);
+
+    entries[0].tags = vec!["work".to_string(), "infra".to_string()];
+    entries[1].tags = vec!["work".to_string()];
+    let groups: Vec<(Option<String>, Vec<String>)> = by_tag(entries)
+        .into_iter()
+        .map(|(tag, entries)| (tag, names(&entries)))
+        .collect();
+    assert_eq!(
+        groups,
+        vec![
+            (Some("infra".to_string()), vec!["a".to_string()]),
+            (
+                Some("work".to_string()),
+                vec!["a".to_string(), "b".to_string()]
+            ),
+            (None, vec!["c".to_string()]),
+        ]
+    );
+}
+
+#[test]
+fn test_parse_list_options() {
+    assert_eq!(
+        Request::parse_from(["hp", "__bhop_list__", "--tree", "--sort=usage", "*hop*"]),
+        Request::Search(
+            Some("*hop*".to_string()),
+            ListOptions {
+                view: ListView::Tree,
+                sort: Some(SortBy::Usage),
+                ..Default::default()
+            }
+        )
+    );
+    assert_eq!(
+        Request::parse_from(["hp", "__bhop_list__", "--by-tag", "--sort", "recent"]),
+        Request::Search(
+            None,
+            ListOptions {
+                view: ListView::ByTag,
+                sort: Some(SortBy::Recent),
+                ..Default::default()
+            }
+        )
+    );
+    assert_eq!(
+        Request::parse_from(["hp", "__bhop_list__", "--fuzzy", "hp"]),
+        Request::Search(
+            Some("hp".to_string()),
+            ListOptions {
+                mode: SearchMode::Fuzzy,
+                ..Default::default()
+            }
+        )
+    );
+    assert!(matches!(
+        Request::parse_from(["hp", "__bhop_list__", "--regex", "--fuzzy"]),
+        Request::Notify(_)
+    ));
+    assert!(matches!(
+        Request::parse_from(["hp", "__bhop_list__", "--tree", "--by-tag"]),
+        Request::Notify(_)
+    ));
+    assert!(matches!(
+        Request::parse_from(["hp", "__bhop_list__", "--sort=size"]),
+        Request::Notify(_)
+    ));
+}